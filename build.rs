@@ -0,0 +1,20 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // The gRPC server's messages and service, from the same proto/snake.proto clients use.
+    // protox reads it without protoc.
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/snake.proto");
+
+        let files = protox::compile(["proto/snake.proto"], ["proto"]).expect("proto/snake.proto");
+
+        tonic_build::configure()
+            .build_client(false)
+            .compile_fds(files)
+            .expect("generating the gRPC server");
+    }
+
+    #[cfg(feature = "ffi")]
+    uniffi::generate_scaffolding("uniffi/snake.udl").expect("uniffi/snake.udl");
+}
@@ -0,0 +1,792 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::order::StartOrder;
+use crate::symmetry;
+use crate::tiles::TileRegistry;
+use crate::{
+    search_starts, solve_from, solve_within, trail, Board, Constraints, Dir, Outcome, Partial,
+    Position, SearchStats, Solution, StartStats, Tile,
+};
+
+/// Whether the board can be solved from one starting cherry.
+#[derive(Debug, Clone)]
+pub enum Viability {
+    Solvable(Solution),
+    Unsolvable,
+    /// The search ran out of time.
+    Unknown,
+}
+
+/// Try every starting cherry instead of stopping at the first that works, giving each one
+/// up to `limit`. A start that mirrors one already tried isn't searched again: it gets the
+/// mirror image of that start's answer.
+pub fn viable_starts(
+    board: &Board,
+    limit: Duration,
+    tiles: &TileRegistry,
+) -> Vec<(Position, Viability)> {
+    let symmetries = symmetry::pruning(board);
+    let mut tried: HashMap<Position, Viability> = HashMap::new();
+
+    board
+        .starting_positions()
+        .map(|p| {
+            let (first, s) = symmetry::representative(p, &symmetries, board);
+
+            let viability = tried.entry(first).or_insert_with(|| {
+                let outcome = solve_from(board, first, None, Some(Instant::now() + limit), tiles);
+
+                match outcome.solution {
+                    Some(solution) => Viability::Solvable(solution),
+                    None if outcome.timed_out => Viability::Unknown,
+                    None => Viability::Unsolvable,
+                }
+            });
+
+            let viability = match (viability, s) {
+                (Viability::Solvable(solution), Some(s)) => {
+                    Viability::Solvable(solution.transformed(s.inverse(), board))
+                }
+                (viability, _) => viability.clone(),
+            };
+
+            (p, viability)
+        })
+        .collect()
+}
+
+/// The board with each starting cherry marked `o` if the board can be solved from it, `x`
+/// if it can't and `?` if that's unknown.
+pub fn start_overlay(board: &Board, starts: &[(Position, Viability)]) -> String {
+    board
+        .tiles
+        .iter()
+        .enumerate()
+        .map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(x, &tile)| {
+                    let start = starts.iter().find(|(p, _)| *p == (x, y));
+
+                    match (tile, start) {
+                        (_, Some((_, Viability::Solvable(_)))) => 'o',
+                        (_, Some((_, Viability::Unsolvable))) => 'x',
+                        (_, Some((_, Viability::Unknown))) => '?',
+                        (Tile::Rock, None) => 'r',
+                        (_, None) => '.',
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The shortest solution from any start. Each start is searched breadth-first only as deep
+/// as a solution from it could still beat the best so far, so `stats.starts` doubles as a
+/// proof of optimality: see [`no_solution_within`].
+pub fn shortest(board: &Board, deadline: Option<Instant>, tiles: &TileRegistry) -> Outcome {
+    improving(board, deadline, tiles).finish()
+}
+
+/// The search behind [`shortest`], yielding each solution shorter than the ones before it
+/// as soon as it's found, so that callers can show something while it carries on.
+pub fn improving<'a>(
+    board: &'a Board,
+    deadline: Option<Instant>,
+    tiles: &'a TileRegistry,
+) -> Improving<'a, impl Iterator<Item = Position> + 'a> {
+    Improving {
+        board,
+        deadline,
+        tiles,
+        starts: board.starts(),
+        started: Instant::now(),
+        outcome: Outcome {
+            solution: None,
+            partial: None,
+            states: 0,
+            timed_out: false,
+            stats: SearchStats::default(),
+        },
+    }
+}
+
+pub struct Improving<'a, S> {
+    board: &'a Board,
+    deadline: Option<Instant>,
+    tiles: &'a TileRegistry,
+    starts: S,
+    started: Instant,
+    /// Everything found so far, with the best solution as `solution`.
+    outcome: Outcome,
+}
+
+impl<S: Iterator<Item = Position>> Improving<'_, S> {
+    /// Search the remaining starts and return the shortest solution, with the stats of the
+    /// whole search.
+    pub fn finish(mut self) -> Outcome {
+        self.by_ref().for_each(drop);
+
+        let mut outcome = self.outcome;
+
+        outcome.stats.depth = outcome.solution.as_ref().map(|s| s.moves.len());
+        outcome.stats.elapsed = self.started.elapsed();
+
+        if outcome.solution.is_some() {
+            outcome.partial = None;
+        }
+
+        outcome
+    }
+}
+
+impl<S: Iterator<Item = Position>> Iterator for Improving<'_, S> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        if self.outcome.timed_out {
+            return None;
+        }
+
+        for p in self.starts.by_ref() {
+            let best = &mut self.outcome;
+            let max_depth = best
+                .solution
+                .as_ref()
+                .map(|s| s.moves.len().saturating_sub(1));
+            let outcome = solve_from(self.board, p, max_depth, self.deadline, self.tiles);
+
+            if let Some(p) = outcome.partial {
+                if best
+                    .partial
+                    .as_ref()
+                    .is_none_or(|q| p.cherries_left < q.cherries_left)
+                {
+                    best.partial = Some(p);
+                }
+            }
+
+            best.states += outcome.states;
+            best.stats.starts.extend(outcome.stats.starts);
+            best.stats.duplicates += outcome.stats.duplicates;
+            best.stats.stranded += outcome.stats.stranded;
+            best.stats.peak_memory = best.stats.peak_memory.max(outcome.stats.peak_memory);
+            best.stats.peak_queue = best.stats.peak_queue.max(outcome.stats.peak_queue);
+
+            if outcome.timed_out {
+                best.timed_out = true;
+                return None;
+            }
+
+            if let Some(solution) = outcome.solution {
+                best.solution = Some(solution.clone());
+                return Some(solution);
+            }
+        }
+
+        None
+    }
+}
+
+/// The most moves a search from one start checked completely without finding a solution:
+/// every layer before the one a solution was found in, or every layer expanded otherwise.
+/// `None` if not even the start itself was ruled out.
+pub fn no_solution_within(start: &StartStats) -> Option<usize> {
+    if start.solved || start.timed_out {
+        start.depth.checked_sub(1)
+    } else {
+        Some(start.depth)
+    }
+}
+
+/// A longest solution from any start, found by a depth-first search that remembers the
+/// longest finish from every state it has explored and never goes back to a state on the
+/// way to it. Where every move eats a cherry, as with only cherries and rocks by the
+/// default rules, no state can come round again and this is the longest there is. Where
+/// one can, as on empty cells, with pass-through or decay rules, or with custom tiles, a
+/// finish remembered while some states were off limits gets reused where they aren't, so
+/// it may miss longer solutions. Cut short by `deadline`, the longest solution found so far
+/// comes back as the partial.
+pub fn longest(board: &Board, deadline: Option<Instant>, tiles: &TileRegistry) -> Outcome {
+    let started = Instant::now();
+
+    let mut search = Longest {
+        tiles,
+        deadline,
+        memo: HashMap::new(),
+        path: HashSet::new(),
+        states: 0,
+        timed_out: false,
+    };
+
+    let mut best: Option<(usize, Position)> = None;
+
+    // A mirrored start's longest solution is as long as its mirror image's
+    for p in search_starts(board, Constraints::default(), StartOrder::default(), tiles) {
+        let Some(n) = search.finish(&board.place_snake(p)) else {
+            continue;
+        };
+
+        if best.is_none_or(|(m, _)| n > m) {
+            best = Some((n, p));
+        }
+    }
+
+    let solution = best.map(|(_, start)| {
+        let mut b = board.place_snake(start);
+        let mut moves = Vec::new();
+
+        while let Some(&Some((_, Some(dir)))) = search.memo.get(&b) {
+            moves.push(dir);
+            b = b
+                .move_snake(dir, tiles)
+                .expect("Remembered moves are legal");
+        }
+
+        Solution { start, moves }
+    });
+
+    let (solution, partial) = match search.timed_out {
+        false => (solution, None),
+        true => (None, solution.map(|s| partial(board, s, tiles))),
+    };
+
+    Outcome {
+        stats: SearchStats {
+            depth: solution.as_ref().map(|s| s.moves.len()),
+            elapsed: started.elapsed(),
+            ..SearchStats::default()
+        },
+        solution,
+        partial,
+        states: search.states,
+        timed_out: search.timed_out,
+    }
+}
+
+/// `solution` as a partial, with the cherries it leaves.
+fn partial(board: &Board, solution: Solution, tiles: &TileRegistry) -> Partial {
+    let cherries_left = board
+        .place_snake(solution.start)
+        .apply_moves(&solution.moves, tiles)
+        .map_or(board.cherry_count(), |b| b.cherry_count());
+
+    Partial {
+        solution,
+        cherries_left,
+    }
+}
+
+struct Longest<'a> {
+    tiles: &'a TileRegistry,
+    deadline: Option<Instant>,
+    /// For every explored state, the most moves left to a solution and the first of them,
+    /// or `None` if there's no solution from it.
+    memo: HashMap<Board, Option<(usize, Option<Dir>)>>,
+    /// The states on the way to the current one, which mustn't be revisited.
+    path: HashSet<Board>,
+    states: usize,
+    timed_out: bool,
+}
+
+impl Longest<'_> {
+    fn finish(&mut self, board: &Board) -> Option<usize> {
+        if let Some(known) = self.memo.get(board) {
+            return known.map(|(n, _)| n);
+        }
+
+        if self.timed_out {
+            return None;
+        }
+
+        self.states += 1;
+
+        if self.states.is_multiple_of(1024) && self.deadline.is_some_and(|d| Instant::now() >= d) {
+            self.timed_out = true;
+            return None;
+        }
+
+        let best = if self.tiles.solved(board) {
+            Some((0, None))
+        } else {
+            self.path.insert(board.clone());
+
+            let best = board
+                .moves(self.tiles)
+                .filter(|(_, m)| m != board && !self.path.contains(m))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .filter_map(|(dir, m)| Some((self.finish(&m)? + 1, Some(dir))))
+                .max_by_key(|&(n, _)| n);
+
+            self.path.remove(board);
+
+            best
+        };
+
+        // A search cut short proves nothing, but a finish it found is still one to build
+        // the partial from
+        if !self.timed_out || best.is_some() {
+            self.memo.insert(board.clone(), best);
+        }
+
+        best.map(|(n, _)| n)
+    }
+}
+
+/// The smallest sets of at most `max_removals` rocks that would make an unsolvable board
+/// solvable if they were cherries instead, each with the solution it allows. Every set is
+/// the same size; an empty list means no such set was found. Each attempt gets `limit`.
+pub fn blocking_rocks(
+    board: &Board,
+    max_removals: usize,
+    limit: Duration,
+    tiles: &TileRegistry,
+) -> Vec<(Vec<Position>, Solution)> {
+    let rocks: Vec<Position> = board
+        .tiles
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, &t)| t == Tile::Rock)
+                .map(move |(x, _)| (x, y))
+        })
+        .collect();
+
+    for size in 1..=max_removals {
+        let found: Vec<_> = combinations(rocks.len(), size)
+            .into_iter()
+            .filter_map(|set| {
+                let removed: Vec<Position> = set.iter().map(|&i| rocks[i]).collect();
+                let mut candidate = board.clone();
+
+                for &p in &removed {
+                    candidate.set(p, Tile::Cherry);
+                }
+
+                solve_within(&candidate, Some(Instant::now() + limit), tiles)
+                    .solution
+                    .map(|solution| (removed, solution))
+            })
+            .collect();
+
+        if !found.is_empty() {
+            return found;
+        }
+    }
+
+    Vec::new()
+}
+
+/// Every way of choosing `k` of `0..n`, in lexicographic order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+
+    (0..n)
+        .flat_map(|first| {
+            combinations(n - first - 1, k - 1)
+                .into_iter()
+                .map(move |rest| {
+                    std::iter::once(first)
+                        .chain(rest.into_iter().map(|i| i + first + 1))
+                        .collect()
+                })
+        })
+        .collect()
+}
+
+/// What would have happened instead of one move of a solution.
+#[derive(Debug, Clone)]
+pub enum Alternative {
+    /// The board could still have been solved.
+    AlsoSolves,
+    /// The board could no longer be solved. Holds the cherries the best continuation
+    /// still leaves uneaten.
+    Strands(Vec<Position>),
+    /// The search ran out of time.
+    Unknown,
+}
+
+/// For each move of `solution`, every other direction that would have moved the snake and
+/// what would have come of it, found by re-solving from there with up to `limit` each.
+pub fn explain(
+    board: &Board,
+    solution: &Solution,
+    limit: Duration,
+    tiles: &TileRegistry,
+) -> Vec<Vec<(Dir, Alternative)>> {
+    let mut b = board.place_snake(solution.start);
+    let mut explanations = Vec::new();
+
+    for &dir in &solution.moves {
+        let alternatives = board
+            .rules()
+            .dirs()
+            .iter()
+            .copied()
+            .filter(|&d| d != dir)
+            .filter_map(|d| {
+                let other = b.clone().move_snake(d, tiles).filter(|m| *m != b)?;
+                let head = other.get_snake_head()?;
+                let outcome = solve_from(&other, head, None, Some(Instant::now() + limit), tiles);
+
+                let alternative = match outcome {
+                    Outcome {
+                        solution: Some(_), ..
+                    } => Alternative::AlsoSolves,
+                    Outcome {
+                        timed_out: true, ..
+                    } => Alternative::Unknown,
+                    Outcome { partial, .. } => {
+                        let mut end = other.place_snake(head);
+
+                        for &m in partial.iter().flat_map(|p| &p.solution.moves) {
+                            end = end
+                                .move_snake(m, tiles)
+                                .expect("Partial lines only make legal moves");
+                        }
+
+                        Alternative::Strands(end.starting_positions().collect())
+                    }
+                };
+
+                Some((d, alternative))
+            })
+            .collect();
+
+        explanations.push(alternatives);
+        b = b
+            .move_snake(dir, tiles)
+            .expect("Solutions only make legal moves");
+    }
+
+    explanations
+}
+
+/// A short reason a move was played, for showing next to it.
+pub fn describe(alternatives: &[(Dir, Alternative)]) -> String {
+    if alternatives.is_empty() {
+        return "the only move that goes anywhere".to_string();
+    }
+
+    let also: Vec<String> = alternatives
+        .iter()
+        .filter(|(_, a)| matches!(a, Alternative::AlsoSolves))
+        .map(|(d, _)| format!("{d:?}"))
+        .collect();
+
+    if !also.is_empty() {
+        return format!("{} would also work", also.join(" or "));
+    }
+
+    alternatives
+        .iter()
+        .map(|(d, a)| match a {
+            Alternative::Strands(cells) => match cells.first() {
+                Some((x, y)) if cells.len() == 1 => format!("{d:?} strands ({x}, {y})"),
+                Some((x, y)) => format!("{d:?} strands ({x}, {y}) and {} more", cells.len() - 1),
+                None => format!("{d:?} leads nowhere"),
+            },
+            _ => format!("{d:?} is unclear"),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// How much of their paths two solutions share: the steps between neighbouring cells that
+/// both take (in either direction), as a share of the steps either takes.
+pub fn path_overlap(board: &Board, a: &Solution, b: &Solution, tiles: &TileRegistry) -> f64 {
+    let steps = |solution| -> HashSet<(Position, Position)> {
+        trail(board, solution, tiles)
+            .windows(2)
+            .map(|w| (w[0].min(w[1]), w[0].max(w[1])))
+            .collect()
+    };
+
+    let (a, b) = (steps(a), steps(b));
+    let union = a.union(&b).count();
+
+    if union == 0 {
+        return 1.0;
+    }
+
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// How two solutions of the same board compare, such as a player's and the solver's.
+#[derive(Debug, Clone)]
+pub struct SolutionDiff {
+    /// Moves the two make alike from the start before they part. Zero if they start on
+    /// different cherries.
+    pub shared: usize,
+    /// Where the head is when they part. `None` if they start on different cherries or
+    /// don't part at all.
+    pub fork: Option<Position>,
+    /// Each solution's cells, as [`trail`] gives them.
+    pub trails: (Vec<Position>, Vec<Position>),
+    /// See [`path_overlap`].
+    pub overlap: f64,
+}
+
+pub fn diff(board: &Board, a: &Solution, b: &Solution, tiles: &TileRegistry) -> SolutionDiff {
+    let shared = match a.start == b.start {
+        true => a
+            .moves
+            .iter()
+            .zip(&b.moves)
+            .take_while(|(x, y)| x == y)
+            .count(),
+        false => 0,
+    };
+
+    let fork = (a.start == b.start && a.moves != b.moves).then(|| {
+        let prefix = Solution {
+            start: a.start,
+            moves: a.moves[..shared].to_vec(),
+        };
+
+        *trail(board, &prefix, tiles)
+            .last()
+            .expect("A trail always has its start")
+    });
+
+    SolutionDiff {
+        shared,
+        fork,
+        trails: (trail(board, a, tiles), trail(board, b, tiles)),
+        overlap: path_overlap(board, a, b, tiles),
+    }
+}
+
+/// For every cell, the fewest moves that could take the snake's head from `head` onto it,
+/// or `None` if no moves can. `None` everywhere if `head` is off the board.
+///
+/// Every move is played on `board` as it stands, but may end on any cell it crosses, since
+/// the body left by earlier moves could stop it there. So no cell the snake can really
+/// reach is missing, and none is reached in fewer moves than this says, which makes the map
+/// safe to prune with.
+pub fn reachability(
+    board: &Board,
+    head: Position,
+    tiles: &TileRegistry,
+) -> Vec<Vec<Option<usize>>> {
+    let mut distances: Vec<Vec<Option<usize>>> = board
+        .tiles
+        .iter()
+        .map(|row| vec![None; row.len()])
+        .collect();
+
+    let (x, y) = head;
+
+    let Some(cell) = distances.get_mut(y).and_then(|row| row.get_mut(x)) else {
+        return distances;
+    };
+
+    *cell = Some(0);
+
+    // Without the snake's head, so that it can be put down anywhere
+    let mut base = board.clone();
+
+    if let Some(h) = board.get_snake_head() {
+        base.set(h, Tile::SnakeBody);
+    }
+
+    let mut stops = HashSet::from([head]);
+    let mut queue = VecDeque::from([(head, 0)]);
+
+    while let Some((p, depth)) = queue.pop_front() {
+        for &dir in board.rules.dirs() {
+            let mut cells = Vec::new();
+
+            base.place_snake(p).slide(dir, tiles, Some(&mut cells));
+
+            for &(x, y) in &cells {
+                distances[y][x].get_or_insert(depth + 1);
+
+                if stops.insert((x, y)) {
+                    queue.push_back(((x, y), depth + 1));
+                }
+            }
+        }
+    }
+
+    distances
+}
+
+/// Static measures of how hard a board looks, computed without solving it.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    pub cherries: usize,
+    /// Cherries as a share of all cells.
+    pub open_ratio: f64,
+    /// Cells covered by an average first move from any start.
+    pub average_slide: f64,
+    /// Moves available from the average state one move in.
+    pub branching_factor: f64,
+    /// Roughly how many states a search would have to look at: a tree with
+    /// `branching_factor` children per state, as deep as the cherries take to eat at
+    /// `average_slide` cells a move, from every start.
+    pub state_space: f64,
+}
+
+pub fn metrics(board: &Board, tiles: &TileRegistry) -> Metrics {
+    let cherries = board.cherry_count();
+    let cells: usize = board.tiles.iter().map(Vec::len).sum();
+
+    let distance = |from: &Board, to: &Board| match (from.get_snake_head(), to.get_snake_head()) {
+        (Some((x1, y1)), Some((x2, y2))) => x1.abs_diff(x2) + y1.abs_diff(y2),
+        _ => 0,
+    };
+
+    let mut slides = Vec::new();
+    let mut branches = Vec::new();
+
+    for p in board.starting_positions() {
+        let start = board.place_snake(p);
+
+        for (_, next) in start.moves(tiles) {
+            let slide = distance(&start, &next);
+
+            if slide == 0 {
+                continue;
+            }
+
+            slides.push(slide);
+            branches.push(next.moves(tiles).filter(|(_, m)| *m != next).count());
+        }
+    }
+
+    let mean = |xs: &[usize]| match xs.len() {
+        0 => 0.0,
+        n => xs.iter().sum::<usize>() as f64 / n as f64,
+    };
+
+    let average_slide = mean(&slides);
+    let branching_factor = mean(&branches);
+
+    let depth = match average_slide {
+        s if s > 0.0 => (cherries.saturating_sub(1) as f64 / s).ceil() as i32,
+        _ => 0,
+    };
+    let tree: f64 = (0..=depth).map(|i| branching_factor.powi(i)).sum();
+
+    Metrics {
+        cherries,
+        open_ratio: match cells {
+            0 => 0.0,
+            n => cherries as f64 / n as f64,
+        },
+        average_slide,
+        branching_factor,
+        state_space: tree * cherries as f64,
+    }
+}
+
+/// Shorten `solution` with local rewrites, for solvers whose raw output wanders: moves
+/// that leave the board as it was are dropped, and every stretch of up to `window` moves is
+/// replaced by the shortest line between the same two states, if that's shorter. Both
+/// states have the same cherries eaten and the head in the same place, so the rest of the
+/// solution still works after the swap.
+pub fn optimize(
+    board: &Board,
+    solution: &Solution,
+    window: usize,
+    tiles: &TileRegistry,
+) -> Solution {
+    let mut states = vec![board.place_snake(solution.start)];
+    let mut moves = Vec::new();
+
+    for &dir in &solution.moves {
+        let last = states.last().expect("There is always a first state");
+
+        match last.clone().move_snake(dir, tiles) {
+            Some(next) if next != *last => {
+                states.push(next);
+                moves.push(dir);
+            }
+            _ => {}
+        }
+    }
+
+    let mut i = 0;
+
+    while i < moves.len() {
+        let far = (i + window).min(moves.len());
+
+        // The furthest state reachable in fewer moves gives the biggest saving
+        let shortcut = (i + 2..=far).rev().find_map(|j| {
+            Some((
+                j,
+                shortest_between(&states[i], &states[j], j - i - 1, tiles)?,
+            ))
+        });
+
+        match shortcut {
+            Some((j, (between, detour))) => {
+                moves.splice(i..j, detour);
+                states.splice(i + 1..j, between);
+            }
+            None => i += 1,
+        }
+    }
+
+    Solution {
+        start: solution.start,
+        moves,
+    }
+}
+
+/// The shortest line of at most `max` moves from `from` to `to`, as the states strictly
+/// between them and the moves.
+fn shortest_between(
+    from: &Board,
+    to: &Board,
+    max: usize,
+    tiles: &TileRegistry,
+) -> Option<(Vec<Board>, Vec<Dir>)> {
+    let mut parents: HashMap<Board, Option<(Board, Dir)>> = HashMap::from([(from.clone(), None)]);
+    let mut layer = vec![from.clone()];
+
+    for _ in 0..max {
+        let mut next = Vec::new();
+
+        for b in &layer {
+            for (dir, m) in b.moves(tiles) {
+                if parents.contains_key(&m) {
+                    continue;
+                }
+
+                parents.insert(m.clone(), Some((b.clone(), dir)));
+
+                if m == *to {
+                    let mut between = Vec::new();
+                    let mut moves = vec![dir];
+                    let mut at = b.clone();
+
+                    while let Some(Some((parent, d))) = parents.get(&at) {
+                        between.push(at.clone());
+                        moves.push(*d);
+                        at = parent.clone();
+                    }
+
+                    between.reverse();
+                    moves.reverse();
+
+                    return Some((between, moves));
+                }
+
+                next.push(m);
+            }
+        }
+
+        layer = next;
+    }
+
+    None
+}
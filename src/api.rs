@@ -0,0 +1,424 @@
+use std::time::{Duration, Instant};
+
+use crate::analysis::{self, Viability};
+use crate::generate::{board_text, check_request, generate as generate_board};
+use crate::json::Json;
+use crate::objective;
+use crate::order::StartOrder;
+use crate::rng::Rng;
+use crate::session::Session;
+use crate::tiles::{Declared, TileRegistry};
+use crate::{
+    check, solve_traced, trail, Board, Constraints, Dir, Invalid, Outcome, Position, SearchStats,
+    Solution,
+};
+
+/// Custom tiles declared by the request as `"tiles": {"glyph": {"enterable": bool,
+/// "stops_slide": bool}}`.
+fn tiles(request: &Json) -> Result<TileRegistry, String> {
+    let mut registry = TileRegistry::default();
+
+    let Some(declared) = request.get("tiles") else {
+        return Ok(registry);
+    };
+
+    let declared = declared.as_object().ok_or("\"tiles\" must be an object")?;
+
+    for (glyph, flags) in declared {
+        let mut chars = glyph.chars();
+        let (Some(glyph), None) = (chars.next(), chars.next()) else {
+            return Err(format!("Tile glyph {glyph:?} must be a single character"));
+        };
+
+        let flag = |key| flags.get(key).and_then(Json::as_bool).unwrap_or(false);
+
+        registry
+            .register(
+                glyph,
+                Declared {
+                    enterable: flag("enterable"),
+                    stops_slide: flag("stops_slide"),
+                },
+            )
+            .ok_or("Too many custom tiles")?;
+    }
+
+    Ok(registry)
+}
+
+fn board(request: &Json, tiles: &TileRegistry) -> Result<Board, String> {
+    let text = request
+        .get("board")
+        .and_then(Json::as_str)
+        .ok_or("Missing \"board\"")?;
+
+    let board = Board::parse(text, tiles).map_err(|e| e.to_string())?;
+
+    board.validate().map_err(|e| e.to_string())?;
+
+    Ok(board)
+}
+
+fn number(request: &Json, key: &str) -> Result<Option<u64>, String> {
+    request
+        .get(key)
+        .map(|v| {
+            v.as_u64()
+                .ok_or_else(|| format!("\"{key}\" must be a non-negative integer"))
+        })
+        .transpose()
+}
+
+fn position(request: &Json, key: &str) -> Result<Position, String> {
+    let invalid = || format!("\"{key}\" must be [x, y]");
+
+    match request.get(key).and_then(Json::as_array) {
+        Some([x, y]) => match (x.as_u64(), y.as_u64()) {
+            (Some(x), Some(y)) => Ok((x as usize, y as usize)),
+            _ => Err(invalid()),
+        },
+        _ => Err(invalid()),
+    }
+}
+
+pub fn position_json((x, y): Position) -> Json {
+    Json::Arr(vec![x.into(), y.into()])
+}
+
+pub fn moves_json(moves: &[Dir]) -> Json {
+    Json::Arr(moves.iter().map(|d| Json::Str(format!("{d:?}"))).collect())
+}
+
+pub fn trail_json(board: &Board, solution: &Solution, tiles: &TileRegistry) -> Json {
+    Json::Arr(
+        trail(board, solution, tiles)
+            .into_iter()
+            .map(position_json)
+            .collect(),
+    )
+}
+
+/// What a solve request asks for: the board, its custom tiles, and how long to search,
+/// which is `time_limit` or the request's own `time_limit_ms`, whichever is shorter.
+pub struct SolveRequest {
+    pub board: Board,
+    pub tiles: TileRegistry,
+    pub limit: Duration,
+    /// Find the shortest solution from any start, with a certificate, instead of the first
+    /// start that works.
+    pub shortest: bool,
+    /// Stop after expanding this many states and answer with the best line so far. Only
+    /// applies when not looking for the shortest solution.
+    pub max_states: Option<usize>,
+    /// Drop any start whose search grows past this many bytes, as with `max_states` only
+    /// when not looking for the shortest solution.
+    pub max_memory: Option<usize>,
+    /// See [`StartOrder`]'s `FromStr` for the names. The shortest solution is the same in
+    /// any order, so this too only applies when not looking for it.
+    pub order: StartOrder,
+}
+
+impl SolveRequest {
+    pub fn run(&self, started: Instant) -> Outcome {
+        let deadline = Some(started + self.limit);
+
+        if self.shortest {
+            analysis::shortest(&self.board, deadline, &self.tiles)
+        } else {
+            let constraints = Constraints {
+                max_states: self.max_states,
+                max_memory: self.max_memory,
+                ..Constraints::default()
+            };
+
+            solve_traced(
+                &self.board,
+                constraints,
+                self.order,
+                deadline,
+                &self.tiles,
+                &mut (),
+                None,
+            )
+        }
+    }
+
+    /// [`solve_result`], plus the certificate for shortest solves and the seed of a random
+    /// start order.
+    pub fn result(&self, outcome: &Outcome, elapsed: Duration) -> Json {
+        let mut result = solve_result(&self.board, &self.tiles, outcome, elapsed);
+
+        if let Json::Obj(fields) = &mut result {
+            if self.shortest {
+                fields.push(("certificate".to_string(), certificate_json(outcome)));
+            }
+
+            if let Some(seed) = self.order.seed() {
+                fields.push(("seed".to_string(), seed.into()));
+            }
+        }
+
+        result
+    }
+}
+
+pub fn solve_request(request: &Json, time_limit: Duration) -> Result<SolveRequest, String> {
+    let tiles = tiles(request)?;
+    let board = board(request, &tiles)?;
+
+    let limit = number(request, "time_limit_ms")?
+        .map(Duration::from_millis)
+        .map_or(time_limit, |d| d.min(time_limit));
+
+    let order = match request.get("start_order") {
+        Some(order) => order
+            .as_str()
+            .ok_or("\"start_order\" must be a string")?
+            .parse()?,
+        None => StartOrder::default(),
+    };
+
+    Ok(SolveRequest {
+        board,
+        tiles,
+        limit,
+        shortest: request.get("shortest").and_then(Json::as_bool) == Some(true),
+        max_states: number(request, "max_states")?.map(|n| n as usize),
+        max_memory: number(request, "max_memory_bytes")?.map(|n| n as usize),
+        order,
+    })
+}
+
+pub fn solve_result(
+    board: &Board,
+    tiles: &TileRegistry,
+    outcome: &Outcome,
+    elapsed: Duration,
+) -> Json {
+    // Callers with a budget always get something back, so say plainly which kind it is
+    let answer = match (&outcome.solution, &outcome.partial) {
+        (Some(_), _) => "solution",
+        (None, Some(_)) => "partial",
+        (None, None) => "none",
+    };
+
+    let mut fields = vec![
+        ("id", board.id().to_string().into()),
+        ("solved", Json::Bool(outcome.solution.is_some())),
+        ("answer", answer.into()),
+    ];
+
+    if let Some(solution) = &outcome.solution {
+        fields.push(("start", position_json(solution.start)));
+        fields.push(("move_count", solution.moves.len().into()));
+        fields.push(("moves", moves_json(&solution.moves)));
+        fields.push(("cells", objective::cells(board, solution, tiles).into()));
+        fields.push(("trail", trail_json(board, solution, tiles)));
+    }
+
+    if let Some(partial) = &outcome.partial {
+        fields.push((
+            "partial",
+            Json::obj([
+                ("start", position_json(partial.solution.start)),
+                ("moves", moves_json(&partial.solution.moves)),
+                ("cherries_left", partial.cherries_left.into()),
+            ]),
+        ));
+    }
+
+    fields.push(("timed_out", outcome.timed_out.into()));
+    fields.push(("states", outcome.states.into()));
+    fields.push(("elapsed_ms", (elapsed.as_secs_f64() * 1000.0).into()));
+    fields.push(("stats", stats_json(&outcome.stats)));
+
+    Json::obj(fields)
+}
+
+/// How the search went, with one entry per start tried.
+fn stats_json(stats: &SearchStats) -> Json {
+    let starts = stats
+        .starts
+        .iter()
+        .map(|s| {
+            Json::obj([
+                ("start", position_json(s.start)),
+                ("expanded", s.expanded.into()),
+                ("solved", s.solved.into()),
+                ("timed_out", s.timed_out.into()),
+                ("out_of_memory", s.out_of_memory.into()),
+                ("depth", s.depth.into()),
+                ("bound", s.bound.into()),
+                ("elapsed_ms", (s.elapsed.as_secs_f64() * 1000.0).into()),
+            ])
+        })
+        .collect();
+
+    Json::obj([
+        ("depth", stats.depth.into()),
+        ("duplicates", stats.duplicates.into()),
+        ("stranded", stats.stranded.into()),
+        ("peak_memory_bytes", stats.peak_memory.into()),
+        ("peak_queue", stats.peak_queue.into()),
+        ("branching_factor", stats.branching_factor().into()),
+        ("starts", Json::Arr(starts)),
+    ])
+}
+
+/// Proof that a `shortest` solve found the shortest solution: for every start, the number
+/// of moves within which it was shown to have no solution (or no shorter one).
+fn certificate_json(outcome: &Outcome) -> Json {
+    let starts = outcome
+        .stats
+        .starts
+        .iter()
+        .map(|s| {
+            let result = match (s.solved, s.timed_out) {
+                (true, _) => "solved",
+                (false, true) if s.out_of_memory => "out_of_memory",
+                (false, true) => "timed_out",
+                (false, false) => "exhausted",
+            };
+
+            Json::obj([
+                ("start", position_json(s.start)),
+                ("result", result.into()),
+                ("no_solution_within", analysis::no_solution_within(s).into()),
+                ("states", s.expanded.into()),
+            ])
+        })
+        .collect();
+
+    Json::obj([
+        (
+            "optimal_moves",
+            outcome.solution.as_ref().map(|s| s.moves.len()).into(),
+        ),
+        ("complete", (!outcome.timed_out).into()),
+        ("starts", Json::Arr(starts)),
+    ])
+}
+
+pub fn solve(request: &Json, time_limit: Duration) -> Result<Json, String> {
+    let request = solve_request(request, time_limit)?;
+    let started = Instant::now();
+    let outcome = request.run(started);
+
+    Ok(request.result(&outcome, started.elapsed()))
+}
+
+/// Replay a proposed solution against `board`.
+pub fn validate(request: &Json) -> Result<Json, String> {
+    let tiles = tiles(request)?;
+    let board = board(request, &tiles)?;
+
+    let start = position(request, "start")?;
+
+    let moves = request
+        .get("moves")
+        .and_then(Json::as_array)
+        .unwrap_or_default()
+        .iter()
+        .map(|m| {
+            m.as_str()
+                .ok_or("Moves must be strings".to_string())?
+                .parse()
+        })
+        .collect::<Result<Vec<Dir>, String>>()?;
+
+    let body = match check(&board, start, &moves, &tiles) {
+        Ok(()) => Json::obj([("valid", Json::Bool(true))]),
+        Err(invalid) => {
+            let mut fields = vec![("valid", Json::Bool(false))];
+
+            match invalid {
+                Invalid::BadStart(p) => {
+                    fields.push((
+                        "reason",
+                        "Start is not on a cherry or the snake's head".into(),
+                    ));
+                    fields.push(("start", position_json(p)));
+                }
+                Invalid::Stuck(i, dir) => {
+                    fields.push(("reason", format!("Snake can't move {dir:?}").into()));
+                    fields.push(("move_index", i.into()));
+                }
+                Invalid::Incomplete(n) => {
+                    fields.push(("reason", "Cherries remain".into()));
+                    fields.push(("cherries_left", n.into()));
+                }
+            }
+
+            Json::obj(fields)
+        }
+    };
+
+    Ok(body)
+}
+
+/// Start an editing [`Session`] on the request's board and say whether it's solvable.
+pub fn open_session(request: &Json, time_limit: Duration) -> Result<(Session, Json), String> {
+    let tiles = tiles(request)?;
+    let board = board(request, &tiles)?;
+    let mut session = Session::new(board, tiles);
+    let result = session_result(&mut session, time_limit);
+
+    Ok((session, result))
+}
+
+/// Toggle the tile `at` [x, y] between rock and cherry and say whether the board is still
+/// solvable.
+pub fn toggle(session: &mut Session, request: &Json, time_limit: Duration) -> Result<Json, String> {
+    session.toggle(position(request, "at")?)?;
+
+    Ok(session_result(session, time_limit))
+}
+
+fn session_result(session: &mut Session, time_limit: Duration) -> Json {
+    let started = Instant::now();
+    let viability = session.solvable(time_limit);
+    let elapsed_ms = (started.elapsed().as_secs_f64() * 1000.0).into();
+
+    match viability {
+        Viability::Solvable(solution) => Json::obj([
+            ("solvable", Json::Bool(true)),
+            ("start", position_json(solution.start)),
+            ("moves", moves_json(&solution.moves)),
+            ("elapsed_ms", elapsed_ms),
+        ]),
+        Viability::Unsolvable => {
+            Json::obj([("solvable", Json::Bool(false)), ("elapsed_ms", elapsed_ms)])
+        }
+        Viability::Unknown => Json::obj([
+            ("solvable", Json::Null),
+            ("timed_out", Json::Bool(true)),
+            ("elapsed_ms", elapsed_ms),
+        ]),
+    }
+}
+
+/// Generate a solvable board. Each candidate gets `time_limit` to prove itself solvable.
+pub fn generate(request: &Json, time_limit: Duration) -> Result<Json, String> {
+    let width = number(request, "width")?.unwrap_or(6) as usize;
+    let height = number(request, "height")?.unwrap_or(6) as usize;
+    let rocks = number(request, "rocks")?.map_or(width.saturating_mul(height) / 6, |n| n as usize);
+    let attempts = number(request, "attempts")?.unwrap_or(100) as usize;
+
+    check_request(width, height, rocks, attempts)?;
+
+    let mut rng = number(request, "seed")?.map_or_else(Rng::from_clock, Rng::new);
+
+    let limit = number(request, "time_limit_ms")?
+        .map(Duration::from_millis)
+        .map_or(time_limit, |d| d.min(time_limit));
+
+    let generated = generate_board(width, height, rocks, &mut rng, attempts, limit)
+        .ok_or_else(|| format!("No solvable board found in {attempts} attempts"))?;
+
+    Ok(Json::obj([
+        ("board", board_text(&generated.board).into()),
+        ("seed", rng.seed().into()),
+        ("start", position_json(generated.solution.start)),
+        ("moves", moves_json(&generated.solution.moves)),
+    ]))
+}
@@ -0,0 +1,187 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::order::StartOrder;
+use crate::tiles::TileRegistry;
+use crate::{search_starts, solution, Board, Constraints, Outcome, Partial, SearchStats};
+
+/// A lower bound on the moves left: a move can't eat more cherries than fit in a straight
+/// line across the board, so eating the rest takes at least that many lines. Never more
+/// than one less after any move, so the first solution taken off the heap is a shortest.
+pub(crate) fn heuristic(board: &Board, longest: usize) -> usize {
+    board.cherry_count().div_ceil(longest)
+}
+
+/// How [`solve_astar_with`] estimates the moves left from a board. The first solution it
+/// finds is only sure to be a shortest if the estimate never says more than it takes, and
+/// never drops by more than one in a move. Any `Fn(&Board) -> usize` is one.
+pub trait Heuristic {
+    fn estimate(&self, board: &Board) -> usize;
+}
+
+impl<F: Fn(&Board) -> usize> Heuristic for F {
+    fn estimate(&self, board: &Board) -> usize {
+        self(board)
+    }
+}
+
+/// The heuristics that come with the solver, which can be chosen by name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Builtin {
+    /// See [`heuristic`].
+    #[default]
+    Lines,
+    /// No estimate at all, which makes A* a breadth-first search of every start at once.
+    Zero,
+}
+
+impl FromStr for Builtin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lines" => Ok(Builtin::Lines),
+            "zero" => Ok(Builtin::Zero),
+            _ => Err(format!(
+                "Unknown A* heuristic {s:?}, expected lines or zero"
+            )),
+        }
+    }
+}
+
+impl Heuristic for Builtin {
+    fn estimate(&self, board: &Board) -> usize {
+        match self {
+            Builtin::Lines => heuristic(board, longest_line(board)),
+            Builtin::Zero => 0,
+        }
+    }
+}
+
+/// The most cells a single move can cross on `board`.
+pub(crate) fn longest_line(board: &Board) -> usize {
+    let height = board.tiles.len();
+    let width = board.tiles.iter().map(Vec::len).max().unwrap_or(0);
+
+    width.max(height).saturating_sub(1).max(1)
+}
+
+/// The shortest solution from any start, like [`crate::analysis::shortest`], but searching
+/// every start at once best-first, most promising state first, rather than each start
+/// breadth-first in turn. With [`crate::tiles::Hooks`], whose goal `heuristic` knows
+/// nothing of, it searches with [`Builtin::Zero`] instead.
+pub fn solve_astar(board: &Board, deadline: Option<Instant>, tiles: &TileRegistry) -> Outcome {
+    solve_astar_with(board, deadline, tiles, &Builtin::default())
+}
+
+/// [`solve_astar`], ranking states by `heuristic`'s estimate of the moves they have left.
+pub fn solve_astar_with(
+    board: &Board,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    heuristic: &dyn Heuristic,
+) -> Outcome {
+    let started = Instant::now();
+    let mut stats = SearchStats::default();
+    let heuristic = match tiles.hooks() {
+        Some(_) => &Builtin::Zero,
+        None => heuristic,
+    };
+
+    let mut parents: HashMap<Board, Option<Board>> = HashMap::new();
+    let mut costs: HashMap<Board, usize> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+    // Boards by when they were queued, which the heap refers to them by. Ties go to the
+    // state queued first, which keeps runs repeatable
+    let mut queued: Vec<Option<Board>> = Vec::new();
+
+    for p in search_starts(board, Constraints::default(), StartOrder::default(), tiles) {
+        let start = board.place_snake(p);
+
+        parents.insert(start.clone(), None);
+        costs.insert(start.clone(), 0);
+        heap.push(Reverse((heuristic.estimate(&start), 0, queued.len())));
+        queued.push(Some(start));
+    }
+
+    let mut states: usize = 0;
+    let mut timed_out = false;
+    let mut best: Option<(usize, Board)> = None;
+
+    while let Some(Reverse((_, cost, i))) = heap.pop() {
+        let Some(b) = queued[i].take() else {
+            continue;
+        };
+
+        // Queued again since by a cheaper path
+        if costs.get(&b).is_some_and(|&c| c < cost) {
+            continue;
+        }
+
+        states += 1;
+
+        if states.is_multiple_of(1024) && deadline.is_some_and(|d| Instant::now() >= d) {
+            timed_out = true;
+            break;
+        }
+
+        let cherries_left = b.cherry_count();
+
+        if let Some(head) = b.get_snake_head() {
+            *stats.heat.entry(head).or_default() += 1;
+        }
+
+        if best.as_ref().is_none_or(|(left, _)| cherries_left < *left) {
+            best = Some((cherries_left, b.clone()));
+        }
+
+        if tiles.solved(&b) {
+            let solution = solution(&b, &parents, tiles);
+
+            stats.depth = Some(solution.moves.len());
+            stats.elapsed = started.elapsed();
+
+            return Outcome {
+                solution: Some(solution),
+                partial: None,
+                states,
+                timed_out: false,
+                stats,
+            };
+        }
+
+        for (_, m) in b.moves(tiles) {
+            let cost = cost + 1;
+
+            if costs.get(&m).is_some_and(|&c| c <= cost) {
+                if m != b {
+                    stats.duplicates += 1;
+                }
+
+                continue;
+            }
+
+            parents.insert(m.clone(), Some(b.clone()));
+            costs.insert(m.clone(), cost);
+            heap.push(Reverse((cost + heuristic.estimate(&m), cost, queued.len())));
+            queued.push(Some(m));
+        }
+
+        stats.peak_queue = stats.peak_queue.max(heap.len());
+    }
+
+    stats.elapsed = started.elapsed();
+
+    Outcome {
+        solution: None,
+        partial: best.map(|(cherries_left, b)| Partial {
+            solution: solution(&b, &parents, tiles),
+            cherries_left,
+        }),
+        states,
+        timed_out,
+        stats,
+    }
+}
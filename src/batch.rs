@@ -0,0 +1,132 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::api;
+use crate::cache::Cache;
+use crate::json::Json;
+
+/// Solve every board file in `dir` on `jobs` threads, giving each board up to
+/// `time_limit`. Each result is written to `out` as one JSON line as soon as it's ready, so
+/// results arrive in the order boards finish rather than the order they're listed. Returns
+/// the consolidated report.
+///
+/// Boards with a result in `cache` aren't searched again: theirs is marked `"cached"`, with
+/// the states and time the search took when it ran.
+pub fn run(
+    dir: &Path,
+    jobs: usize,
+    time_limit: Duration,
+    cache: Option<&Cache>,
+    out: &mut impl Write,
+) -> io::Result<Json> {
+    let mut boards: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+
+    boards.sort();
+
+    let started = Instant::now();
+    let next = AtomicUsize::new(0);
+    let (sender, results) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            let sender = sender.clone();
+            let (boards, next) = (&boards, &next);
+
+            scope.spawn(move || {
+                while let Some(path) = boards.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if sender.send(solve(path, time_limit, cache)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        // Otherwise the loop below would wait for this sender too
+        drop(sender);
+
+        let mut all = Vec::with_capacity(boards.len());
+
+        for result in results {
+            writeln!(out, "{result}")?;
+            out.flush()?;
+            all.push(result);
+        }
+
+        Ok(report(all, started.elapsed()))
+    })
+}
+
+fn solve(path: &Path, time_limit: Duration, cache: Option<&Cache>) -> Json {
+    let name = path.display().to_string();
+
+    let result = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|board| api::solve_request(&Json::obj([("board", board.into())]), time_limit))
+        .map(|request| {
+            let started = Instant::now();
+            let cached = cache.and_then(|c| c.get(&request.board, request.order));
+            let hit = cached.is_some();
+            let outcome = cached.unwrap_or_else(|| request.run(started));
+
+            if let (Some(cache), false) = (cache, hit) {
+                // A cache that can't be written to only costs the next run time
+                let _ = cache.put(&request.board, request.order, &outcome);
+            }
+
+            let elapsed = match hit {
+                true => outcome.stats.elapsed,
+                false => started.elapsed(),
+            };
+
+            match request.result(&outcome, elapsed) {
+                Json::Obj(mut fields) if hit => {
+                    fields.push(("cached".to_string(), true.into()));
+
+                    Json::Obj(fields)
+                }
+                result => result,
+            }
+        });
+
+    match result {
+        Ok(Json::Obj(mut fields)) => {
+            fields.insert(0, ("board".to_string(), name.into()));
+
+            Json::Obj(fields)
+        }
+        Ok(other) => other,
+        Err(e) => Json::obj([("board", name.into()), ("error", e.into())]),
+    }
+}
+
+/// Totals across every result, with the results themselves sorted by board name.
+fn report(mut results: Vec<Json>, elapsed: Duration) -> Json {
+    let flag = |r: &Json, key| r.get(key).and_then(Json::as_bool) == Some(true);
+
+    results.sort_by_key(|r| r.get("board").and_then(Json::as_str).map(str::to_string));
+
+    let solved = results.iter().filter(|r| flag(r, "solved")).count();
+    let timed_out = results.iter().filter(|r| flag(r, "timed_out")).count();
+    let errors = results.iter().filter(|r| r.get("error").is_some()).count();
+
+    Json::obj([
+        ("boards", results.len().into()),
+        ("solved", solved.into()),
+        (
+            "unsolvable",
+            (results.len() - solved - timed_out - errors).into(),
+        ),
+        ("timed_out", timed_out.into()),
+        ("errors", errors.into()),
+        ("elapsed_ms", (elapsed.as_secs_f64() * 1000.0).into()),
+        ("results", Json::Arr(results)),
+    ])
+}
@@ -0,0 +1,245 @@
+use crate::rules::Movement;
+use crate::tiles::TileRegistry;
+use crate::{Board, Dir, Position, Space, Tile};
+
+/// Cells a [`Packed`] state can hold: 16 × 16, or any other board with as many cells.
+const MAX_CELLS: usize = 256;
+
+const WORDS: usize = MAX_CELLS / 64;
+
+/// A search state as the cells still open, cherries and empty floor, one bit per cell, and
+/// where the head is. That's all there is to a state on a board of only rocks, cherries
+/// and floor: rocks never move, and every other cell is under the body. A few dozen bytes,
+/// where a [`Board`] is a row of vectors.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Packed {
+    cherries: [u64; WORDS],
+    head: u16,
+}
+
+impl Packed {
+    fn has(&self, cell: u16) -> bool {
+        self.cherries[cell as usize / 64] & (1 << (cell % 64)) != 0
+    }
+
+    fn eat(&mut self, cell: u16) {
+        self.cherries[cell as usize / 64] &= !(1 << (cell % 64));
+    }
+}
+
+/// What every [`Packed`] state of one search shares: the board they were made from, which
+/// cell is where, and where each move from each cell leads.
+pub(crate) struct Layout<'a> {
+    board: &'a Board,
+    tiles: &'a TileRegistry,
+    /// The position of each cell, in reading order.
+    cells: Vec<Position>,
+    rocks: [u64; WORDS],
+    /// The cell one step from each cell in each of the rules' directions, if any.
+    next: Vec<Vec<Option<u16>>>,
+    /// The open cells at the start, before the snake is placed.
+    cherries: [u64; WORDS],
+    /// Which of those are empty floor rather than cherries.
+    floor: [u64; WORDS],
+}
+
+impl<'a> Layout<'a> {
+    /// The layout of `board`, if its states can be packed: it has no custom tiles, whose
+    /// behaviour could do anything to the board, no snake yet, at most [`MAX_CELLS`] cells,
+    /// and a body that doesn't decay, which would need ages kept as well.
+    pub(crate) fn new(board: &'a Board, tiles: &'a TileRegistry) -> Option<Self> {
+        if board.rules.decay.is_some() || tiles.hooks().is_some() {
+            return None;
+        }
+
+        let cells: Vec<Position> = board
+            .tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| (0..row.len()).map(move |x| (x, y)))
+            .collect();
+
+        if cells.len() > MAX_CELLS {
+            return None;
+        }
+
+        let mut rocks = [0; WORDS];
+        let mut cherries = [0; WORDS];
+        let mut floor = [0; WORDS];
+
+        for (i, &(x, y)) in cells.iter().enumerate() {
+            let bit = 1 << (i % 64);
+
+            match board.tiles[y][x] {
+                Tile::Rock => rocks[i / 64] |= bit,
+                Tile::Cherry => cherries[i / 64] |= bit,
+                Tile::Empty => {
+                    cherries[i / 64] |= bit;
+                    floor[i / 64] |= bit;
+                }
+                Tile::SnakeBody => {}
+                Tile::SnakeHead | Tile::Custom(_) => return None,
+            }
+        }
+
+        let index = |(x, y): Position| {
+            let before: usize = board.tiles[..y].iter().map(Vec::len).sum();
+
+            (before + x) as u16
+        };
+
+        let next = cells
+            .iter()
+            .map(|&p| {
+                board
+                    .rules
+                    .dirs()
+                    .iter()
+                    .map(|&dir| board.neighbour(p, dir).map(index))
+                    .collect()
+            })
+            .collect();
+
+        Some(Self {
+            board,
+            tiles,
+            cells,
+            rocks,
+            next,
+            cherries,
+            floor,
+        })
+    }
+
+    fn index(&self, p: Position) -> u16 {
+        self.cells
+            .iter()
+            .position(|&c| c == p)
+            .expect("positions come from the board") as u16
+    }
+
+    fn rock(&self, cell: u16) -> bool {
+        self.rocks[cell as usize / 64] & (1 << (cell % 64)) != 0
+    }
+
+    fn floor(&self, cell: u16) -> bool {
+        self.floor[cell as usize / 64] & (1 << (cell % 64)) != 0
+    }
+
+    /// [`Board::move_snake`] on a packed state, for the built-in tiles only.
+    fn slide(&self, state: &Packed, d: usize) -> Packed {
+        let rules = self.board.rules;
+        let start = state.head;
+        let mut next = state.clone();
+
+        while let Some(n) = self.next[next.head as usize][d] {
+            // Under the body unless it's open, and the body only blocks if the rules say
+            if n == start || self.rock(n) || (!next.has(n) && rules.body_blocks) {
+                break;
+            }
+
+            next.eat(n);
+            next.head = n;
+
+            if rules.movement == Movement::Step {
+                break;
+            }
+        }
+
+        next
+    }
+}
+
+impl Space for Layout<'_> {
+    type State = Packed;
+
+    fn board(&self) -> &Board {
+        self.board
+    }
+
+    fn tiles(&self) -> &TileRegistry {
+        self.tiles
+    }
+
+    fn place(&self, p: Position) -> Packed {
+        let mut state = Packed {
+            cherries: self.cherries,
+            head: self.index(p),
+        };
+
+        state.eat(state.head);
+        state
+    }
+
+    fn cherry_count(&self, state: &Packed) -> usize {
+        state
+            .cherries
+            .iter()
+            .zip(&self.floor)
+            .map(|(w, f)| (w & !f).count_ones() as usize)
+            .sum()
+    }
+
+    fn head(&self, state: &Packed) -> Option<Position> {
+        Some(self.cells[state.head as usize])
+    }
+
+    fn moves(&self, state: &Packed) -> Vec<(Dir, Packed)> {
+        self.board
+            .rules
+            .dirs()
+            .iter()
+            .enumerate()
+            .map(|(d, &dir)| (dir, self.slide(state, d)))
+            .collect()
+    }
+
+    /// [`Board::stranded_cherries`], following open cells from the head a cell at a time.
+    fn stranded(&self, state: &Packed) -> usize {
+        if !self.board.rules.body_blocks {
+            return 0;
+        }
+
+        let mut seen = [0u64; WORDS];
+        let mut stack = vec![state.head];
+        let mut reached = 0;
+
+        while let Some(cell) = stack.pop() {
+            for &n in self.next[cell as usize].iter().flatten() {
+                let (word, bit) = (n as usize / 64, 1 << (n % 64));
+
+                if state.has(n) && seen[word] & bit == 0 {
+                    seen[word] |= bit;
+                    reached += usize::from(!self.floor(n));
+                    stack.push(n);
+                }
+            }
+        }
+
+        self.cherry_count(state) - reached
+    }
+
+    fn unpack(&self, state: &Packed) -> Board {
+        let mut board = self.board.clone();
+
+        for (i, &(x, y)) in self.cells.iter().enumerate() {
+            let cell = i as u16;
+
+            let tile = match board.tiles[y][x] {
+                Tile::Rock => Tile::Rock,
+                _ if cell == state.head => Tile::SnakeHead,
+                _ if state.has(cell) && self.floor(cell) => Tile::Empty,
+                _ if state.has(cell) => Tile::Cherry,
+                _ => Tile::SnakeBody,
+            };
+
+            board.set((x, y), tile);
+        }
+
+        board
+    }
+
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
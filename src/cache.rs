@@ -0,0 +1,214 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::id::BoardId;
+use crate::order::StartOrder;
+use crate::{Board, Outcome, Partial, SearchStats, Solution, Tile};
+
+/// Results of finished searches, kept on disk so that solving a board again, in a later
+/// run or a later batch, is instant. Each result is a file named after the board's
+/// [`BoardId`] and the start order it was searched in, which decides which solution the
+/// search finds.
+///
+/// Only answers that are final are kept: a solution, or proof there's none. Boards with
+/// custom tiles aren't kept at all, since their tiles could behave differently next time.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// One cached result, as [`Cache::entries`] lists them.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub id: BoardId,
+    pub order: String,
+    pub solved: bool,
+    pub moves: Option<usize>,
+    pub states: usize,
+    pub elapsed_ms: f64,
+}
+
+/// A result as it's kept in its file.
+#[derive(Serialize, Deserialize)]
+struct Saved {
+    version: String,
+    board: Board,
+    solution: Option<Solution>,
+    partial: Option<Partial>,
+    states: usize,
+    elapsed_ms: f64,
+}
+
+/// Results from other versions may have been found by a different search.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Writes so far, so that temporary files from threads writing at once never share a name.
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+impl Cache {
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `$SNAKE_CACHE_DIR`, or `snake` in `$XDG_CACHE_HOME` or else `~/.cache`.
+    pub fn default_dir() -> Option<PathBuf> {
+        let var = |name| {
+            env::var_os(name)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+
+        var("SNAKE_CACHE_DIR")
+            .or_else(|| var("XDG_CACHE_HOME").map(|d| d.join("snake")))
+            .or_else(|| var("HOME").map(|d| d.join(".cache").join("snake")))
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    fn path(&self, id: BoardId, order: StartOrder) -> PathBuf {
+        self.dir.join(format!("{id}-{order}.json"))
+    }
+
+    /// The outcome of searching `board` in `order`, if it's been cached. Its stats only
+    /// say how deep the solution is and how long the search took when it ran.
+    pub fn get(&self, board: &Board, order: StartOrder) -> Option<Outcome> {
+        if !cacheable(board) {
+            return None;
+        }
+
+        let text = fs::read_to_string(self.path(board.id(), order)).ok()?;
+        let saved: Saved = serde_json::from_str(&text).ok()?;
+
+        // Two boards with the same id are all but impossible, but cheap to rule out
+        if saved.version != VERSION || saved.board != *board {
+            return None;
+        }
+
+        Some(Outcome {
+            stats: SearchStats {
+                depth: saved.solution.as_ref().map(|s| s.moves.len()),
+                elapsed: Duration::from_secs_f64(saved.elapsed_ms / 1000.0),
+                ..SearchStats::default()
+            },
+            solution: saved.solution,
+            partial: saved.partial,
+            states: saved.states,
+            timed_out: false,
+        })
+    }
+
+    /// Keep `outcome` as the result of searching `board` in `order`, unless it isn't final.
+    pub fn put(&self, board: &Board, order: StartOrder, outcome: &Outcome) -> io::Result<()> {
+        if outcome.timed_out || !cacheable(board) {
+            return Ok(());
+        }
+
+        let saved = Saved {
+            version: VERSION.to_string(),
+            board: board.clone(),
+            solution: outcome.solution.clone(),
+            partial: outcome.partial.clone(),
+            states: outcome.states,
+            elapsed_ms: outcome.stats.elapsed.as_secs_f64() * 1000.0,
+        };
+        let json = serde_json::to_string(&saved)?;
+
+        fs::create_dir_all(&self.dir)?;
+
+        // Written aside and moved into place, so that nobody reads half a result
+        let path = self.path(board.id(), order);
+        let temporary = self.dir.join(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        fs::write(&temporary, format!("{json}\n"))?;
+        fs::rename(&temporary, path)
+    }
+
+    /// Every cached result, by id. Files that can't be read are skipped.
+    pub fn entries(&self) -> io::Result<Vec<Entry>> {
+        let mut entries: Vec<Entry> = self
+            .files()?
+            .into_iter()
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_str()?;
+                let (id, order) = name.split_once('-')?;
+                let saved: Saved = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+
+                Some(Entry {
+                    id: id.parse().ok()?,
+                    order: order.to_string(),
+                    solved: saved.solution.is_some(),
+                    moves: saved.solution.map(|s| s.moves.len()),
+                    states: saved.states,
+                    elapsed_ms: saved.elapsed_ms,
+                })
+            })
+            .collect();
+
+        entries.sort_by(|a, b| (a.id, &a.order).cmp(&(b.id, &b.order)));
+
+        Ok(entries)
+    }
+
+    /// Forget every result for the board with `id`, returning how many there were.
+    pub fn remove(&self, id: BoardId) -> io::Result<usize> {
+        let prefix = format!("{id}-");
+        let mut removed = 0;
+
+        for path in self.files()? {
+            if path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&prefix))
+            {
+                fs::remove_file(path)?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Forget every result, returning how many there were.
+    pub fn clear(&self) -> io::Result<usize> {
+        let files = self.files()?;
+
+        for path in &files {
+            fs::remove_file(path)?;
+        }
+
+        Ok(files.len())
+    }
+
+    /// The result files, which is none if the cache hasn't been written to yet.
+    fn files(&self) -> io::Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        Ok(entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .collect())
+    }
+}
+
+fn cacheable(board: &Board) -> bool {
+    !board
+        .tiles
+        .iter()
+        .flatten()
+        .any(|t| matches!(t, Tile::Custom(_)))
+}
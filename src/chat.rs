@@ -0,0 +1,122 @@
+use std::fmt::Write;
+
+use crate::widget::{BoardWidget, Theme, TileStyle};
+use crate::{Board, Solution};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Discord,
+    Slack,
+}
+
+fn emoji_theme() -> Theme {
+    Theme {
+        rock: TileStyle::new("🪨", None),
+        cherry: TileStyle::new("🍒", None),
+        empty: TileStyle::new("⬜", None),
+        body: TileStyle::new("🟩", None),
+        head: TileStyle::new("🐍", None),
+        custom: TileStyle::new("❓", None),
+    }
+}
+
+/// Render the board as a grid of emoji, one message line per row.
+pub fn emoji_board(board: &Board) -> String {
+    BoardWidget::new(board, &emoji_theme()).to_string()
+}
+
+/// Render the board in its plain text form inside a monospace block.
+pub fn code_board(board: &Board) -> String {
+    format!("```\n{}\n```", BoardWidget::new(board, &Theme::plain()))
+}
+
+/// Render a solution hidden behind a spoiler where the platform supports one. Slack has no
+/// spoiler markup, so there the moves go in a code block below a warning line.
+pub fn solution(platform: Platform, solution: &Solution) -> String {
+    let Solution {
+        start: (x, y),
+        moves,
+    } = solution;
+
+    let mut steps = format!("Place snake at {x}, {y}");
+
+    for (i, d) in moves.iter().enumerate() {
+        let _ = write!(steps, "\n{:2}. {d:?}", i + 1);
+    }
+
+    let summary = format!("Solution in {} moves:", moves.len());
+
+    match platform {
+        Platform::Discord => format!("{summary}\n||```\n{steps}\n```||"),
+        Platform::Slack => format!("{summary} (spoilers below)\n```\n{steps}\n```"),
+    }
+}
+
+/// Find a board in a chat message. A fenced code block wins; otherwise the longest run of
+/// consecutive lines that look like board rows (plain text or emoji) is used.
+pub fn parse_board(message: &str) -> Option<Board> {
+    if let Some(block) = code_block(message) {
+        let text = rows_to_text(block.lines())?;
+
+        return Board::new(&text).ok();
+    }
+
+    let mut best: Vec<&str> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for line in message.lines() {
+        if row_to_text(line).is_some() {
+            current.push(line);
+        } else {
+            if current.len() > best.len() {
+                best = std::mem::take(&mut current);
+            }
+
+            current.clear();
+        }
+    }
+
+    if current.len() > best.len() {
+        best = current;
+    }
+
+    rows_to_text(best.into_iter()).and_then(|text| Board::new(&text).ok())
+}
+
+fn code_block(message: &str) -> Option<&str> {
+    let (_, rest) = message.split_once("```")?;
+    let (block, _) = rest.split_once("```")?;
+
+    // Drop a language tag such as ```text
+    Some(match block.split_once('\n') {
+        Some((tag, body)) if !tag.trim().is_empty() && row_to_text(tag).is_none() => body,
+        _ => block,
+    })
+}
+
+fn rows_to_text<'a>(lines: impl Iterator<Item = &'a str>) -> Option<String> {
+    let rows: Vec<String> = lines
+        .filter(|l| !l.trim().is_empty())
+        .map(row_to_text)
+        .collect::<Option<_>>()?;
+
+    (!rows.is_empty()).then(|| rows.join("\n"))
+}
+
+fn row_to_text(line: &str) -> Option<String> {
+    let line = line.trim();
+
+    if line.is_empty() {
+        return None;
+    }
+
+    line.chars()
+        .filter(|&c| c != '\u{fe0f}')
+        .map(|c| match c {
+            'r' | '🪨' | '⬛' => Some('r'),
+            'c' | '🍒' => Some('c'),
+            '.' | '⬜' => Some('.'),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,511 @@
+use std::fs::{self, File};
+use std::io::{stdin, IsTerminal, Read};
+use std::process::exit;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+#[cfg(feature = "script")]
+use snake::script::Script;
+use snake::tiles::TileRegistry;
+use snake::widget::{BoardWidget, Theme};
+use snake::{Board, Dir, Position, SnakeError, Solution, Tile};
+
+mod boards;
+#[cfg(feature = "chat")]
+mod chat;
+mod daemons;
+mod edit;
+mod formats;
+mod inspect;
+mod runs;
+mod solve;
+#[cfg(feature = "store")]
+mod store;
+#[cfg(feature = "gui")]
+mod window;
+
+pub use boards::{
+    analyze_command, generate_command, id_command, rocks_command, starts_command, stitch_command,
+    symmetry_command, transform_command,
+};
+#[cfg(feature = "chat")]
+pub use chat::chat_reply;
+#[cfg(feature = "grpc")]
+pub use daemons::grpc_command;
+pub use daemons::{jsonl_command, serve_command, worker_command};
+pub use edit::edit_command;
+pub use formats::{pddl_command, xsb_command};
+pub use inspect::{debug_command, demo_command, diff_command, hint_command, verify_command};
+pub use runs::{batch_command, cache_command, compare_command, dataset_command};
+pub use solve::solve_command;
+#[cfg(feature = "store")]
+pub use store::store_command;
+#[cfg(feature = "gui")]
+pub use window::window_command;
+
+/// Solve snake puzzles: boards of rocks and cherries where a snake, placed on any cherry,
+/// slides in straight lines eating cherries until it's blocked, and has to eat them all.
+#[derive(Parser)]
+#[command(name = "snake", subcommand_value_name = "COMMAND")]
+pub struct Cli {
+    #[command(flatten)]
+    pub options: Options,
+    // What runs without a command, as `solve` does
+    #[command(flatten)]
+    pub solve: Option<solve::SolveArgs>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Solve the board, or each board FILE given (the default when no command is given).
+    Solve(Box<solve::SolveArgs>),
+    /// Check a solution, with moves as letters like LDRU.
+    ///
+    /// Check that playing MOVES from the cherry at X,Y solves the board in FILE or on
+    /// stdin.
+    Verify(inspect::VerifyArgs),
+    /// Suggest the next move of a game in progress, within a budget.
+    ///
+    /// Suggest the next move after playing MOVES from X,Y on the board in FILE or on stdin.
+    Hint(inspect::HintArgs),
+    /// Compare two solutions.
+    ///
+    /// Compare two solutions of the board on stdin: how long each is, how far they agree,
+    /// and where they part, drawn on the board.
+    DiffSolutions(inspect::DiffArgs),
+    /// Step back and forth through the search.
+    ///
+    /// Record the search of the board in FILE and step through it, showing the queue and
+    /// visited set at each step. Commands are read from stdin.
+    DebugSearch(inspect::DebugArgs),
+    /// Toggle rocks, checking in the background whether the board can still be solved.
+    ///
+    /// Edit the board in FILE a rock or cherry at a time, saying after every edit whether
+    /// it can still be solved and how short a solution can be.
+    Edit(edit::EditArgs),
+    /// List the built-in puzzles, or solve and play one.
+    ///
+    /// List the built-in puzzles, or solve the one called NAME and play the solution.
+    Demo(inspect::DemoArgs),
+    /// Solve the board and play the solution in a window.
+    ///
+    /// Solve the board and play the solution in a window. Space plays it again and Escape
+    /// closes the window.
+    #[cfg(feature = "gui")]
+    Window(window::WindowArgs),
+    /// Generate a solvable board.
+    Generate(boards::GenerateArgs),
+    /// Check which starting cherries work.
+    ///
+    /// Check which cherries the board on stdin can be solved from.
+    Starts(boards::StartsArgs),
+    /// Suggest rocks to remove from an unsolvable board.
+    ///
+    /// Suggest rocks to remove from the unsolvable board on stdin, trying every single rock
+    /// and then every pair.
+    Rocks(boards::RocksArgs),
+    /// List the board's symmetries.
+    Symmetry,
+    /// Print each board's id, the same wherever its file is.
+    ///
+    /// Print the id of each board in the files, or on stdin, the same wherever its file is.
+    Id(boards::IdArgs),
+    /// Turn, mirror, crop or pad the board.
+    ///
+    /// Apply each change to the board in turn, in the order given, and print the result.
+    Transform(boards::TransformArgs),
+    /// Join boards into one, side by side.
+    ///
+    /// Put the board in each file side by side to make one larger board, warning if not
+    /// every cherry of it can be reached from every other.
+    Stitch(boards::StitchArgs),
+    /// Measure the board, or count the moves to each cell.
+    ///
+    /// Measure the board on stdin without solving it, or show how many moves the head needs
+    /// from X,Y to each cell.
+    Analyze(boards::AnalyzeArgs),
+    /// Solve a directory of boards in parallel.
+    ///
+    /// Solve every board in DIR in parallel, printing each result as a JSON line as it
+    /// finishes.
+    Batch(runs::BatchArgs),
+    /// Compare two saved batch runs.
+    ///
+    /// Compare two batch reports board by board and in total.
+    CompareRuns(runs::CompareArgs),
+    /// Write each move of each board's shortest solution as CSV, for training models.
+    ///
+    /// Write a CSV row for every move of the shortest solution of every board in DIR.
+    ExportDataset(runs::DatasetArgs),
+    /// Look after the results solve and batch keep.
+    ///
+    /// Look after the results solve and batch keep, in $SNAKE_CACHE_DIR or the user's cache
+    /// directory.
+    #[command(subcommand)]
+    Cache(runs::CacheCommand),
+    /// Convert to and from PDDL, for planners.
+    #[command(subcommand)]
+    Pddl(formats::PddlCommand),
+    /// Convert to and from XSB levels.
+    #[command(subcommand)]
+    Xsb(formats::XsbCommand),
+    /// Serve the solver over HTTP.
+    Serve(daemons::ServeArgs),
+    /// Serve the solver over gRPC, as proto/snake.proto describes.
+    #[cfg(feature = "grpc")]
+    Grpc(daemons::GrpcArgs),
+    /// Solve jobs from a shared queue directory.
+    ///
+    /// Solve the jobs in QUEUE_DIR, which other workers may share.
+    Worker(daemons::WorkerArgs),
+    /// Answer JSON requests on stdin, one per line.
+    ///
+    /// Answer JSON requests on stdin, one per line, with a JSON line each on stdout.
+    #[command(long_flag = "jsonl")]
+    Jsonl(daemons::JsonlArgs),
+    /// Reply to a chat message with its board and a spoilered solution.
+    ///
+    /// Read a chat message on stdin and reply with its board and a spoilered solution.
+    #[cfg(feature = "chat")]
+    Chat(chat::ChatArgs),
+    /// Solve puzzles into a store and query it.
+    #[cfg(feature = "store")]
+    Store(store::StoreArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+/// Flags every command accepts, wherever they appear.
+#[derive(Args, Clone)]
+pub struct Options {
+    /// Read the board from FILE instead of stdin.
+    #[arg(short, long, value_name = "FILE", global = true)]
+    input: Option<String>,
+    /// Write answers as text or JSON.
+    #[arg(short, long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
+    /// Only print the answer.
+    #[arg(short, long, global = true, overrides_with = "verbose")]
+    quiet: bool,
+    /// Also print the board as it was read.
+    #[arg(short, long, global = true, overrides_with = "quiet")]
+    verbose: bool,
+    /// Read boards written in other glyphs, like '#=r .=c' for # as rock and . as cherry; a
+    /// board file can say the same on a tiles: line.
+    #[arg(
+        long = "tiles",
+        value_name = "PAIRS",
+        value_parser = alias,
+        value_delimiter = ' ',
+        global = true
+    )]
+    aliases: Vec<(char, Tile)>,
+    /// Draw boards without colour, even in a terminal.
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Add tiles and change the rules with a Rhai script.
+    #[cfg(feature = "script")]
+    #[arg(long, value_name = "FILE", value_parser = script, global = true)]
+    script: Option<Script>,
+}
+
+/// One pair of `--tiles`, like `#=r`.
+fn alias(pair: &str) -> Result<(char, Tile), String> {
+    match TileRegistry::default().parse_aliases(pair).as_deref() {
+        Ok(&[alias]) => Ok(alias),
+        _ => Err(format!("expected pairs like #=r .=c, not {pair:?}")),
+    }
+}
+
+/// The script of `--script`, installed once to check that it can be.
+#[cfg(feature = "script")]
+fn script(path: &str) -> Result<Script, String> {
+    Script::load(path.as_ref()).and_then(|s| s.install(&mut TileRegistry::default()).map(|_| s))
+}
+
+/// A number of seconds, like `--time-limit` takes.
+fn seconds(s: &str) -> Result<Duration, String> {
+    s.parse()
+        .ok()
+        .filter(|s: &f64| s.is_finite() && *s >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or_else(|| "expected a number of seconds".to_string())
+}
+
+/// A number of milliseconds, like `--delay` takes.
+fn millis(s: &str) -> Result<Duration, String> {
+    s.parse()
+        .map(Duration::from_millis)
+        .map_err(|_| "expected a number of milliseconds".to_string())
+}
+
+/// A cell written `X,Y`.
+fn position(s: &str) -> Result<Position, String> {
+    parse_position(s).ok_or_else(|| "expected X,Y".to_string())
+}
+
+/// A number of bytes, as [`parse_bytes`] reads them.
+fn size(s: &str) -> Result<usize, String> {
+    parse_bytes(s).ok_or_else(|| "expected a size like 512M or 2G".to_string())
+}
+
+impl Options {
+    fn verbosity(&self) -> Verbosity {
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (_, true) => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        }
+    }
+
+    /// How to draw boards to a stream, in colour only if it's a terminal.
+    fn theme(&self, terminal: bool) -> Theme {
+        match !self.no_color && terminal {
+            true => Theme::colored(),
+            false => Theme::plain(),
+        }
+    }
+
+    /// The tiles to read boards with: the usual ones, any `--tiles` aliases and whatever
+    /// `--script` adds.
+    fn registry(&self) -> TileRegistry {
+        let mut tiles = TileRegistry::default();
+
+        for &(glyph, tile) in &self.aliases {
+            tiles.alias(glyph, tile);
+        }
+
+        // Installed once already when parsing, so it can't fail now
+        #[cfg(feature = "script")]
+        if let Some(script) = &self.script {
+            let _ = script.install(&mut tiles);
+        }
+
+        tiles
+    }
+
+    /// Exit with the first error `--script` ran into while searching, if it ran into any.
+    fn check_script(&self) {
+        #[cfg(feature = "script")]
+        if let Some(e) = self.script.as_ref().and_then(Script::error) {
+            eprintln!("Script error in {e}");
+            exit(1);
+        }
+    }
+
+    /// The board text, from `--input` or stdin.
+    fn read_input(&self) -> String {
+        let read = match &self.input {
+            Some(path) => fs::read_to_string(path),
+            None => {
+                let mut input = String::new();
+
+                stdin().read_to_string(&mut input).map(|_| input)
+            }
+        };
+
+        read.unwrap_or_else(|e| self.fail(e.into()))
+    }
+
+    /// The board from `--input` or stdin, ready to search.
+    fn read_board(&self) -> Board {
+        let board = match &self.input {
+            Some(path) => File::open(path)
+                .map_err(SnakeError::from)
+                .and_then(|f| Board::read(f, &self.registry())),
+            None => Board::read(stdin(), &self.registry()),
+        };
+
+        board
+            .and_then(|b| b.validate().map(|()| b))
+            .unwrap_or_else(|e| self.fail(e))
+    }
+
+    /// Every board from `--input` or stdin, separated by blank lines and ready to search.
+    fn read_boards(&self) -> Result<Vec<Result<Board, SnakeError>>, SnakeError> {
+        let input = match &self.input {
+            Some(path) => fs::read_to_string(path)?,
+            None => {
+                let mut input = String::new();
+
+                stdin().read_to_string(&mut input)?;
+                input
+            }
+        };
+
+        let boards = Board::parse_all(&input, &self.registry())
+            .into_iter()
+            .map(|board| board.and_then(|b| b.validate().map(|()| b)))
+            .collect();
+
+        Ok(boards)
+    }
+
+    /// Exit with `e`, naming the board file it's about if there is one.
+    fn fail(&self, e: SnakeError) -> ! {
+        match &self.input {
+            Some(path) => {
+                eprintln!("{path}: {e}");
+                exit(1);
+            }
+            None => fail(e),
+        }
+    }
+}
+
+fn fail(e: SnakeError) -> ! {
+    eprintln!("{e}");
+    exit(1);
+}
+
+fn print_solution(solution: &Solution) {
+    println!("Solution found in {} moves.", solution.moves.len());
+    print_moves(solution);
+}
+
+fn print_moves(
+    Solution {
+        start: (x, y),
+        moves,
+    }: &Solution,
+) {
+    println!("Place snake at {x}, {y}");
+
+    for (i, d) in moves.iter().enumerate() {
+        println!("{i:2}. {d:?}");
+    }
+}
+
+/// A solution given as `X,Y MOVES`, either as two arguments or as one the way `--compact`
+/// writes it, and the arguments after it.
+fn solution_args(args: &[String]) -> Option<(Solution, &[String])> {
+    match args {
+        [one, rest @ ..] if one.trim().contains(char::is_whitespace) => {
+            Some((one.parse().ok()?, rest))
+        }
+        [start, moves, rest @ ..] => Some((
+            Solution {
+                start: parse_position(start)?,
+                moves: parse_moves(moves),
+            },
+            rest,
+        )),
+        _ => None,
+    }
+}
+
+/// [`snake::parse_moves`], exiting on anything that isn't a move.
+fn parse_moves(moves: &str) -> Vec<Dir> {
+    snake::parse_moves(moves).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(2);
+    })
+}
+
+/// Play `solution` on `board` one frame per move, `delay` apart, each under `heading`.
+fn replay(
+    board: &Board,
+    solution: &Solution,
+    delay: Duration,
+    heading: &str,
+    options: &Options,
+    tiles: &TileRegistry,
+) {
+    // Only a terminal can be redrawn in place; anywhere else each frame follows the last
+    let animate = std::io::stdout().is_terminal();
+    let theme = options.theme(animate);
+    let frame = |b: &Board, caption: &str| {
+        if animate {
+            print!("\x1b[2J\x1b[H");
+        }
+
+        if !heading.is_empty() {
+            println!("{heading}");
+        }
+
+        println!("{}\n", BoardWidget::new(b, &theme));
+        println!("{caption}\n");
+    };
+
+    let mut b = board.place_snake(solution.start);
+    let (x, y) = solution.start;
+
+    frame(&b, &format!("Start at {x}, {y}"));
+
+    for (i, &dir) in solution.moves.iter().enumerate() {
+        if animate {
+            thread::sleep(delay);
+        }
+
+        b = b
+            .move_snake(dir, tiles)
+            .expect("moves of a solution can be played");
+
+        frame(
+            &b,
+            &format!("Move {} of {}: {dir:?}", i + 1, solution.moves.len()),
+        );
+    }
+
+    println!("Every cherry eaten in {} moves.", solution.moves.len());
+}
+
+/// A number of bytes, with an optional `K`, `M` or `G` for kibibytes, mebibytes or
+/// gibibytes.
+fn parse_bytes(s: &str) -> Option<usize> {
+    let (number, shift) = match s.to_ascii_uppercase().trim_end_matches(['B', 'I']) {
+        n if n.ends_with('K') => (n[..n.len() - 1].to_string(), 10),
+        n if n.ends_with('M') => (n[..n.len() - 1].to_string(), 20),
+        n if n.ends_with('G') => (n[..n.len() - 1].to_string(), 30),
+        n => (n.to_string(), 0),
+    };
+
+    number.trim().parse::<usize>().ok()?.checked_mul(1 << shift)
+}
+
+fn parse_position(s: &str) -> Option<Position> {
+    let (x, y) = s.split_once(',')?;
+
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn every_command_parses_its_arguments() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn solve_runs_without_a_command_and_options_go_anywhere() {
+        let cli = Cli::parse_from(["snake", "board.txt", "--stats", "-q"]);
+
+        assert!(cli.command.is_none() && cli.options.quiet);
+        assert!(cli.solve.is_some());
+
+        let cli = Cli::parse_from(["snake", "-v", "verify", "0,0", "DRU", "-f", "json"]);
+
+        assert!(matches!(cli.command, Some(Command::Verify(_))));
+        assert!(cli.solve.is_none());
+        assert_eq!(cli.options.verbosity(), Verbosity::Verbose);
+        assert_eq!(cli.options.format, Format::Json);
+    }
+}
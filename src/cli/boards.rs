@@ -0,0 +1,630 @@
+use std::num::NonZeroUsize;
+use std::process::exit;
+use std::time::{Duration, Instant};
+
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Args, FromArgMatches};
+use snake::objective;
+use snake::symmetry::Symmetry;
+use snake::tiles::TileRegistry;
+use snake::transform::Join;
+use snake::{analysis, api, json, solve_within, symmetry, Board, Position, Tile};
+
+use super::{position, seconds, solution_args, Format, Options, Verbosity};
+
+#[derive(Args)]
+pub struct StartsArgs {
+    /// Give each start at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    time_limit: Duration,
+    /// Also compare the shortest solutions from each.
+    #[arg(long)]
+    compare: bool,
+}
+
+/// `starts [--time-limit SECS] [--compare]` checks every starting cherry of the board on
+/// stdin and prints which of them the board can be solved from, optionally comparing the
+/// shortest solutions from each.
+pub fn starts_command(options: &Options, args: StartsArgs) {
+    let StartsArgs {
+        time_limit,
+        compare,
+    } = args;
+    let board = options.read_board();
+    let starts = analysis::viable_starts(&board, time_limit, &TileRegistry::default());
+
+    println!("{}", analysis::start_overlay(&board, &starts));
+
+    // Long slides feel different from short hops, so list the least travelled first
+    let mut solvable: Vec<_> = starts
+        .iter()
+        .filter_map(|(p, v)| match v {
+            analysis::Viability::Solvable(solution) => Some((
+                p,
+                solution,
+                objective::cells(&board, solution, &TileRegistry::default()),
+            )),
+            _ => None,
+        })
+        .collect();
+
+    solvable.sort_by_key(|&(_, solution, cells)| (cells, solution.moves.len()));
+
+    let unknown = starts
+        .iter()
+        .filter(|(_, v)| matches!(v, analysis::Viability::Unknown))
+        .count();
+
+    println!();
+
+    for ((x, y), solution, cells) in &solvable {
+        println!(
+            "From {x}, {y}: {} moves, {cells} cells",
+            solution.moves.len()
+        );
+    }
+
+    println!(
+        "{} of {} starts solve the board.",
+        solvable.len(),
+        starts.len()
+    );
+
+    if unknown > 0 {
+        println!("{unknown} timed out after {time_limit:?} each.");
+    }
+
+    if compare && solvable.len() > 1 {
+        println!();
+        print!(
+            "{:<9} {:>6} {:>6} {:>6}  overlap:",
+            "start", "moves", "turns", "cells"
+        );
+
+        for ((x, y), ..) in &solvable {
+            print!(" {:>7}", format!("{x},{y}"));
+        }
+
+        println!();
+
+        for ((x, y), a, cells) in &solvable {
+            print!(
+                "{:<9} {:>6} {:>6} {cells:>6}          ",
+                format!("{x}, {y}"),
+                a.moves.len(),
+                objective::turns(&a.moves)
+            );
+
+            for (_, b, _) in &solvable {
+                let overlap = analysis::path_overlap(&board, a, b, &TileRegistry::default());
+
+                print!(" {:>6.0}%", overlap * 100.0);
+            }
+
+            println!();
+        }
+    }
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("measure").required(true).args(["metrics", "reach"])))]
+pub struct AnalyzeArgs {
+    /// Print the cherries, openness, branching and estimated state space.
+    #[arg(long)]
+    metrics: bool,
+    /// Print the metrics as JSON.
+    #[arg(long, conflicts_with = "reach")]
+    json: bool,
+    /// Print the moves from X,Y to each cell.
+    #[arg(long, value_name = "X,Y", value_parser = position)]
+    reach: Option<Position>,
+}
+
+/// `analyze --metrics [--json]` measures the board on stdin without solving it, and
+/// `analyze --reach X,Y` shows how many moves the head needs from `X,Y` to each cell.
+pub fn analyze_command(options: &Options, args: AnalyzeArgs) {
+    if let Some(head) = args.reach {
+        let board = options.read_board();
+
+        for row in analysis::reachability(&board, head, &TileRegistry::default()) {
+            let cells: String = row
+                .iter()
+                .map(|d| d.map_or("  .".to_string(), |d| format!("{d:>3}")))
+                .collect();
+
+            println!("{cells}");
+        }
+
+        return;
+    }
+
+    let board = options.read_board();
+    let m = analysis::metrics(&board, &TileRegistry::default());
+
+    if args.json {
+        let json = json::Json::obj([
+            ("cherries", m.cherries.into()),
+            ("open_ratio", m.open_ratio.into()),
+            ("average_slide", m.average_slide.into()),
+            ("branching_factor", m.branching_factor.into()),
+            ("state_space", m.state_space.into()),
+        ]);
+
+        println!("{json}");
+        return;
+    }
+
+    println!("cherries:          {}", m.cherries);
+    println!("open area:         {:.1}%", m.open_ratio * 100.0);
+    println!("average slide:     {:.2} cells", m.average_slide);
+    println!("branching factor:  {:.2}", m.branching_factor);
+    println!("state space:       ~{:.2e} states", m.state_space);
+}
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    /// Columns (6 by default).
+    #[arg(long, value_name = "W")]
+    width: Option<usize>,
+    /// Rows (6 by default).
+    #[arg(long, value_name = "H")]
+    height: Option<usize>,
+    /// Rocks to place (a sixth of the cells by default).
+    #[arg(long, value_name = "N")]
+    rocks: Option<usize>,
+    /// Generate the same board again from the seed it printed.
+    #[arg(long, value_name = "S")]
+    seed: Option<u64>,
+    /// Give up after N unsolvable boards (100 by default).
+    #[arg(long, value_name = "N")]
+    attempts: Option<usize>,
+    /// Give each attempt at most SECS seconds to solve.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "1")]
+    time_limit: Duration,
+}
+
+/// `generate [--width W] [--height H] [--rocks N] [--seed S] [--attempts N]
+/// [--time-limit SECS]` prints a new solvable board.
+pub fn generate_command(options: &Options, args: GenerateArgs) {
+    let sizes = [
+        ("width", args.width),
+        ("height", args.height),
+        ("rocks", args.rocks),
+        ("attempts", args.attempts),
+    ];
+    let request: Vec<(&str, json::Json)> = sizes
+        .into_iter()
+        .filter_map(|(key, n)| Some((key, n?.into())))
+        .chain(args.seed.map(|seed| ("seed", seed.into())))
+        .collect();
+
+    let generated = api::generate(&json::Json::obj(request), args.time_limit).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1);
+    });
+
+    if options.format == Format::Json {
+        println!("{generated}");
+        return;
+    }
+
+    let board = generated.get("board").and_then(json::Json::as_str);
+
+    println!("{}", board.unwrap_or_default());
+
+    if options.verbosity() > Verbosity::Quiet {
+        if let Some(seed) = generated.get("seed") {
+            eprintln!("Seed: {seed}");
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct TransformArgs {
+    #[command(flatten)]
+    transforms: Transforms,
+    /// Move a solution along with the board and print it too.
+    #[arg(long, num_args = 2, value_names = ["X,Y", "MOVES"])]
+    solution: Option<Vec<String>>,
+    /// Transform the board in FILE instead of stdin.
+    #[arg(value_name = "FILE")]
+    path: Option<String>,
+}
+
+/// `transform [--rotate DEG] [--mirror x|y] [--transpose] [--crop X,Y,W,H] [--pad N|L,T,R,B]
+/// [--solution X,Y MOVES] [FILE]` applies each change to the board in turn and prints the
+/// result, with the solution moved along with it.
+pub fn transform_command(options: &Options, args: TransformArgs) {
+    let mut solution = args.solution.map(|s| match solution_args(&s) {
+        Some((solution, _)) => solution,
+        None => {
+            eprintln!("--solution expects X,Y MOVES");
+            exit(2);
+        }
+    });
+    let input = args.path.or(options.input.clone());
+
+    let options = Options {
+        input,
+        ..options.clone()
+    };
+    let mut board = options.read_board();
+
+    for op in args.transforms.0 {
+        match op {
+            Transform::Image(s) => {
+                let image = board.transformed(s).unwrap_or_else(|| {
+                    eprintln!("Only rectangular boards can be turned or mirrored.");
+                    exit(1);
+                });
+
+                solution = solution.map(|sol| sol.transformed(s, &board));
+                board = image;
+            }
+            Transform::Crop(at, w, h) => {
+                if solution.is_some() {
+                    eprintln!("A cropped board needs a new solution; leave out --solution.");
+                    exit(2);
+                }
+
+                board = board.crop(at, w, h).unwrap_or_else(|| {
+                    eprintln!(
+                        "The board doesn't cover all of {w}×{h} from {},{}.",
+                        at.0, at.1
+                    );
+                    exit(1);
+                });
+            }
+            Transform::Pad(sides) => {
+                board = board.pad(sides, Tile::Rock);
+                solution = solution.map(|sol| sol.shifted(sides[0], sides[1]));
+            }
+        }
+    }
+
+    let tiles = TileRegistry::default();
+
+    if options.format == Format::Json {
+        let json = match &solution {
+            Some(solution) => serde_json::json!({ "board": board, "solution": solution }),
+            None => serde_json::json!({ "board": board }),
+        };
+
+        println!("{json}");
+        return;
+    }
+
+    println!("{}", board.to_text(&tiles));
+
+    if let Some(solution) = &solution {
+        println!();
+        println!("{solution}");
+    }
+}
+
+/// One change `transform` makes to a board.
+#[derive(Clone)]
+enum Transform {
+    Image(Symmetry),
+    Crop(Position, usize, usize),
+    Pad([usize; 4]),
+}
+
+/// The changes `transform` makes, in the order they were given, which a derived parser
+/// would lose between one flag and another.
+struct Transforms(Vec<Transform>);
+
+/// Every flag of [`Transforms`], each of them read as a [`Transform`].
+const TRANSFORMS: [&str; 5] = ["rotate", "mirror", "transpose", "crop", "pad"];
+
+impl FromArgMatches for Transforms {
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        let mut given: Vec<(usize, Transform)> = TRANSFORMS
+            .iter()
+            .filter_map(|&id| Some(matches.indices_of(id)?.zip(matches.get_many(id)?.cloned())))
+            .flatten()
+            .collect();
+
+        given.sort_by_key(|&(i, _)| i);
+
+        Ok(Self(given.into_iter().map(|(_, t)| t).collect()))
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+
+        Ok(())
+    }
+}
+
+impl Args for Transforms {
+    fn augment_args(cmd: clap::Command) -> clap::Command {
+        let transform = |id: &'static str, value: &'static str, help: &'static str| {
+            Arg::new(id)
+                .long(id)
+                .value_name(value)
+                .help(help)
+                .action(ArgAction::Append)
+        };
+
+        cmd.arg(
+            transform("rotate", "DEG", "Turn clockwise by 90, 180 or 270 degrees")
+                .value_parser(rotation),
+        )
+        .arg(
+            transform(
+                "mirror",
+                "x|y",
+                "Flip left to right (x) or top to bottom (y)",
+            )
+            .value_parser(mirror),
+        )
+        .arg(
+            transform("transpose", "", "Swap rows and columns")
+                .num_args(0)
+                .default_missing_value("")
+                .value_parser(|_: &str| Ok::<_, String>(Transform::Image(Symmetry::Transpose))),
+        )
+        .arg(transform("crop", "X,Y,W,H", "Keep the W by H cells from X,Y").value_parser(crop))
+        .arg(
+            transform(
+                "pad",
+                "N|L,T,R,B",
+                "Add rock around the board, N on every side or each side its own",
+            )
+            .value_parser(pad),
+        )
+    }
+
+    fn augment_args_for_update(cmd: clap::Command) -> clap::Command {
+        Self::augment_args(cmd)
+    }
+}
+
+fn numbers(s: &str) -> Option<Vec<usize>> {
+    s.split(',').map(|n| n.trim().parse().ok()).collect()
+}
+
+fn rotation(s: &str) -> Result<Transform, String> {
+    match s {
+        "90" => Ok(Transform::Image(Symmetry::Rotate90)),
+        "180" => Ok(Transform::Image(Symmetry::Rotate180)),
+        "270" => Ok(Transform::Image(Symmetry::Rotate270)),
+        _ => Err("expected 90, 180 or 270".to_string()),
+    }
+}
+
+fn mirror(s: &str) -> Result<Transform, String> {
+    match s {
+        "x" => Ok(Transform::Image(Symmetry::MirrorX)),
+        "y" => Ok(Transform::Image(Symmetry::MirrorY)),
+        _ => Err("expected x or y".to_string()),
+    }
+}
+
+fn crop(s: &str) -> Result<Transform, String> {
+    match numbers(s).as_deref() {
+        Some(&[x, y, w, h]) => Ok(Transform::Crop((x, y), w, h)),
+        _ => Err("expected X,Y,W,H".to_string()),
+    }
+}
+
+fn pad(s: &str) -> Result<Transform, String> {
+    match numbers(s).as_deref() {
+        Some(&[n]) => Ok(Transform::Pad([n; 4])),
+        Some(&[l, t, r, b]) => Ok(Transform::Pad([l, t, r, b])),
+        _ => Err("expected N or L,T,R,B".to_string()),
+    }
+}
+
+#[derive(Args)]
+pub struct StitchArgs {
+    /// The boards to put side by side.
+    #[arg(value_name = "FILE", required = true)]
+    paths: Vec<String>,
+    /// Put N boards to a row (all of them by default).
+    #[arg(long, value_name = "N")]
+    columns: Option<NonZeroUsize>,
+    /// Part the boards by rock N thick with a corridor of cherries through it, instead of
+    /// edge to edge.
+    #[arg(long, value_name = "N")]
+    corridor: Option<usize>,
+}
+
+/// `stitch FILE... [--columns N] [--corridor N]` puts the board in each file side by side,
+/// `N` to a row, to make one larger board, and warns if not every cherry of it can be
+/// reached from every other.
+pub fn stitch_command(options: &Options, args: StitchArgs) {
+    let join = args.corridor.map_or(Join::Touching, Join::Corridor);
+    let rooms: Vec<Board> = args
+        .paths
+        .into_iter()
+        .map(|path| {
+            Options {
+                input: Some(path),
+                ..options.clone()
+            }
+            .read_board()
+        })
+        .collect();
+    let rows: Vec<Vec<Board>> = rooms
+        .chunks(args.columns.map_or(rooms.len(), NonZeroUsize::get))
+        .map(<[Board]>::to_vec)
+        .collect();
+
+    let board = Board::stitch(&rows, join).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1);
+    });
+
+    let tiles = TileRegistry::default();
+
+    match options.format {
+        Format::Json => println!("{}", serde_json::json!(board)),
+        Format::Text => println!("{}", board.to_text(&tiles)),
+    }
+
+    let cherries: Vec<_> = board.starting_positions().collect();
+
+    if let Some(&first) = cherries.first() {
+        let region = board.region(first, &tiles);
+
+        if cherries.iter().any(|p| !region.contains(p)) {
+            eprintln!("Some cherries can't be reached from the others, so this can't be solved.");
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct IdArgs {
+    /// The files of boards, or stdin without any.
+    #[arg(value_name = "FILE")]
+    paths: Vec<String>,
+    /// Give boards that are turns or mirrors of each other the same id.
+    #[arg(long)]
+    symmetric: bool,
+}
+
+/// `id [--symmetric] [FILE...]` prints the id of each board in the files, or on stdin,
+/// one per line, with the file it came from when there are several.
+pub fn id_command(options: &Options, args: IdArgs) {
+    let symmetric = args.symmetric;
+    let sources: Vec<Option<String>> = match args.paths.is_empty() {
+        true => vec![options.input.clone()],
+        false => args.paths.into_iter().map(Some).collect(),
+    };
+    let mut failed = false;
+
+    for input in sources {
+        let options = Options {
+            input,
+            ..options.clone()
+        };
+        let boards = options.read_boards().unwrap_or_else(|e| options.fail(e));
+        let name = options.input.as_deref().unwrap_or("-");
+
+        for (i, board) in boards.iter().enumerate() {
+            let label = match boards.len() {
+                1 => name.to_string(),
+                _ => format!("{name} #{}", i + 1),
+            };
+
+            match board {
+                Ok(board) if symmetric => println!("{}  {label}", board.symmetric_id()),
+                Ok(board) => println!("{}  {label}", board.id()),
+                Err(e) => {
+                    eprintln!("{label}: {e}");
+                    failed = true;
+                }
+            }
+        }
+    }
+
+    if failed {
+        exit(1);
+    }
+}
+
+/// `symmetry` lists the symmetries of the board on stdin.
+pub fn symmetry_command(options: &Options) {
+    let board = options.read_board();
+    let symmetries = symmetry::symmetries(&board);
+
+    if symmetries.is_empty() {
+        println!("The board has no symmetries.");
+        return;
+    }
+
+    for s in &symmetries {
+        println!("{s}");
+    }
+
+    let distinct = board
+        .starting_positions()
+        .filter(|&p| symmetry::is_canonical(p, &symmetries, &board))
+        .count();
+
+    println!();
+    println!(
+        "{distinct} of {} starts are distinct up to symmetry.",
+        board.cherry_count()
+    );
+}
+
+#[derive(Args)]
+pub struct RocksArgs {
+    /// Give each try at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    time_limit: Duration,
+}
+
+/// `rocks [--time-limit SECS]` suggests rocks to remove from the unsolvable board on stdin,
+/// trying every single rock and then every pair.
+pub fn rocks_command(options: &Options, args: RocksArgs) {
+    let time_limit = args.time_limit;
+    let board = options.read_board();
+    let tiles = TileRegistry::default();
+
+    if solve_within(&board, Some(Instant::now() + time_limit), &tiles)
+        .solution
+        .is_some()
+    {
+        println!("The board is already solvable.");
+        return;
+    }
+
+    let fixes = analysis::blocking_rocks(&board, 2, time_limit, &tiles);
+
+    if fixes.is_empty() {
+        println!("No one or two rocks stand in the way.");
+        exit(1);
+    }
+
+    for (rocks, solution) in fixes {
+        let rocks: Vec<String> = rocks.iter().map(|(x, y)| format!("{x}, {y}")).collect();
+
+        println!(
+            "Removing the {} at {} allows a solution in {} moves from {}, {}.",
+            if rocks.len() == 1 { "rock" } else { "rocks" },
+            rocks.join(" and "),
+            solution.moves.len(),
+            solution.start.0,
+            solution.start.1
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::{Cli, Command};
+
+    #[test]
+    fn transforms_apply_in_the_order_given() {
+        let cli = Cli::parse_from([
+            "snake",
+            "transform",
+            "--pad",
+            "1",
+            "--rotate",
+            "90",
+            "--transpose",
+            "--rotate",
+            "180",
+        ]);
+        let Some(Command::Transform(args)) = cli.command else {
+            panic!("transform parses as transform");
+        };
+
+        assert!(matches!(
+            args.transforms.0.as_slice(),
+            [
+                Transform::Pad([1, 1, 1, 1]),
+                Transform::Image(Symmetry::Rotate90),
+                Transform::Image(Symmetry::Transpose),
+                Transform::Image(Symmetry::Rotate180),
+            ]
+        ));
+    }
+}
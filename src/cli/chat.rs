@@ -0,0 +1,51 @@
+use std::process::exit;
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use snake::tiles::TileRegistry;
+use snake::{chat, solve_within, Outcome};
+
+use super::Options;
+
+#[derive(Args)]
+pub struct ChatArgs {
+    /// Write for Slack instead of Discord.
+    #[arg(long)]
+    slack: bool,
+    /// Draw the board in a code block instead of emoji.
+    #[arg(long)]
+    code: bool,
+}
+
+/// Read a chat message on stdin and print a reply with the board and a spoilered solution.
+pub fn chat_reply(options: &Options, args: ChatArgs) {
+    let platform = if args.slack {
+        chat::Platform::Slack
+    } else {
+        chat::Platform::Discord
+    };
+
+    let Some(board) = chat::parse_board(&options.read_input()) else {
+        println!("I couldn't find a board in that message.");
+        exit(1);
+    };
+
+    if args.code {
+        println!("{}", chat::code_board(&board));
+    } else {
+        println!("{}", chat::emoji_board(&board));
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    match solve_within(&board, Some(deadline), &TileRegistry::default()) {
+        Outcome {
+            solution: Some(solution),
+            ..
+        } => println!("{}", chat::solution(platform, &solution)),
+        Outcome {
+            timed_out: true, ..
+        } => println!("I gave up after 10 seconds."),
+        _ => println!("No solution found."),
+    }
+}
@@ -0,0 +1,85 @@
+use std::process::exit;
+use std::time::Duration;
+
+use clap::Args;
+use snake::{jsonl, server, worker};
+
+use super::seconds;
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Listen on ADDR.
+    #[arg(value_name = "ADDR", default_value = "127.0.0.1:8080")]
+    addr: String,
+    /// Give each solve at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    time_limit: Duration,
+}
+
+/// `serve [ADDR] [--time-limit SECS]` serves the solver over HTTP.
+pub fn serve_command(args: ServeArgs) {
+    if let Err(e) = server::serve(&args.addr, args.time_limit) {
+        eprintln!("Server error: {e}");
+        exit(1);
+    }
+}
+
+#[cfg(feature = "grpc")]
+#[derive(Args)]
+pub struct GrpcArgs {
+    /// Listen on ADDR.
+    #[arg(value_name = "ADDR", default_value = "127.0.0.1:50051")]
+    addr: String,
+    /// Give each request at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    time_limit: Duration,
+}
+
+/// `grpc [ADDR] [--time-limit SECS]` serves the solver over gRPC.
+#[cfg(feature = "grpc")]
+pub fn grpc_command(args: GrpcArgs) {
+    if let Err(e) = snake::grpc::serve(&args.addr, args.time_limit) {
+        eprintln!("Server error: {e}");
+        exit(1);
+    }
+}
+
+#[derive(Args)]
+pub struct JsonlArgs {
+    /// Give each solve at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    time_limit: Duration,
+}
+
+/// `--jsonl [--time-limit SECS]` answers JSON requests on stdin, one per line.
+pub fn jsonl_command(args: JsonlArgs) {
+    if let Err(e) = jsonl::run(args.time_limit) {
+        eprintln!("I/O error: {e}");
+        exit(1);
+    }
+}
+
+#[derive(Args)]
+pub struct WorkerArgs {
+    /// Take jobs from the queue in QUEUE_DIR.
+    #[arg(value_name = "QUEUE_DIR")]
+    dir: String,
+    /// Give each job at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    time_limit: Duration,
+    /// Keep waiting for new jobs instead of stopping when there are none.
+    #[arg(long)]
+    watch: bool,
+}
+
+/// `worker QUEUE_DIR [--time-limit SECS] [--watch]` solves jobs from a shared queue
+/// directory.
+pub fn worker_command(args: WorkerArgs) {
+    let result = worker::Queue::open(&args.dir)
+        .and_then(|queue| worker::run(&queue, args.time_limit, args.watch, Duration::from_secs(5)));
+
+    if let Err(e) = result {
+        eprintln!("Worker error: {e}");
+        exit(1);
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{stdin, Write};
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use clap::Args;
+use snake::analysis::Viability;
+use snake::session::Session;
+use snake::tiles::TileRegistry;
+use snake::widget::{BoardWidget, Theme};
+use snake::{Board, Position};
+
+use super::{parse_position, seconds, Options};
+
+/// What the `edit` screen waits on: a line typed, or a background check of some board
+/// finishing, which by then may no longer be the board on the screen.
+enum EditEvent {
+    Line(String),
+    Checked(Board, Viability),
+    Shortest(Board, Option<usize>),
+}
+
+#[derive(Args)]
+pub struct EditArgs {
+    /// Edit the board in FILE (or --input).
+    #[arg(value_name = "FILE")]
+    path: Option<String>,
+    /// Give each check at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    limit: Duration,
+}
+
+/// `edit FILE [--limit SECS]` edits the board a rock or cherry at a time, saying after every
+/// edit whether it can still be solved and, if so, how short a solution can be. Checks run
+/// in the background and each edit cancels the one before, so typing never waits on them.
+pub fn edit_command(options: &Options, args: EditArgs) {
+    let Some(path) = args.path.or(options.input.clone()) else {
+        eprintln!("Usage: snake edit FILE [--limit SECS]");
+        exit(2);
+    };
+
+    if args.limit.is_zero() {
+        eprintln!("--limit expects a number of seconds above zero");
+        exit(2);
+    }
+
+    let limit = args.limit;
+    let options = Options {
+        input: Some(path.clone()),
+        ..options.clone()
+    };
+    let mut session = Session::new(options.read_board(), TileRegistry::default());
+
+    let (events, received) = mpsc::channel();
+    let typed = events.clone();
+
+    thread::spawn(move || {
+        for line in stdin().lines().map_while(Result::ok) {
+            if typed.send(EditEvent::Line(line)).is_err() {
+                return;
+            }
+        }
+
+        let _ = typed.send(EditEvent::Line("q".to_string()));
+    });
+
+    let mut cancel = Arc::new(AtomicBool::new(false));
+    let mut viability = None;
+    let mut lengths: HashMap<Board, Option<usize>> = HashMap::new();
+    let mut edits: Vec<Position> = Vec::new();
+    let mut note = String::new();
+    let mut edited = true;
+
+    loop {
+        if edited {
+            edited = false;
+            cancel.store(true, AtomicOrdering::Relaxed);
+            cancel = Arc::new(AtomicBool::new(false));
+            viability = session.known();
+
+            let settled = match &viability {
+                Some(Viability::Solvable(_)) => lengths.contains_key(session.board()),
+                known => known.is_some(),
+            };
+
+            if !settled {
+                let check = session.check();
+                let known = viability.clone();
+                let cancel = Arc::clone(&cancel);
+                let events = events.clone();
+
+                thread::spawn(move || {
+                    let board = check.board().clone();
+                    let viability = known.unwrap_or_else(|| check.run(limit, &cancel));
+
+                    // Anything after an edit is about the wrong board
+                    if cancel.load(AtomicOrdering::Relaxed) {
+                        return;
+                    }
+
+                    let _ = events.send(EditEvent::Checked(board.clone(), viability.clone()));
+
+                    if let Viability::Solvable(solution) = viability {
+                        let shortest = check.shortest(solution, limit, &cancel);
+
+                        if !cancel.load(AtomicOrdering::Relaxed) {
+                            let _ = events
+                                .send(EditEvent::Shortest(board, shortest.map(|s| s.moves.len())));
+                        }
+                    }
+                });
+            }
+        }
+
+        show_edit(
+            session.board(),
+            viability.as_ref(),
+            lengths.get(session.board()),
+            limit,
+            &note,
+        );
+
+        let Ok(event) = received.recv() else {
+            return;
+        };
+
+        let line = match event {
+            EditEvent::Line(line) => line,
+            EditEvent::Checked(board, checked) => {
+                if board == *session.board() {
+                    viability = Some(checked.clone());
+                }
+
+                session.record(board, checked);
+                continue;
+            }
+            EditEvent::Shortest(board, length) => {
+                lengths.insert(board, length);
+                continue;
+            }
+        };
+
+        note.clear();
+
+        let mut words = line.split_whitespace();
+
+        match (words.next(), words.next()) {
+            (Some("q"), _) => return,
+            (Some("u"), _) => match edits.pop() {
+                Some(p) => {
+                    // Toggling is its own undo
+                    let _ = session.toggle(p);
+                    edited = true;
+                }
+                None => note = "There's nothing to undo.".to_string(),
+            },
+            (Some("w"), file) => {
+                let file = file.unwrap_or(&path);
+                let text = session.board().to_text(&TileRegistry::default()) + "\n";
+
+                note = match fs::write(file, text) {
+                    Ok(()) => format!("Wrote {file}."),
+                    Err(e) => format!("Couldn't write {file}: {e}"),
+                };
+            }
+            (Some("t"), Some(at)) | (Some(at), None) if parse_position(at).is_some() => {
+                let p = parse_position(at).expect("checked above");
+
+                match session.toggle(p) {
+                    Ok(()) => {
+                        edits.push(p);
+                        edited = true;
+                    }
+                    Err(e) => note = e,
+                }
+            }
+            (None, _) => {}
+            _ => note = format!("Unknown command {line:?}."),
+        }
+    }
+}
+
+/// The `edit` screen: the board with its columns and rows numbered, and what's known about
+/// it so far.
+fn show_edit(
+    board: &Board,
+    viability: Option<&Viability>,
+    shortest: Option<&Option<usize>>,
+    limit: Duration,
+    note: &str,
+) {
+    let rows = BoardWidget::new(board, &Theme::plain()).to_string();
+    let width = rows
+        .lines()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    print!("\x1b[2J\x1b[H");
+
+    println!(
+        "    {}",
+        (0..width).map(|x| (x % 10).to_string()).collect::<String>()
+    );
+
+    for (y, row) in rows.lines().enumerate() {
+        println!("{y:>3} {row}");
+    }
+
+    println!();
+
+    match viability {
+        None => println!("Checking..."),
+        Some(Viability::Unsolvable) => println!("Unsolvable."),
+        Some(Viability::Unknown) => {
+            println!("Unknown: no answer within {} s.", limit.as_secs_f64())
+        }
+        Some(Viability::Solvable(solution)) => {
+            let (x, y) = solution.start;
+
+            println!("Solvable from {x}, {y} in {} moves.", solution.moves.len());
+
+            match shortest {
+                None => println!("Looking for the shortest solution..."),
+                Some(Some(n)) => println!("The shortest solution takes {n} moves."),
+                Some(None) => println!(
+                    "No shortest solution found within {} s.",
+                    limit.as_secs_f64()
+                ),
+            }
+        }
+    }
+
+    if !note.is_empty() {
+        println!("\n{note}");
+    }
+
+    print!("\n[t] X,Y to toggle a rock, [u]ndo, [w]rite [FILE], [q]uit > ");
+    let _ = std::io::stdout().flush();
+}
@@ -0,0 +1,96 @@
+use std::fs;
+use std::io::IsTerminal;
+use std::process::exit;
+
+use clap::Subcommand;
+use snake::tiles::TileRegistry;
+use snake::widget::BoardWidget;
+use snake::{check, pddl, xsb, Position};
+
+use super::{position, print_solution, Options};
+
+#[derive(Subcommand)]
+pub enum PddlCommand {
+    /// Print the PDDL domain.
+    Domain,
+    /// Turn the board on stdin into a problem.
+    Problem,
+    /// Read a planner's plan in PLAN_FILE for the board on stdin back into a solution.
+    Plan {
+        #[arg(value_name = "PLAN_FILE")]
+        path: String,
+    },
+}
+
+/// `pddl domain` prints the domain, `pddl problem` turns the board on stdin into a problem,
+/// and `pddl plan FILE` reads a planner's plan for the board on stdin back into a solution.
+pub fn pddl_command(options: &Options, command: PddlCommand) {
+    match command {
+        PddlCommand::Domain => print!("{}", pddl::DOMAIN),
+        PddlCommand::Problem => match pddl::problem(&options.read_board(), "snake") {
+            Ok(problem) => print!("{problem}"),
+            Err(e) => {
+                eprintln!("{e}");
+                exit(1);
+            }
+        },
+        PddlCommand::Plan { path } => {
+            let plan = fs::read_to_string(&path).unwrap_or_else(|e| {
+                eprintln!("Couldn't read {path}: {e}");
+                exit(1);
+            });
+
+            let solution = pddl::parse_plan(&plan).unwrap_or_else(|e| {
+                eprintln!("Invalid plan: {e}");
+                exit(1);
+            });
+
+            print_solution(&solution);
+
+            if let Err(invalid) = check(
+                &options.read_board(),
+                solution.start,
+                &solution.moves,
+                &TileRegistry::default(),
+            ) {
+                println!("The plan does not solve the board: {invalid:?}");
+                exit(1);
+            }
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum XsbCommand {
+    /// Turn the XSB level on stdin into a board.
+    Import,
+    /// Turn the board on stdin into an XSB level.
+    Export {
+        /// Put the snake on X,Y in the level.
+        #[arg(long, value_name = "X,Y", value_parser = position)]
+        start: Option<Position>,
+    },
+}
+
+/// `xsb import` turns the XSB level on stdin into a board; `xsb export [--start X,Y]` does
+/// the reverse.
+pub fn xsb_command(options: &Options, command: XsbCommand) {
+    match command {
+        XsbCommand::Import => match xsb::import(&options.read_input()) {
+            Ok((board, player)) => {
+                let theme = options.theme(std::io::stdout().is_terminal());
+
+                println!("{}", BoardWidget::new(&board, &theme));
+
+                if let Some((x, y)) = player {
+                    eprintln!("Player starts at {x}, {y}");
+                }
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                exit(1);
+            }
+        },
+        XsbCommand::Export { start } => println!("{}", xsb::export(&options.read_board(), start)),
+    }
+}
@@ -0,0 +1,429 @@
+use std::cmp::Ordering;
+use std::io::{stdin, IsTerminal, Write};
+use std::process::exit;
+use std::time::Duration;
+
+use clap::Args;
+use snake::hint::{self, Hint};
+use snake::tiles::TileRegistry;
+use snake::widget::{BoardWidget, DiffWidget, Theme};
+use snake::{
+    analysis, api, check, debugger, json, puzzles, solve_within, Invalid, Position, Solution,
+};
+
+use super::{
+    fail, millis, parse_moves, position, replay, solution_args, Format, Options, Verbosity,
+};
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// The cherry to start from, the moves and the board, either as X,Y MOVES [FILE] or with
+    /// the solution as one argument, the way --compact writes it.
+    #[arg(value_names = ["X,Y", "MOVES", "FILE"], num_args = 1..=3, required = true)]
+    solution: Vec<String>,
+}
+
+/// `verify X,Y MOVES [FILE]` checks that playing `MOVES`, written as letters like `LDRU`,
+/// from `X,Y` solves the board.
+pub fn verify_command(options: &Options, args: VerifyArgs) {
+    let Some((Solution { start, moves }, rest)) = solution_args(&args.solution) else {
+        eprintln!("Usage: snake verify X,Y MOVES [FILE]");
+        exit(2);
+    };
+
+    let options = Options {
+        input: rest.first().cloned().or(options.input.clone()),
+        ..options.clone()
+    };
+    let board = options.read_board();
+    let tiles = TileRegistry::default();
+    let result = check(&board, start, &moves, &tiles);
+
+    if options.format == Format::Json {
+        let mut fields = vec![("valid", result.is_ok().into())];
+
+        if let Err(invalid) = &result {
+            fields.push(("reason", format!("{invalid:?}").into()));
+
+            match *invalid {
+                Invalid::BadStart(_) => {}
+                Invalid::Stuck(i, dir) => {
+                    fields.push(("move", i.into()));
+                    fields.push(("dir", dir.letter().to_string().into()));
+                }
+                Invalid::Incomplete(n) => fields.push(("cherries_left", n.into())),
+            }
+        }
+
+        println!("{}", json::Json::obj(fields));
+    } else {
+        match &result {
+            Ok(()) => println!("The moves solve the board."),
+            Err(Invalid::BadStart((x, y))) => {
+                println!("{x}, {y} is not a cherry or the snake's head.")
+            }
+            Err(Invalid::Stuck(i, dir)) => {
+                println!("Move {i} ({dir:?}) doesn't go anywhere. The board before it:\n");
+
+                // Every move before it went somewhere, so replaying them can't fail
+                let before = board.place_snake(start).apply_moves(&moves[..*i], &tiles);
+
+                if let Ok(before) = before {
+                    let theme = options.theme(std::io::stdout().is_terminal());
+
+                    println!("{}", BoardWidget::new(&before, &theme));
+                }
+            }
+            Err(Invalid::Incomplete(n)) => println!("{n} cherries are left after the last move."),
+        }
+    }
+
+    if result.is_err() {
+        exit(1);
+    }
+}
+
+#[derive(Args)]
+pub struct HintArgs {
+    /// The game so far and the board, either as X,Y MOVES [FILE] or with the moves as one
+    /// argument, the way --compact writes them.
+    #[arg(value_names = ["X,Y", "MOVES", "FILE"], num_args = 1..=3, required = true)]
+    solution: Vec<String>,
+    /// Search for at most MS milliseconds.
+    #[arg(long, value_name = "MS", value_parser = millis, default_value = "200")]
+    time: Duration,
+    /// Expand at most N states.
+    #[arg(long, value_name = "N", default_value_t = 100_000)]
+    states: usize,
+}
+
+/// `hint X,Y MOVES [FILE] [--time MS] [--states N]` suggests the next move after playing
+/// `MOVES` from `X,Y`, within a strict budget.
+pub fn hint_command(options: &Options, args: HintArgs) {
+    let budget = hint::Budget {
+        time: args.time,
+        states: args.states,
+    };
+
+    let Some((Solution { start, moves }, rest)) = solution_args(&args.solution) else {
+        eprintln!("Usage: snake hint X,Y MOVES [FILE] [--time MS] [--states N]");
+        exit(2);
+    };
+
+    let options = Options {
+        input: rest.first().cloned().or(options.input.clone()),
+        ..options.clone()
+    };
+    let board = options.read_board();
+    let tiles = TileRegistry::default();
+
+    let Some(state) = board.start_at(start) else {
+        eprintln!(
+            "{}, {} is not a cherry or the snake's head.",
+            start.0, start.1
+        );
+        exit(1);
+    };
+
+    let state = state.apply_moves(&moves, &tiles).unwrap_or_else(|e| {
+        if let Invalid::Stuck(i, dir) = e {
+            eprintln!("Move {i} ({dir:?}) doesn't go anywhere.");
+        }
+
+        exit(1);
+    });
+
+    match hint::hint(&state, budget, &tiles) {
+        Hint::Optimal(dir, n) => println!("{dir:?}: the shortest way to finish takes {n} moves."),
+        Hint::Greedy(dir, n) => println!("{dir:?}: this finishes in {n} moves, if not the fewest."),
+        Hint::Nudge(dir) => println!("{dir:?}, maybe: it cuts off the fewest cherries."),
+        Hint::Lost => println!("There's no way to finish from here."),
+        Hint::Done => println!("Every cherry is eaten."),
+    }
+}
+
+#[derive(Args)]
+pub struct DebugArgs {
+    /// Search the board in FILE (or --input).
+    #[arg(value_name = "FILE")]
+    path: Option<String>,
+    /// Record at most N steps.
+    #[arg(long, value_name = "N", default_value_t = 10_000)]
+    limit: usize,
+}
+
+/// `debug-search FILE [--limit N]` records up to `N` steps of the search of the board in
+/// `FILE` and steps through them on the terminal, showing the queue and visited set at
+/// each. Commands come from stdin, which is why the board can't.
+pub fn debug_command(options: &Options, args: DebugArgs) {
+    let Some(path) = args.path.or(options.input.clone()) else {
+        eprintln!("Usage: snake debug-search FILE [--limit N]");
+        exit(2);
+    };
+
+    let options = Options {
+        input: Some(path),
+        ..options.clone()
+    };
+    let board = options.read_board();
+    let recording = debugger::record(&board, &TileRegistry::default(), args.limit);
+
+    if recording.steps.is_empty() {
+        println!("There's nothing to search.");
+        return;
+    }
+
+    let last = recording.steps.len() - 1;
+    let mut step = 0;
+    let mut lines = stdin().lines();
+
+    loop {
+        show_step(
+            &recording,
+            step,
+            &options.theme(std::io::stdout().is_terminal()),
+        );
+
+        print!("\n[n]ext, [p]revious, [g]o N, [s]tart, [e]nd, [q]uit > ");
+        let _ = std::io::stdout().flush();
+
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return;
+        };
+
+        let mut words = line.split_whitespace();
+
+        step = match words.next().unwrap_or("n") {
+            "n" => (step + 1).min(last),
+            "p" => step.saturating_sub(1),
+            "g" => match words.next().and_then(|n| n.parse().ok()) {
+                Some(n) => usize::min(n, last),
+                None => step,
+            },
+            "s" => 0,
+            "e" => last,
+            "q" => return,
+            _ => step,
+        };
+    }
+}
+
+/// One step of a recorded search: the state expanded, where each move went, and the queue
+/// and visited set afterwards.
+fn show_step(recording: &debugger::Recording, i: usize, theme: &Theme) {
+    let step = &recording.steps[i];
+    let depth = |s: usize| recording.depths[s];
+    let head = |s: usize| {
+        recording.states[s]
+            .get_snake_head()
+            .map_or("-".to_string(), |(x, y)| format!("{x},{y}"))
+    };
+
+    // Clear the screen, so that stepping looks like animation
+    print!("\x1b[2J\x1b[H");
+
+    let (x, y) = step.start;
+
+    println!(
+        "Step {i} of {}{}, from the start at {x}, {y}",
+        recording.steps.len() - 1,
+        if recording.truncated {
+            " (cut short)"
+        } else {
+            ""
+        }
+    );
+    println!(
+        "Expanding state {} at depth {}\n",
+        step.state,
+        depth(step.state)
+    );
+    println!(
+        "{}\n",
+        BoardWidget::new(&recording.states[step.state], theme)
+    );
+
+    if step.solved {
+        println!("Every cherry is eaten: this is the solution.");
+    }
+
+    for &(dir, to, new) in &step.moves {
+        let outcome = match (new, to == step.state) {
+            (true, _) => "new",
+            (false, true) => "stuck",
+            (false, false) => "seen",
+        };
+
+        println!("  {dir:?} -> {} {outcome} (state {to})", head(to));
+    }
+
+    let queue = recording.queue(i);
+
+    println!("\nQueue ({} states):", queue.len());
+
+    for s in queue.clone().take(8) {
+        println!("  state {s}: head {}, depth {}", head(s), depth(s));
+    }
+
+    if queue.len() > 8 {
+        println!("  ...");
+    }
+
+    println!("Visited: {} states", recording.visited(i).len());
+}
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// Where the first solution starts.
+    #[arg(value_name = "X,Y", value_parser = position)]
+    a_start: Position,
+    /// The first solution's moves.
+    #[arg(value_name = "MOVES")]
+    a_moves: String,
+    /// Where the second solution starts.
+    #[arg(value_name = "X,Y", value_parser = position)]
+    b_start: Position,
+    /// The second solution's moves.
+    #[arg(value_name = "MOVES")]
+    b_moves: String,
+}
+
+/// `diff-solutions X,Y MOVES X,Y MOVES` compares two solutions of the board, such as a
+/// player's and the solver's: how long each is, how far they agree, and where they part,
+/// drawn on the board.
+pub fn diff_command(options: &Options, args: DiffArgs) {
+    let a = Solution {
+        start: args.a_start,
+        moves: parse_moves(&args.a_moves),
+    };
+    let b = Solution {
+        start: args.b_start,
+        moves: parse_moves(&args.b_moves),
+    };
+
+    let board = options.read_board();
+    let tiles = TileRegistry::default();
+    let diff = analysis::diff(&board, &a, &b, &tiles);
+    let valid = |s: &Solution| check(&board, s.start, &s.moves, &tiles).is_ok();
+
+    if options.format == Format::Json {
+        let side = |s: &Solution, trail: &[Position]| {
+            json::Json::obj([
+                ("start", api::position_json(s.start)),
+                ("move_count", s.moves.len().into()),
+                ("valid", valid(s).into()),
+                (
+                    "trail",
+                    trail
+                        .iter()
+                        .map(|&p| api::position_json(p))
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+            ])
+        };
+
+        let json = json::Json::obj([
+            ("a", side(&a, &diff.trails.0)),
+            ("b", side(&b, &diff.trails.1)),
+            ("shared", diff.shared.into()),
+            ("fork", diff.fork.map(api::position_json).into()),
+            ("overlap", diff.overlap.into()),
+        ]);
+
+        println!("{json}");
+        return;
+    }
+
+    for (name, s) in [("A", &a), ("B", &b)] {
+        let (x, y) = s.start;
+        let verdict = match valid(s) {
+            true => "",
+            false => " (doesn't solve the board)",
+        };
+
+        println!("{name}: {} moves from {x}, {y}{verdict}", s.moves.len());
+    }
+
+    match diff.fork {
+        None if a.start != b.start => println!("They start on different cherries."),
+        None => println!("They're the same."),
+        Some((x, y)) => {
+            let next = |s: &Solution| match s.moves.get(diff.shared) {
+                Some(d) => format!("goes {d:?}"),
+                None => "stops".to_string(),
+            };
+
+            println!(
+                "They share the first {} moves, then part at {x}, {y}: A {}, B {}.",
+                diff.shared,
+                next(&a),
+                next(&b)
+            );
+        }
+    }
+
+    match a.moves.len().cmp(&b.moves.len()) {
+        Ordering::Less => println!("A is {} moves shorter.", b.moves.len() - a.moves.len()),
+        Ordering::Greater => println!("B is {} moves shorter.", a.moves.len() - b.moves.len()),
+        Ordering::Equal => println!("They're the same length."),
+    }
+
+    println!("{:.0}% of their paths overlap.", diff.overlap * 100.0);
+    println!();
+    println!(
+        "{}",
+        DiffWidget::new(&board, &diff.trails.0, &diff.trails.1, diff.fork)
+    );
+
+    if options.verbosity() > Verbosity::Quiet {
+        eprintln!("= both, a only A, b only B, * where they part");
+    }
+}
+
+#[derive(Args)]
+pub struct DemoArgs {
+    /// The puzzle to play, one of those `snake demo` lists.
+    #[arg(value_name = "NAME")]
+    name: Option<String>,
+    /// Wait MS milliseconds between moves.
+    #[arg(long, value_name = "MS", value_parser = millis, default_value = "400")]
+    delay: Duration,
+}
+
+/// `demo` lists the built-in puzzles, and `demo NAME [--delay MS]` solves one and plays
+/// the solution move by move.
+pub fn demo_command(options: &Options, args: DemoArgs) {
+    let Some(name) = args.name else {
+        for p in puzzles::PUZZLES {
+            println!("{:<12} {}", p.name, p.about);
+        }
+
+        if options.verbosity() > Verbosity::Quiet {
+            eprintln!("\nTry one with: snake demo NAME");
+        }
+
+        return;
+    };
+
+    let Some(puzzle) = puzzles::find(&name) else {
+        eprintln!("There's no puzzle called {name}; `snake demo` lists them.");
+        exit(2);
+    };
+
+    let tiles = TileRegistry::default();
+    let board = puzzle.board(&tiles).unwrap_or_else(|e| fail(e));
+    let Some(solution) = solve_within(&board, None, &tiles).solution else {
+        eprintln!("The {name} puzzle has no solution.");
+        exit(1);
+    };
+
+    if options.format == Format::Json {
+        return println!("{}", serde_json::json!(solution));
+    }
+
+    let heading = format!("{name}: {}\n", puzzle.about);
+
+    replay(&board, &solution, args.delay, &heading, options, &tiles);
+}
@@ -0,0 +1,321 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::process::exit;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+use snake::cache::Cache;
+use snake::id::BoardId;
+use snake::{batch, compare, dataset, json};
+
+use super::{seconds, Format, Options};
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// Solve every board in DIR.
+    #[arg(value_name = "DIR")]
+    dir: String,
+    /// Solve N boards at once (one a CPU by default).
+    #[arg(long, value_name = "N")]
+    jobs: Option<usize>,
+    /// Give each board at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    time_limit: Duration,
+    /// Write the totals and every result to FILE, for compare-runs.
+    #[arg(long, value_name = "FILE")]
+    report: Option<String>,
+    /// Search again even for boards solved before.
+    #[arg(long)]
+    no_cache: bool,
+}
+
+/// `batch DIR [--jobs N] [--time-limit SECS] [--report FILE]` solves every board in `DIR`
+/// in parallel, printing each result as a JSON line as it finishes and writing a report
+/// with the totals and every result to `FILE`.
+pub fn batch_command(args: BatchArgs) {
+    let BatchArgs {
+        dir,
+        jobs,
+        time_limit,
+        report,
+        no_cache,
+    } = args;
+    let jobs = jobs.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+    let cache = Cache::default_dir().filter(|_| !no_cache).map(Cache::open);
+
+    let totals = batch::run(
+        dir.as_ref(),
+        jobs,
+        time_limit,
+        cache.as_ref(),
+        &mut std::io::stdout().lock(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Couldn't solve {dir}: {e}");
+        exit(1);
+    });
+
+    for key in ["boards", "solved", "unsolvable", "timed_out", "errors"] {
+        let n = totals.get(key).and_then(json::Json::as_u64).unwrap_or(0);
+
+        eprintln!("{:<11} {n}", format!("{}:", key.replace('_', " ")));
+    }
+
+    if let Some(path) = &report {
+        if let Err(e) = fs::write(path, format!("{totals}\n")) {
+            eprintln!("Couldn't write report to {path}: {e}");
+            exit(1);
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct DatasetArgs {
+    /// Export every board in DIR.
+    #[arg(value_name = "DIR")]
+    dir: String,
+    /// Write to FILE instead of stdout.
+    #[arg(long, value_name = "FILE")]
+    out: Option<String>,
+    /// Draw every board on a W by H grid (the largest board's by default).
+    #[arg(long, value_name = "WxH", value_parser = grid)]
+    size: Option<(usize, usize)>,
+    /// Give each board at most SECS seconds.
+    #[arg(long, value_name = "SECS", value_parser = seconds, default_value = "10")]
+    time_limit: Duration,
+}
+
+/// A `--size` like `8x8`.
+fn grid(s: &str) -> Result<(usize, usize), String> {
+    s.split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .ok_or_else(|| "expected a width and height like 8x8".to_string())
+}
+
+/// `export-dataset DIR [--out FILE] [--size WxH] [--time-limit SECS]` writes a row for
+/// every move of the shortest solution of every board in `DIR`, as
+/// [`snake::dataset::export`] describes, to `FILE` or stdout.
+pub fn dataset_command(args: DatasetArgs) {
+    let DatasetArgs {
+        dir,
+        out,
+        size,
+        time_limit,
+    } = args;
+
+    let summary = match out {
+        Some(path) => File::create(path)
+            .and_then(|f| dataset::export(dir.as_ref(), time_limit, size, &mut BufWriter::new(f))),
+        None => dataset::export(
+            dir.as_ref(),
+            time_limit,
+            size,
+            &mut std::io::stdout().lock(),
+        ),
+    }
+    .unwrap_or_else(|e| {
+        eprintln!("Couldn't export {dir}: {e}");
+        exit(1);
+    });
+
+    for (name, reason) in &summary.skipped {
+        eprintln!("Skipped {name}: {reason}");
+    }
+
+    eprintln!(
+        "Wrote {} moves from {} of {} boards.",
+        summary.rows, summary.solved, summary.boards
+    );
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// List the cached results.
+    List,
+    /// Remove the results for each board ID.
+    Remove {
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<BoardId>,
+    },
+    /// Remove every cached result.
+    Clear,
+    /// Print where the cache is.
+    Path,
+}
+
+/// `cache (list | remove ID... | clear | path)` looks after the results `solve` and `batch`
+/// keep, in `$SNAKE_CACHE_DIR` or the user's cache directory.
+pub fn cache_command(command: CacheCommand) {
+    let Some(cache) = Cache::default_dir().map(Cache::open) else {
+        eprintln!("There's nowhere to keep a cache: set SNAKE_CACHE_DIR or HOME");
+        exit(1);
+    };
+
+    let dir = cache.dir().display().to_string();
+    let fail = |e: std::io::Error| -> ! {
+        eprintln!("Couldn't use the cache in {dir}: {e}");
+        exit(1);
+    };
+
+    match command {
+        CacheCommand::List => {
+            let entries = cache.entries().unwrap_or_else(|e| fail(e));
+
+            for e in &entries {
+                let result = match e.moves {
+                    Some(n) => format!("solved in {n} moves"),
+                    None => "unsolvable".to_string(),
+                };
+
+                println!(
+                    "{} {:<12} {result:<20} {:>10.1} ms {:>10} states",
+                    e.id, e.order, e.elapsed_ms, e.states
+                );
+            }
+
+            eprintln!("{} cached results.", entries.len());
+        }
+        CacheCommand::Remove { ids } => {
+            for id in ids {
+                match cache.remove(id).unwrap_or_else(|e| fail(e)) {
+                    0 => eprintln!("Nothing cached for {id}."),
+                    n => eprintln!("Removed {n} results for {id}."),
+                }
+            }
+        }
+        CacheCommand::Clear => {
+            let n = cache.clear().unwrap_or_else(|e| fail(e));
+
+            eprintln!("Removed {n} cached results.");
+        }
+        CacheCommand::Path => println!("{dir}"),
+    }
+}
+
+#[derive(Args)]
+pub struct CompareArgs {
+    /// The report of the first run.
+    #[arg(value_name = "BEFORE")]
+    before: String,
+    /// The report of the second run.
+    #[arg(value_name = "AFTER")]
+    after: String,
+    /// Flag boards whose time changed by more than PCT percent.
+    #[arg(long, value_name = "PCT", default_value_t = 10.0)]
+    threshold: f64,
+    /// Only list the boards that were flagged.
+    #[arg(long)]
+    changed: bool,
+}
+
+/// `compare-runs BEFORE AFTER [--threshold PCT] [--changed]` compares two saved batch runs,
+/// board by board and in total, flagging boards whose time changed by more than `PCT`
+/// percent (10 by default) and any that stopped or started being solved.
+pub fn compare_command(options: &Options, args: CompareArgs) {
+    let read = |path: &String| {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| compare::parse_run(&text))
+            .unwrap_or_else(|e| {
+                eprintln!("Couldn't read {path}: {e}");
+                exit(1);
+            })
+    };
+
+    let comparison = compare::compare(
+        read(&args.before),
+        read(&args.after),
+        args.threshold / 100.0,
+    );
+
+    if options.format == Format::Json {
+        return println!("{}", comparison.to_json());
+    }
+
+    let changed_only = args.changed;
+    let name_width = comparison
+        .pairs
+        .iter()
+        .map(|p| p.after.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let states = |n: Option<u64>| n.map_or("-".to_string(), |n| n.to_string());
+    let ms = |n: Option<f64>| n.map_or("-".to_string(), |n| format!("{n:.1}"));
+
+    println!(
+        "{:<name_width$} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}  change",
+        "board", "states", "after", "+/-", "ms", "after", "+/-"
+    );
+
+    for p in comparison
+        .pairs
+        .iter()
+        .filter(|p| !changed_only || p.change.is_significant())
+    {
+        let change = match p.change {
+            compare::Change::Lost => "LOST".to_string(),
+            compare::Change::Gained => "gained".to_string(),
+            compare::Change::Slower(r) => format!("{:.0}% slower", (r - 1.0) * 100.0),
+            compare::Change::Faster(r) => format!("{:.0}% faster", (1.0 - r) * 100.0),
+            compare::Change::Same => String::new(),
+        };
+
+        let row = format!(
+            "{:<name_width$} {:>10} {:>10} {:>+10} {:>10} {:>10} {:>+10.1}  {change}",
+            p.after.name,
+            states(p.before.states),
+            states(p.after.states),
+            p.states_delta().unwrap_or(0),
+            ms(p.before.elapsed_ms),
+            ms(p.after.elapsed_ms),
+            p.elapsed_delta().unwrap_or(0.0),
+        );
+
+        println!("{}", row.trim_end());
+    }
+
+    let totals = comparison.totals();
+    let count =
+        |f: fn(&compare::Change) -> bool| comparison.pairs.iter().filter(|p| f(&p.change)).count();
+
+    println!();
+    println!(
+        "Boards in both runs: {}, {} solved before and {} after.",
+        totals.boards, totals.solved_before, totals.solved_after
+    );
+    println!(
+        "States: {} before, {} after ({:+}).",
+        totals.states_before,
+        totals.states_after,
+        totals.states_after as i64 - totals.states_before as i64
+    );
+    println!(
+        "Time: {:.1} ms before, {:.1} ms after ({:+.1} ms).",
+        totals.elapsed_before_ms,
+        totals.elapsed_after_ms,
+        totals.elapsed_after_ms - totals.elapsed_before_ms
+    );
+
+    if let Some(ratio) = totals.mean_ratio {
+        println!("Each board took {ratio:.2}x as long, on average.");
+    }
+
+    println!(
+        "{} slower, {} faster, {} no longer solved, {} newly solved.",
+        count(|c| matches!(c, compare::Change::Slower(_))),
+        count(|c| matches!(c, compare::Change::Faster(_))),
+        count(|c| *c == compare::Change::Lost),
+        count(|c| *c == compare::Change::Gained),
+    );
+
+    for (names, run) in [
+        (&comparison.only_before, "first"),
+        (&comparison.only_after, "second"),
+    ] {
+        if !names.is_empty() {
+            println!("Only in the {run} run: {}", names.join(", "));
+        }
+    }
+}
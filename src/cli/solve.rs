@@ -0,0 +1,1051 @@
+use std::fs::{self, File};
+use std::io::{stderr, BufWriter, IsTerminal, Write};
+use std::num::NonZeroUsize;
+use std::process::exit;
+use std::time::{Duration, Instant};
+
+use clap::{Args, FromArgMatches};
+use snake::cache::Cache;
+use snake::events::EventLog;
+use snake::graph::SearchGraph;
+use snake::greedy::Heuristic;
+use snake::hashing::HashKind;
+#[cfg(feature = "image")]
+use snake::image::{self, Palette};
+use snake::objective::{self, Objective};
+use snake::order::StartOrder;
+use snake::progress::ProgressBar;
+use snake::tiles::TileRegistry;
+use snake::tune::{self, Algorithm};
+use snake::widget::{BoardWidget, HeatmapWidget};
+use snake::{
+    analysis, api, astar, bytes, compress, greedy, ida, json, parallel, profile, solve_traced,
+    steps, symmetry, Board, Constraints, Event, Outcome, Position, ProgressSink, SearchStats,
+    SnakeError, Solution, StartStats, Trace,
+};
+
+use super::{
+    millis, position, print_moves, print_solution, replay, seconds, size, Format, Options,
+    Verbosity,
+};
+
+/// Solve by `options`' rules, with a progress bar on stderr unless they ask for quiet,
+/// optionally tracing every move tried and logging every search event.
+fn solve(
+    board: &Board,
+    constraints: Constraints,
+    order: StartOrder,
+    deadline: Option<Instant>,
+    trace: Option<&mut dyn Trace>,
+    mut events: Option<&mut EventLog<impl Write>>,
+    options: &Options,
+) -> Outcome {
+    let quiet = options.verbosity() == Verbosity::Quiet;
+    let tiles = options.registry();
+    let mut bar = (!quiet).then(|| ProgressBar::new(board, constraints, &tiles));
+    let outcome = solve_traced(
+        board,
+        constraints,
+        order,
+        deadline,
+        &tiles,
+        &mut |e: Event| {
+            if let Some(log) = events.as_mut() {
+                log.report(e.clone());
+            }
+
+            bar.report(e)
+        },
+        trace,
+    );
+
+    if let Some(bar) = &bar {
+        bar.finish();
+    }
+
+    outcome
+}
+
+/// The peak resident set size of this process, where the OS reports it.
+fn peak_rss() -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+
+    Some(kb * 1024)
+}
+
+fn print_report(outcome: &Outcome) {
+    let stats = &outcome.stats;
+
+    eprintln!("Search report");
+    eprintln!("  wall time:          {:.3} s", stats.elapsed.as_secs_f64());
+    eprintln!("  states expanded:    {}", outcome.states);
+    eprintln!("  starts tried:       {}", stats.starts.len());
+
+    if stats.starts.len() > 1 {
+        print_start_table(&stats.starts);
+    }
+
+    if let Some(depth) = stats.depth {
+        eprintln!("  solution depth:     {depth}");
+    }
+
+    if let Some(b) = stats.branching_factor() {
+        eprintln!("  branching factor:   {b:.3}");
+    }
+
+    eprintln!("  duplicates pruned:  {}", stats.duplicates);
+    eprintln!("  stranded pruned:    {}", stats.stranded);
+    eprintln!("  peak queue:         {}", stats.peak_queue);
+    eprintln!(
+        "  search memory:      {} (peak estimate)",
+        bytes(stats.peak_memory)
+    );
+
+    if let Some(rss) = peak_rss() {
+        eprintln!("  peak RSS:           {}", bytes(rss));
+    }
+}
+
+/// How far a search that was cut short got: the states it explored, the starts it ruled
+/// out, and the starts it never reached.
+fn print_coverage(board: &Board, outcome: &Outcome) {
+    let starts = &outcome.stats.starts;
+    let cell = |s: &&StartStats| format!("{}, {}", s.start.0, s.start.1);
+
+    println!("Explored {} states.", outcome.states);
+
+    // Only the breadth-first searches keep track of their starts
+    if starts.is_empty() {
+        return;
+    }
+
+    let exhausted: Vec<_> = starts
+        .iter()
+        .filter(|s| !s.solved && !s.timed_out)
+        .map(|s| cell(&s))
+        .collect();
+    let cut_short: Vec<_> = starts
+        .iter()
+        .filter(|s| s.timed_out && !s.out_of_memory)
+        .map(|s| cell(&s))
+        .collect();
+    let too_big: Vec<_> = starts
+        .iter()
+        .filter(|s| s.out_of_memory)
+        .map(|s| cell(&s))
+        .collect();
+
+    match exhausted.len() {
+        0 => println!("No start was ruled out."),
+        n => println!("Ruled out {n} starts: {}.", exhausted.join("; ")),
+    }
+
+    if !cut_short.is_empty() {
+        println!("Cut short from {}.", cut_short.join("; "));
+    }
+
+    if !too_big.is_empty() {
+        println!("Ran out of memory from {}.", too_big.join("; "));
+    }
+
+    let symmetries = symmetry::pruning(board);
+    let distinct = board
+        .starts()
+        .filter(|&p| symmetry::is_canonical(p, &symmetries, board))
+        .count();
+
+    match distinct.saturating_sub(starts.len()) {
+        0 => {}
+        n => println!("Never reached {n} more starts."),
+    }
+}
+
+/// Why a `--shortest` solution is the shortest: how far every start was searched in vain.
+fn print_certificate(stats: &SearchStats) {
+    eprintln!("\nNo solution exists in fewer moves:");
+
+    for s in &stats.starts {
+        let (x, y) = s.start;
+
+        match analysis::no_solution_within(s) {
+            Some(n) => eprintln!("  from {x}, {y}: none within {n} moves"),
+            None => eprintln!("  from {x}, {y}: not checked"),
+        }
+    }
+}
+
+/// One row per starting position tried, so designers can see which starts are viable and
+/// how hard each one is.
+fn print_start_table(starts: &[StartStats]) {
+    eprintln!();
+    eprintln!(
+        "  {:<9} {:<10} {:>10} {:>10} {:>6} {:>10}",
+        "start", "result", "nodes", "time", "depth", "explored"
+    );
+
+    for s in starts {
+        let result = match (s.solved, s.timed_out) {
+            (true, _) => "solved",
+            (false, true) if s.out_of_memory => "no memory",
+            (false, true) => "timed out",
+            (false, false) => "dead end",
+        };
+
+        eprintln!(
+            "  {:<9} {result:<10} {:>10} {:>8.1}ms {:>6} {:>9.3}%",
+            format!("{}, {}", s.start.0, s.start.1),
+            s.expanded,
+            s.elapsed.as_secs_f64() * 1000.0,
+            s.depth,
+            s.explored() * 100.0
+        );
+    }
+
+    eprintln!();
+}
+
+/// Like [`print_moves`], but with runs of one direction on one line as `R×4`.
+fn print_compressed(
+    Solution {
+        start: (x, y),
+        moves,
+    }: &Solution,
+) {
+    println!("Place snake at {x}, {y}");
+
+    let runs: Vec<String> = compress(moves)
+        .into_iter()
+        .map(|(d, n)| match n {
+            1 => d.letter().to_string(),
+            n => format!("{}×{n}", d.letter()),
+        })
+        .collect();
+
+    println!("{}", runs.join(" "));
+}
+
+fn print_cost(board: &Board, solution: &Solution, tiles: &TileRegistry) {
+    let cells = objective::cells(board, solution, tiles);
+    let turns = objective::turns(&solution.moves);
+
+    println!("{cells} cells, {turns} turns.");
+}
+
+/// Every flag of `solve`, which is also what runs without a command.
+#[derive(Args, Clone)]
+#[command(next_help_heading = "Solve options")]
+pub struct SolveArgs {
+    /// Solve the board in each FILE, or on stdin without any. A file may hold several
+    /// boards, separated by blank lines.
+    #[arg(value_name = "FILE")]
+    files: Vec<String>,
+    /// Search again even if the board was solved before, as --stats and --heatmap do.
+    #[arg(long)]
+    no_cache: bool,
+    /// Give up solving after SECS seconds and say how far the search got.
+    #[arg(long, value_name = "SECS", value_parser = seconds)]
+    timeout: Option<Duration>,
+    /// Print the search report even with --quiet.
+    #[arg(long)]
+    stats: bool,
+    /// Write solutions on one line as X,Y and the moves' letters, as verify reads them.
+    #[arg(long)]
+    compact: bool,
+    /// Write the solution a cell at a time instead of a slide at a time.
+    #[arg(long)]
+    steps: bool,
+    /// Write runs of the same move as counts, like R×3.
+    #[arg(long)]
+    compress: bool,
+    /// Say why each move of the solution was chosen over the others.
+    #[arg(long)]
+    explain: bool,
+    /// Shorten the solution found by replacing stretches of it with shorter lines between
+    /// the same states.
+    #[arg(long)]
+    optimize: bool,
+    /// Replay the solution move by move, --delay apart.
+    #[arg(long)]
+    animate: bool,
+    /// Wait MS milliseconds between the moves of --animate and the steps of --export gif.
+    #[arg(long, value_name = "MS", value_parser = millis, default_value = "400")]
+    delay: Duration,
+    /// Draw how many states the search expanded with the head on each cell.
+    #[arg(long)]
+    heatmap: bool,
+    /// bfs (the default), astar, ida or greedy.
+    #[arg(long, value_name = "A")]
+    algorithm: Option<Algorithm>,
+    /// For greedy: cherries (the default), lines or dead-ends; for astar: lines (the
+    /// default) or zero.
+    #[arg(long, value_name = "H")]
+    heuristic: Option<String>,
+    /// Search from N starts at once on N threads.
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    jobs: usize,
+    /// Try starts row-major, center-out, most-open or random[:SEED], which otherwise is
+    /// picked from a look at the board.
+    #[arg(long, value_name = "O")]
+    start_order: Option<StartOrder>,
+    /// Hash the states seen with std or fx, which otherwise is picked from a look at the
+    /// board.
+    #[arg(long, value_name = "H")]
+    hasher: Option<HashKind>,
+    /// Don't pick the start order, hasher and memory cap from the board.
+    #[arg(long)]
+    no_tune: bool,
+    /// Keep searching states that have stranded cherries, to show what pruning saves.
+    #[arg(long)]
+    no_prune: bool,
+    /// Give up after expanding N states.
+    #[arg(long, value_name = "N")]
+    max_states: Option<usize>,
+    /// Drop any start whose search outgrows SIZE, like 512M or 2G.
+    #[arg(long, value_name = "SIZE", value_parser = size)]
+    max_memory: Option<usize>,
+    /// Only accept solutions that finish with the head on X,Y.
+    #[arg(long, value_name = "X,Y", value_parser = position)]
+    end: Option<Position>,
+    /// Keep searching until no shorter solution can exist, and say why.
+    #[arg(long)]
+    shortest: bool,
+    /// Find the solution with the most moves instead of the fewest.
+    #[arg(long)]
+    longest: bool,
+    /// Find the solution cheapest in moves, turns, cells, or a weighted mix of them written
+    /// MOVES,CELLS,TURNS.
+    #[arg(long, value_name = "O")]
+    objective: Option<Objective>,
+    /// List every solution no other beats in both cells and turns.
+    #[arg(long)]
+    pareto: bool,
+    /// Eat as many cherries as possible in at most N moves.
+    #[arg(long, value_name = "N")]
+    budget: Option<usize>,
+    /// Write every move the search tries to FILE, one per line.
+    #[arg(long, value_name = "FILE")]
+    trace: Option<String>,
+    /// Write every search event to FILE as JSON lines.
+    #[arg(long, value_name = "FILE")]
+    events: Option<String>,
+    /// Write the states the search visited and the moves between them to FILE as a
+    /// Graphviz graph, at most --max-nodes of them.
+    #[arg(long, value_name = "FILE")]
+    export_search_graph: Option<String>,
+    /// Stop --export-search-graph at N states.
+    #[arg(long, value_name = "N")]
+    max_nodes: Option<usize>,
+    /// Write the time spent in each phase to FILE as folded stacks, for flamegraphs
+    /// (profile feature).
+    #[arg(long, value_name = "FILE")]
+    profile: Option<String>,
+    /// Draw the board and the solution's path to an svg FILE, each step of it as
+    /// DIR/step-000.png and on, or the solution as an animated gif FILE (png and gif need
+    /// the image feature).
+    #[arg(long, num_args = 2, value_names = ["svg|png|gif", "PATH"])]
+    export: Option<Vec<String>>,
+    /// Colours for png and gif: light, dark or mono, then any of background, rock, cherry,
+    /// body, head, custom set like cherry=#ff8800.
+    #[arg(long, value_name = "P")]
+    palette: Option<String>,
+    /// Pixels a cell for png and gif.
+    #[arg(long, value_name = "N", default_value = "24")]
+    cell_size: NonZeroUsize,
+}
+
+impl Default for SolveArgs {
+    /// What `solve` does without any flags.
+    fn default() -> Self {
+        let matches = Self::augment_args(clap::Command::new("solve")).get_matches_from(["solve"]);
+
+        Self::from_arg_matches(&matches).expect("Every flag of solve is optional")
+    }
+}
+
+/// `solve [FILE...]` (or no command at all) solves the board in each file, or on stdin
+/// without any, with flags choosing how.
+pub fn solve_command(options: &Options, args: &SolveArgs) {
+    let sources: Vec<Option<&String>> = match options.input.iter().chain(&args.files).collect() {
+        v if Vec::is_empty(&v) => vec![None],
+        v => v.into_iter().map(Some).collect(),
+    };
+
+    // Each source may hold several boards, separated by blank lines
+    let mut boards = Vec::new();
+
+    for source in sources {
+        let name = source.map_or("stdin", String::as_str);
+        let options = Options {
+            input: source.cloned(),
+            ..options.clone()
+        };
+
+        match options.read_boards() {
+            Ok(found) if found.is_empty() => boards.push((
+                name.to_string(),
+                Err(SnakeError::InvalidState("there's no board".to_string())),
+            )),
+            Ok(found) if found.len() == 1 => {
+                boards.extend(found.into_iter().map(|b| (name.to_string(), b)))
+            }
+            Ok(found) => boards.extend(
+                found
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, b)| (format!("{name} #{}", i + 1), b)),
+            ),
+            Err(e) => boards.push((name.to_string(), Err(e))),
+        }
+    }
+
+    if let [(name, board)] = boards.as_slice() {
+        match board {
+            Ok(board) => solve_board(options, args, board),
+            Err(e) => {
+                eprintln!("{name}: {e}");
+                exit(1);
+            }
+        };
+
+        return;
+    }
+
+    let (mut solved, mut errors) = (0, 0);
+
+    for (i, (name, board)) in boards.iter().enumerate() {
+        if options.format == Format::Text {
+            if i > 0 {
+                println!();
+            }
+
+            println!("==> {name} <==");
+        }
+
+        match board {
+            Ok(board) => solved += usize::from(solve_board(options, args, board)),
+            Err(e) => {
+                eprintln!("{name}: {e}");
+                errors += 1;
+            }
+        }
+    }
+
+    let unsolved = boards.len() - solved - errors;
+
+    if options.format == Format::Json {
+        let summary = json::Json::obj([
+            ("boards", boards.len().into()),
+            ("solved", solved.into()),
+            ("unsolved", unsolved.into()),
+            ("errors", errors.into()),
+        ]);
+
+        println!("{summary}");
+    } else {
+        println!();
+        println!(
+            "{solved} of {} boards solved, {unsolved} unsolved, {errors} unreadable.",
+            boards.len()
+        );
+    }
+
+    if errors > 0 {
+        exit(1);
+    }
+}
+
+/// Write `solution` to `path` the way `--export` asks: as an SVG drawing, a directory of
+/// PNGs with one for each step, or an animated GIF showing each step for `--delay`.
+fn export_solution(
+    kind: &str,
+    path: &str,
+    board: &Board,
+    solution: &Solution,
+    tiles: &TileRegistry,
+    args: &SolveArgs,
+) -> std::io::Result<()> {
+    match kind {
+        "svg" => fs::write(path, snake::svg::render(board, solution, tiles)),
+        #[cfg(feature = "image")]
+        "gif" => {
+            let (palette, cell) = image_style(args);
+
+            fs::write(
+                path,
+                image::gif(board, solution, &palette, cell, args.delay, tiles),
+            )
+        }
+        #[cfg(feature = "image")]
+        _ => {
+            let (palette, cell) = image_style(args);
+
+            fs::create_dir_all(path)?;
+
+            for (i, png) in image::png_frames(board, solution, &palette, cell, tiles)
+                .into_iter()
+                .enumerate()
+            {
+                fs::write(
+                    std::path::Path::new(path).join(format!("step-{i:03}.png")),
+                    png,
+                )?;
+            }
+
+            Ok(())
+        }
+        #[cfg(not(feature = "image"))]
+        _ => {
+            let _ = args;
+
+            unreachable!("--export {kind} is refused without the image feature")
+        }
+    }
+}
+
+/// The `--palette` and `--cell-size` to draw images with, exiting if the palette doesn't
+/// parse.
+#[cfg(feature = "image")]
+fn image_style(args: &SolveArgs) -> (Palette, usize) {
+    let palette = args
+        .palette
+        .as_deref()
+        .map_or(Ok(Palette::default()), str::parse);
+    let palette = palette.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(2);
+    });
+
+    (palette, args.cell_size.get())
+}
+
+/// Solve `board` with the flags of [`solve_command`]. Returns whether it was solved.
+fn solve_board(options: &Options, args: &SolveArgs, board: &Board) -> bool {
+    let quiet = options.verbosity() == Verbosity::Quiet;
+    let tiles = options.registry();
+
+    if args.profile.is_some() && !cfg!(feature = "profile") {
+        eprintln!("--profile needs snake to be built with the profile feature");
+        exit(2);
+    }
+
+    let mut trace = args.trace.as_ref().map(|path| {
+        File::create(path).map(BufWriter::new).unwrap_or_else(|e| {
+            eprintln!("Couldn't create {path}: {e}");
+            exit(1);
+        })
+    });
+
+    let graph_path = args.export_search_graph.as_ref();
+    let mut graph = graph_path.map(|_| SearchGraph::new(args.max_nodes));
+
+    if trace.is_some() && graph.is_some() {
+        eprintln!("--trace and --export-search-graph can't be used together");
+        exit(2);
+    }
+
+    let mut events = args.events.as_ref().map(|path| {
+        File::create(path)
+            .map(|f| EventLog::new(BufWriter::new(f)))
+            .unwrap_or_else(|e| {
+                eprintln!("Couldn't create {path}: {e}");
+                exit(1);
+            })
+    });
+
+    let (objective, end, max_states, budget) =
+        (args.objective, args.end, args.max_states, args.budget);
+
+    let export = args.export.as_deref().map(|export| match export {
+        [kind, path] if ["svg", "png", "gif"].contains(&kind.as_str()) => {
+            (kind.as_str(), path.as_str())
+        }
+        _ => {
+            eprintln!("--export expects svg, png or gif and where to write it");
+            exit(2);
+        }
+    });
+
+    if export.is_some_and(|(kind, _)| kind != "svg") && !cfg!(feature = "image") {
+        eprintln!("--export png and gif need snake to be built with the image feature");
+        exit(2);
+    }
+
+    #[cfg(feature = "image")]
+    if export.is_some_and(|(kind, _)| kind != "svg") {
+        image_style(args);
+    }
+
+    let jobs = args.jobs;
+
+    if jobs > 1 && (trace.is_some() || graph.is_some() || events.is_some()) {
+        eprintln!("--trace, --export-search-graph and --events only work with one job");
+        exit(2);
+    }
+
+    // Only the default search by the usual rules is cached, and not when anything asks to
+    // watch it run or for its stats, which a cached result doesn't keep
+    let cache = (args.start_order.is_none()
+        && end.is_none()
+        && max_states.is_none()
+        && args.trace.is_none()
+        && graph_path.is_none()
+        && args.events.is_none()
+        && args.profile.is_none()
+        && !(args.no_cache || args.no_tune || args.stats || args.heatmap))
+        .then(Cache::default_dir)
+        .flatten()
+        .filter(|_| tiles.hooks().is_none())
+        .map(Cache::open);
+
+    let algorithm = args.algorithm;
+
+    if args.heuristic.is_some() && !matches!(algorithm, Some(Algorithm::Greedy | Algorithm::AStar))
+    {
+        eprintln!("--heuristic only works with --algorithm greedy or astar");
+        exit(2);
+    }
+
+    let heuristic = args
+        .heuristic
+        .as_ref()
+        .filter(|_| algorithm == Some(Algorithm::Greedy))
+        .map(|h| {
+            h.parse::<Heuristic>().unwrap_or_else(|e| {
+                eprintln!("{e}");
+                exit(2);
+            })
+        });
+    let estimate = args
+        .heuristic
+        .as_ref()
+        .filter(|_| algorithm == Some(Algorithm::AStar))
+        .map(|h| {
+            h.parse::<astar::Builtin>().unwrap_or_else(|e| {
+                eprintln!("{e}");
+                exit(2);
+            })
+        });
+
+    let other_search = args.pareto
+        || args.shortest
+        || args.longest
+        || algorithm.is_some_and(|a| a != Algorithm::Bfs)
+        || budget.is_some();
+
+    if (end.is_some() || max_states.is_some() || args.max_memory.is_some())
+        && (objective.is_some() || other_search)
+    {
+        eprintln!("--end, --max-states and --max-memory only work with the default search");
+        exit(2);
+    }
+
+    if options.verbosity() == Verbosity::Verbose {
+        eprintln!("Board {}", board.id());
+        eprintln!(
+            "{}\n",
+            BoardWidget::new(board, &options.theme(stderr().is_terminal()))
+        );
+    }
+
+    // The default search fills in whatever the flags leave open from a look at the board
+    let tuning =
+        (objective.is_none() && !other_search && !args.no_tune).then(|| tune::tune(board, &tiles));
+
+    let algorithm = algorithm.unwrap_or_default();
+    let order = args
+        .start_order
+        .or(tuning.map(|t| t.order))
+        .unwrap_or_default();
+    let hasher = args.hasher.or(tuning.map(|t| t.hasher)).unwrap_or_default();
+    // The fallback only takes over starts the tuned memory cap dropped, so it's left out
+    // whenever the cap is the user's or the search is split into jobs
+    let fallback = tuning
+        .filter(|_| args.max_memory.is_none() && jobs == 1)
+        .and_then(|t| t.fallback);
+    let max_memory = args.max_memory.or(tuning.and_then(|t| t.max_memory));
+
+    if let (Some(seed), false) = (order.seed(), quiet) {
+        eprintln!("Seed: {seed} (repeat with --start-order random:{seed})");
+    }
+
+    if let (Some(_), Verbosity::Verbose) = (tuning, options.verbosity()) {
+        match (max_memory, fallback) {
+            (Some(m), Some(f)) => eprintln!(
+                "Trying starts {order:?} hashed with {hasher}, searching any that outgrows {} again with {f}.\n",
+                bytes(m)
+            ),
+            _ => eprintln!("Trying starts {order:?} hashed with {hasher}.\n"),
+        }
+    }
+
+    let constraints = Constraints {
+        end,
+        max_states,
+        max_memory,
+        no_prune: args.no_prune,
+        hasher,
+        ..Constraints::default()
+    };
+
+    let deadline = args.timeout.map(|t| Instant::now() + t);
+
+    if args.pareto {
+        let (solutions, timed_out) = objective::pareto(board, deadline, &tiles);
+
+        options.check_script();
+
+        if solutions.is_empty() {
+            println!("No solution found.");
+        }
+
+        for (i, (cost, solution)) in solutions.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+
+            print_solution(solution);
+            println!("{} cells, {} turns.", cost.cells, cost.turns);
+        }
+
+        if timed_out {
+            eprintln!("The search was cut short, so there may be more.");
+        }
+
+        return !solutions.is_empty();
+    }
+
+    if let Some(budget) = budget {
+        let (best, timed_out) = objective::most_eaten(board, budget, deadline, &tiles);
+
+        options.check_script();
+        let total = board.cherry_count();
+        let eaten = best.as_ref().map_or(0, |b| total - b.cherries_left);
+
+        if options.format == Format::Json {
+            let mut fields = vec![
+                ("eaten", eaten.into()),
+                ("cherries", total.into()),
+                ("budget", budget.into()),
+            ];
+
+            if let Some(best) = &best {
+                fields.push(("start", api::position_json(best.solution.start)));
+                fields.push(("moves", api::moves_json(&best.solution.moves)));
+            }
+
+            fields.push(("timed_out", timed_out.into()));
+            println!("{}", json::Json::obj(fields));
+        } else if let Some(best) = &best {
+            println!(
+                "Eats {eaten} of {total} cherries in {} moves.",
+                best.solution.moves.len()
+            );
+            print_moves(&best.solution);
+        } else {
+            println!("There's nowhere to start.");
+        }
+
+        if timed_out {
+            eprintln!("The search was cut short, so more may be possible.");
+        }
+
+        return best.is_some_and(|b| b.cherries_left == 0);
+    }
+
+    let shortest = args.shortest;
+
+    let mut cached = false;
+    let mut outcome = match objective {
+        Some(objective) => objective::solve_optimal(board, deadline, &tiles, objective),
+        None if shortest => {
+            let mut search = analysis::improving(board, deadline, &tiles);
+
+            for s in search.by_ref().filter(|_| !quiet) {
+                eprintln!(
+                    "Found {} moves from {}, {}, looking for shorter...",
+                    s.moves.len(),
+                    s.start.0,
+                    s.start.1
+                );
+            }
+
+            search.finish()
+        }
+        None if algorithm == Algorithm::AStar => {
+            astar::solve_astar_with(board, deadline, &tiles, &estimate.unwrap_or_default())
+        }
+        None if algorithm == Algorithm::Ida => ida::solve_ida(board, deadline, &tiles),
+        None if algorithm == Algorithm::Greedy => {
+            greedy::solve_greedy(board, heuristic.unwrap_or_default(), deadline, &tiles)
+        }
+        None if args.longest => analysis::longest(board, deadline, &tiles),
+        None if jobs > 1 => {
+            parallel::solve_parallel(board, constraints, order, deadline, &tiles, jobs)
+        }
+        None => match cache.as_ref().and_then(|c| c.get(board, order)) {
+            Some(outcome) => {
+                cached = true;
+                outcome
+            }
+            None => {
+                let outcome = solve(
+                    board,
+                    constraints,
+                    order,
+                    deadline,
+                    match (&mut trace, &mut graph) {
+                        (Some(t), _) => Some(t as &mut dyn Trace),
+                        (_, Some(g)) => Some(g as &mut dyn Trace),
+                        _ => None,
+                    },
+                    events.as_mut(),
+                    options,
+                );
+
+                if let Some(Err(e)) = cache.as_ref().map(|c| c.put(board, order, &outcome)) {
+                    eprintln!("Couldn't cache the result: {e}");
+                }
+
+                outcome
+            }
+        },
+    };
+
+    let dropped: Vec<Position> = outcome
+        .stats
+        .starts
+        .iter()
+        .filter(|s| s.out_of_memory)
+        .map(|s| s.start)
+        .collect();
+
+    if let (Some(Algorithm::Ida), None, false) = (fallback, &outcome.solution, dropped.is_empty()) {
+        if !quiet {
+            eprintln!(
+                "{} starts outgrew memory, so searching them again depth-first.",
+                dropped.len()
+            );
+        }
+
+        let retried = ida::solve_ida_from(board, &dropped, deadline, &tiles);
+
+        outcome.states += retried.states;
+        outcome.timed_out = retried.timed_out;
+        outcome.stats.depth = retried.stats.depth;
+
+        match retried.solution {
+            Some(solution) => {
+                outcome.solution = Some(solution);
+                outcome.partial = None;
+            }
+            None => {
+                if retried.partial.as_ref().is_some_and(|r| {
+                    outcome
+                        .partial
+                        .as_ref()
+                        .is_none_or(|p| r.cherries_left < p.cherries_left)
+                }) {
+                    outcome.partial = retried.partial;
+                }
+            }
+        }
+    }
+
+    options.check_script();
+
+    if cached && !quiet {
+        eprintln!(
+            "Solved before, so this is the cached result, without a search report; --no-cache \
+             searches again."
+        );
+    }
+
+    if let Some(Err(e)) = trace.as_mut().map(BufWriter::flush) {
+        eprintln!("Couldn't write trace: {e}");
+    }
+
+    if let (Some(graph), Some(path)) = (&graph, graph_path) {
+        let written = File::create(path).and_then(|f| {
+            let mut out = BufWriter::new(f);
+
+            graph.write_dot(&mut out)?;
+            out.flush()
+        });
+
+        if let Err(e) = written {
+            eprintln!("Couldn't write the search graph to {path}: {e}");
+        } else if graph.dropped() > 0 && !quiet {
+            eprintln!(
+                "The search graph stops at {} states, leaving out {} moves.",
+                graph.nodes(),
+                graph.dropped()
+            );
+        }
+    }
+
+    if let Some(Err(e)) = events.map(EventLog::finish) {
+        eprintln!("Couldn't write event log: {e}");
+    }
+
+    if let Some(path) = &args.profile {
+        if let Err(e) = profile::write_folded(path.as_ref()) {
+            eprintln!("Couldn't write profile to {path}: {e}");
+            exit(1);
+        }
+    }
+
+    if args.optimize {
+        if let Some(solution) = &mut outcome.solution {
+            let optimized = analysis::optimize(board, solution, 8, &tiles);
+
+            if optimized.moves.len() < solution.moves.len() && !quiet {
+                eprintln!(
+                    "Optimized from {} to {} moves.",
+                    solution.moves.len(),
+                    optimized.moves.len()
+                );
+            }
+
+            *solution = optimized;
+        }
+    }
+
+    if let Some((kind, path)) = export {
+        match &outcome.solution {
+            Some(solution) => {
+                if let Err(e) = export_solution(kind, path, board, solution, &tiles, args) {
+                    eprintln!("Couldn't write {path}: {e}");
+                    exit(1);
+                }
+            }
+            None if !quiet => eprintln!("No solution to export to {path}."),
+            None => {}
+        }
+    }
+
+    if options.format == Format::Json {
+        let elapsed = outcome.stats.elapsed;
+
+        let mut result = api::solve_result(board, &tiles, &outcome, elapsed);
+
+        if let (json::Json::Obj(fields), Some(seed)) = (&mut result, order.seed()) {
+            fields.push(("seed".to_string(), seed.into()));
+        }
+
+        if let (json::Json::Obj(fields), true) = (&mut result, cached) {
+            fields.push(("cached".to_string(), true.into()));
+        }
+
+        println!("{result}");
+        return outcome.solution.is_some();
+    }
+
+    if let Some(solution) = &outcome.solution {
+        let shown = match args.steps {
+            true => Solution {
+                start: solution.start,
+                moves: steps(board, solution, &tiles),
+            },
+            false => solution.clone(),
+        };
+
+        println!("Solution found in {} moves.", solution.moves.len());
+
+        if args.compress {
+            print_compressed(&shown);
+        } else if args.compact {
+            println!("{shown}");
+        } else {
+            print_moves(&shown);
+        }
+
+        if args.explain {
+            let explanations = analysis::explain(board, solution, Duration::from_secs(10), &tiles);
+
+            println!();
+            println!("Why each move:");
+
+            for (i, (d, alternatives)) in solution.moves.iter().zip(&explanations).enumerate() {
+                println!("{i:2}. {d:?}: {}", analysis::describe(alternatives));
+            }
+        }
+
+        if objective.is_some() {
+            print_cost(board, solution, &tiles);
+        }
+
+        if args.animate {
+            println!();
+            replay(board, solution, args.delay, "", options, &tiles);
+        }
+    } else {
+        let out_of_time = deadline.is_some_and(|d| Instant::now() >= d);
+
+        match (args.timeout, max_states) {
+            (Some(t), _) if outcome.timed_out && out_of_time => println!(
+                "No solution found within {} s; this is a best-effort answer.",
+                t.as_secs_f64()
+            ),
+            (_, Some(n)) if outcome.timed_out && outcome.states > n => {
+                println!("No solution found within {n} states; this is a best-effort answer.")
+            }
+            _ if outcome.timed_out && max_memory.is_some() => println!(
+                "No solution found within {} of memory; this is a best-effort answer.",
+                bytes(max_memory.unwrap_or_default())
+            ),
+            _ => println!("No solution found."),
+        }
+
+        if let Some(partial) = &outcome.partial {
+            let cherries = board.cherry_count();
+
+            println!();
+
+            match (partial.cherries_left, end) {
+                (0, Some((x, y))) => {
+                    println!("The best attempt eats every cherry but doesn't end at {x}, {y}:")
+                }
+                (0, None) => println!("The best attempt solves it, but may not be the longest:"),
+                _ => println!(
+                    "The best attempt eats {} of {cherries} cherries:",
+                    cherries - partial.cherries_left
+                ),
+            }
+
+            match args.compact {
+                true => println!("{}", partial.solution),
+                false => print_moves(&partial.solution),
+            }
+        }
+
+        if outcome.timed_out {
+            println!();
+            print_coverage(board, &outcome);
+        }
+    }
+
+    // A cached result has no stats to report, and says so above
+    if !cached && (!quiet || args.stats) {
+        print_report(&outcome);
+    }
+
+    if !quiet {
+        if shortest {
+            print_certificate(&outcome.stats);
+        }
+
+        if args.heatmap {
+            let heatmap = HeatmapWidget::new(
+                board,
+                &outcome.stats.heat,
+                !options.no_color && stderr().is_terminal(),
+            );
+
+            eprintln!("\nStates per head position:\n{heatmap}");
+        }
+    }
+
+    outcome.solution.is_some()
+}
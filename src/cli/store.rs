@@ -0,0 +1,115 @@
+use std::fs;
+use std::process::exit;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Subcommand};
+use snake::progress::ProgressBar;
+use snake::tiles::TileRegistry;
+use snake::{solve_watched, store, Board, Constraints};
+
+#[derive(Args)]
+pub struct StoreArgs {
+    /// Keep the store in the SQLite file PATH.
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = "snake-store.db",
+        global = true
+    )]
+    db: String,
+    #[command(subcommand)]
+    command: StoreCommand,
+}
+
+#[derive(Subcommand)]
+enum StoreCommand {
+    /// Solve the board in each FILE and record how it went.
+    Add {
+        #[arg(value_name = "FILE", required = true)]
+        paths: Vec<String>,
+    },
+    /// List the latest record of each board.
+    List,
+    /// List the boards whose latest attempt didn't solve them.
+    Unsolved,
+    /// List the N fastest solves.
+    Fastest {
+        #[arg(value_name = "N", default_value_t = 10)]
+        n: usize,
+    },
+}
+
+/// `store [--db PATH] (add FILE... | list | unsolved | fastest [N])`: solve puzzles into
+/// the store and query it.
+pub fn store_command(args: StoreArgs) {
+    let db = &args.db;
+    let fail = |e: rusqlite::Error| -> ! {
+        eprintln!("Couldn't use store {db}: {e}");
+        exit(1);
+    };
+
+    let store = store::Store::open(db).unwrap_or_else(|e| fail(e));
+
+    let show = |records: Vec<store::Record>| {
+        for r in records {
+            let result = match (r.solved, r.timed_out) {
+                (true, _) => format!("solved in {} moves", r.moves.len()),
+                (false, true) => "timed out".to_string(),
+                (false, false) => "unsolvable".to_string(),
+            };
+
+            println!(
+                "{} {:<30} {result:<20} {:>10.1} ms {:>10} states",
+                r.id, r.name, r.elapsed_ms, r.states
+            );
+        }
+    };
+
+    match args.command {
+        StoreCommand::Add { paths } => {
+            for path in paths {
+                let text = fs::read_to_string(&path).unwrap_or_else(|e| {
+                    eprintln!("Couldn't read {path}: {e}");
+                    exit(1);
+                });
+
+                let board = match Board::new(&text) {
+                    Ok(board) => board,
+                    Err(e) => {
+                        eprintln!("Skipping {path}: {e}");
+                        continue;
+                    }
+                };
+                let tiles = TileRegistry::default();
+                let mut bar = ProgressBar::new(&board, Constraints::default(), &tiles);
+                let started = Instant::now();
+                let deadline = started + Duration::from_secs(60);
+                let outcome = solve_watched(&board, Some(deadline), &tiles, &mut bar);
+                let elapsed = started.elapsed();
+
+                bar.finish();
+
+                let record = store::Record {
+                    id: board.id(),
+                    name: path,
+                    board: text.trim().to_string(),
+                    solved: outcome.solution.is_some(),
+                    timed_out: outcome.timed_out,
+                    start: outcome.solution.as_ref().map(|s| s.start),
+                    moves: outcome.solution.map(|s| s.moves).unwrap_or_default(),
+                    states: outcome.states,
+                    elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+                    recorded_at: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs()),
+                };
+
+                store.add(&record).unwrap_or_else(|e| fail(e));
+                show(vec![record]);
+            }
+        }
+        StoreCommand::List => show(store.latest().unwrap_or_else(|e| fail(e))),
+        StoreCommand::Unsolved => show(store.unsolved().unwrap_or_else(|e| fail(e))),
+        StoreCommand::Fastest { n } => show(store.fastest(n).unwrap_or_else(|e| fail(e))),
+    }
+}
@@ -0,0 +1,40 @@
+use std::process::exit;
+use std::time::Duration;
+
+use clap::Args;
+use snake::{solve_within, window};
+
+use super::{millis, Options};
+
+#[derive(Args)]
+pub struct WindowArgs {
+    /// Take MS milliseconds over each cell the snake moves.
+    #[arg(long, value_name = "MS", value_parser = millis, default_value = "180")]
+    delay: Duration,
+}
+
+/// `window [--delay MS]`: solve the board and play the solution in a window.
+pub fn window_command(options: &Options, args: WindowArgs) {
+    // Rather than let the windowing library panic when it finds none
+    #[cfg(target_os = "linux")]
+    if ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .all(|v| std::env::var_os(v).is_none())
+    {
+        eprintln!("There's no display to open a window on.");
+        exit(1);
+    }
+
+    let tiles = options.registry();
+    let board = options.read_board();
+    let outcome = solve_within(&board, None, &tiles);
+
+    options.check_script();
+
+    let Some(solution) = outcome.solution else {
+        eprintln!("No solution found.");
+        exit(1);
+    };
+
+    window::play(&board, &solution, &tiles, args.delay.as_secs_f64());
+}
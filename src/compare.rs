@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use crate::json::Json;
+
+/// What one batch run recorded for one board.
+#[derive(Debug, Clone, Default)]
+pub struct BoardRun {
+    pub name: String,
+    /// See [`crate::Board::id`]. Runs from before ids were recorded don't have one.
+    pub id: Option<String>,
+    pub solved: bool,
+    pub error: bool,
+    pub moves: Option<u64>,
+    pub states: Option<u64>,
+    pub elapsed_ms: Option<f64>,
+}
+
+impl BoardRun {
+    fn from_json(json: &Json) -> Option<Self> {
+        let number = |key| match json.get(key) {
+            Some(Json::Num(n)) => Some(*n),
+            _ => None,
+        };
+
+        Some(Self {
+            name: json.get("board")?.as_str()?.to_string(),
+            id: json.get("id").and_then(Json::as_str).map(str::to_string),
+            solved: json.get("solved").and_then(Json::as_bool) == Some(true),
+            error: json.get("error").is_some(),
+            moves: json.get("move_count").and_then(Json::as_u64),
+            states: json.get("states").and_then(Json::as_u64),
+            elapsed_ms: number("elapsed_ms"),
+        })
+    }
+
+    /// A row of a CSV file with a header naming its columns: `board`, and any of `id`,
+    /// `solved`, `error`, `move_count`, `states` and `elapsed_ms`.
+    fn from_row(header: &[String], row: &[String]) -> Option<Self> {
+        let field = |key| {
+            header
+                .iter()
+                .position(|h| h == key)
+                .and_then(|i| row.get(i))
+                .filter(|v| !v.is_empty())
+        };
+
+        Some(Self {
+            name: field("board")?.clone(),
+            id: field("id").cloned(),
+            solved: field("solved").is_some_and(|v| v == "true" || v == "1"),
+            error: field("error").is_some(),
+            moves: field("move_count").and_then(|v| v.parse().ok()),
+            states: field("states").and_then(|v| v.parse().ok()),
+            elapsed_ms: field("elapsed_ms").and_then(|v| v.parse().ok()),
+        })
+    }
+}
+
+/// Read the results of a batch run, whichever way they were saved: the `--report` file,
+/// the JSON lines `batch` prints, or a CSV export with a header row.
+pub fn parse_run(text: &str) -> Result<Vec<BoardRun>, String> {
+    let text = text.trim();
+
+    if text.starts_with('{') {
+        let lines = match Json::parse(text) {
+            Ok(report) => match report.get("results").and_then(Json::as_array) {
+                Some(results) => results.to_vec(),
+                None => vec![report],
+            },
+            Err(_) => text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(i, line)| Json::parse(line).map_err(|e| format!("line {}: {e}", i + 1)))
+                .collect::<Result<_, _>>()?,
+        };
+
+        return lines
+            .iter()
+            .enumerate()
+            .map(|(i, json)| {
+                BoardRun::from_json(json).ok_or(format!("result {} has no board", i + 1))
+            })
+            .collect();
+    }
+
+    let mut rows = text.lines().map(csv_row);
+    let header = rows.next().ok_or("there are no results")?;
+
+    if !header.iter().any(|h| h == "board") {
+        return Err("the CSV header has no board column".to_string());
+    }
+
+    rows.enumerate()
+        .map(|(i, row)| {
+            BoardRun::from_row(&header, &row).ok_or(format!("row {} has no board", i + 2))
+        })
+        .collect()
+}
+
+/// The fields of one CSV line. Fields may be quoted, with `""` for a quote inside one.
+fn csv_row(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("there's always a field");
+
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+
+    fields.iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// How a board fared in the second run compared with the first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    /// Solved before but not after.
+    Lost,
+    /// Solved after but not before.
+    Gained,
+    /// Took longer by more than the threshold, as a ratio of after to before.
+    Slower(f64),
+    Faster(f64),
+    Same,
+}
+
+impl Change {
+    pub fn is_significant(self) -> bool {
+        self != Change::Same
+    }
+}
+
+/// The same board in two runs.
+#[derive(Debug, Clone)]
+pub struct Pair {
+    pub before: BoardRun,
+    pub after: BoardRun,
+    pub change: Change,
+}
+
+impl Pair {
+    pub fn states_delta(&self) -> Option<i64> {
+        Some(self.after.states? as i64 - self.before.states? as i64)
+    }
+
+    pub fn elapsed_delta(&self) -> Option<f64> {
+        Some(self.after.elapsed_ms? - self.before.elapsed_ms?)
+    }
+}
+
+pub struct Comparison {
+    /// Boards in both runs, in the order of the first.
+    pub pairs: Vec<Pair>,
+    pub only_before: Vec<String>,
+    pub only_after: Vec<String>,
+}
+
+/// Timings under this many milliseconds are too short to call faster or slower.
+const NOISE_MS: f64 = 1.0;
+
+/// Pair up the boards of two runs, by id where both runs recorded one and by name
+/// otherwise, and say which changed by more than `threshold`, a fraction of the time taken.
+pub fn compare(before: Vec<BoardRun>, after: Vec<BoardRun>, threshold: f64) -> Comparison {
+    let mut after: Vec<Option<BoardRun>> = after.into_iter().map(Some).collect();
+    let by_id: HashMap<String, usize> = after
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| Some((r.as_ref()?.id.clone()?, i)))
+        .collect();
+    let by_name: HashMap<String, usize> = after
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| Some((r.as_ref()?.name.clone(), i)))
+        .collect();
+
+    let mut pairs = Vec::new();
+    let mut only_before = Vec::new();
+
+    for b in before {
+        let found =
+            b.id.as_ref()
+                .and_then(|id| by_id.get(id))
+                .or_else(|| by_name.get(&b.name))
+                .and_then(|&i| after[i].take());
+
+        match found {
+            Some(a) => pairs.push(Pair {
+                change: change(&b, &a, threshold),
+                before: b,
+                after: a,
+            }),
+            None => only_before.push(b.name),
+        }
+    }
+
+    Comparison {
+        pairs,
+        only_before,
+        only_after: after.into_iter().flatten().map(|r| r.name).collect(),
+    }
+}
+
+fn change(before: &BoardRun, after: &BoardRun, threshold: f64) -> Change {
+    match (before.solved, after.solved) {
+        (true, false) => return Change::Lost,
+        (false, true) => return Change::Gained,
+        _ => {}
+    }
+
+    let (Some(b), Some(a)) = (before.elapsed_ms, after.elapsed_ms) else {
+        return Change::Same;
+    };
+
+    if b.max(a) < NOISE_MS || b <= 0.0 {
+        return Change::Same;
+    }
+
+    match a / b {
+        ratio if ratio > 1.0 + threshold => Change::Slower(ratio),
+        ratio if ratio < 1.0 / (1.0 + threshold) => Change::Faster(ratio),
+        _ => Change::Same,
+    }
+}
+
+/// Totals over the boards in both runs.
+#[derive(Debug, Clone, Default)]
+pub struct Totals {
+    pub boards: usize,
+    pub solved_before: usize,
+    pub solved_after: usize,
+    pub states_before: u64,
+    pub states_after: u64,
+    pub elapsed_before_ms: f64,
+    pub elapsed_after_ms: f64,
+    /// The geometric mean of the after-to-before time ratios, which unlike the totals
+    /// isn't dominated by the slowest boards.
+    pub mean_ratio: Option<f64>,
+}
+
+impl Comparison {
+    pub fn totals(&self) -> Totals {
+        let mut totals = Totals {
+            boards: self.pairs.len(),
+            ..Totals::default()
+        };
+        let mut logs = Vec::new();
+
+        for p in &self.pairs {
+            totals.solved_before += usize::from(p.before.solved);
+            totals.solved_after += usize::from(p.after.solved);
+            totals.states_before += p.before.states.unwrap_or(0);
+            totals.states_after += p.after.states.unwrap_or(0);
+            totals.elapsed_before_ms += p.before.elapsed_ms.unwrap_or(0.0);
+            totals.elapsed_after_ms += p.after.elapsed_ms.unwrap_or(0.0);
+
+            if let (Some(b), Some(a)) = (p.before.elapsed_ms, p.after.elapsed_ms) {
+                if b > 0.0 && a > 0.0 {
+                    logs.push((a / b).ln());
+                }
+            }
+        }
+
+        totals.mean_ratio =
+            (!logs.is_empty()).then(|| (logs.iter().sum::<f64>() / logs.len() as f64).exp());
+
+        totals
+    }
+
+    pub fn to_json(&self) -> Json {
+        let number = |n: Option<f64>| n.map_or(Json::Null, Json::Num);
+        let totals = self.totals();
+
+        let boards = self
+            .pairs
+            .iter()
+            .map(|p| {
+                let (change, ratio) = match p.change {
+                    Change::Lost => ("lost", None),
+                    Change::Gained => ("gained", None),
+                    Change::Slower(r) => ("slower", Some(r)),
+                    Change::Faster(r) => ("faster", Some(r)),
+                    Change::Same => ("same", None),
+                };
+
+                Json::obj([
+                    ("board", p.after.name.as_str().into()),
+                    ("change", change.into()),
+                    ("ratio", number(ratio)),
+                    ("solved_before", p.before.solved.into()),
+                    ("solved_after", p.after.solved.into()),
+                    (
+                        "states_before",
+                        p.before.states.map_or(Json::Null, Json::from),
+                    ),
+                    (
+                        "states_after",
+                        p.after.states.map_or(Json::Null, Json::from),
+                    ),
+                    ("elapsed_before_ms", number(p.before.elapsed_ms)),
+                    ("elapsed_after_ms", number(p.after.elapsed_ms)),
+                ])
+            })
+            .collect();
+
+        let names = |names: &[String]| Json::Arr(names.iter().map(|n| n.as_str().into()).collect());
+
+        Json::obj([
+            ("boards", totals.boards.into()),
+            ("solved_before", totals.solved_before.into()),
+            ("solved_after", totals.solved_after.into()),
+            ("states_before", totals.states_before.into()),
+            ("states_after", totals.states_after.into()),
+            ("elapsed_before_ms", totals.elapsed_before_ms.into()),
+            ("elapsed_after_ms", totals.elapsed_after_ms.into()),
+            ("mean_ratio", number(totals.mean_ratio)),
+            ("results", Json::Arr(boards)),
+            ("only_before", names(&self.only_before)),
+            ("only_after", names(&self.only_after)),
+        ])
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::analysis;
+use crate::tiles::TileRegistry;
+use crate::{Board, Dir, Tile};
+
+/// The `move` column's labels: a move's index in this list.
+pub const MOVES: [Dir; 8] = [
+    Dir::Up,
+    Dir::Down,
+    Dir::Right,
+    Dir::Left,
+    Dir::UpLeft,
+    Dir::UpRight,
+    Dir::DownLeft,
+    Dir::DownRight,
+];
+
+/// The one-hot channels of each cell, in the order they're written. Cells past the edge of
+/// a smaller board are rock, and empty floor has none of them set.
+const CHANNELS: [&str; 4] = ["rock", "cherry", "body", "head"];
+
+/// What [`export`] did with the boards it was given.
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub boards: usize,
+    /// Boards whose shortest solution was found and written out.
+    pub solved: usize,
+    /// Boards left out as unsolvable, too slow to prove a solution shortest, too big for
+    /// the grid or unreadable.
+    pub skipped: Vec<(String, String)>,
+    /// Rows written, one per move.
+    pub rows: usize,
+}
+
+/// Solve every board in the files in `dir` as shortly as possible, giving each up to
+/// `time_limit`, and write one CSV row to `out` for each move of each solution: the state
+/// before the move and the move.
+///
+/// The columns are `board` (its [`Board::id`]), `step`, `rules` (the words of the rules
+/// header, empty for the default rules), `move` (an index into [`MOVES`]), then one 0 or 1 for each
+/// channel of each cell, named `{channel}_{x}_{y}`, row by row. Every board is drawn on
+/// the same `width` × `height` grid, the largest board's unless given, so that each row
+/// is a tensor of the same shape.
+pub fn export(
+    dir: &Path,
+    time_limit: Duration,
+    size: Option<(usize, usize)>,
+    out: &mut impl Write,
+) -> io::Result<Summary> {
+    let tiles = TileRegistry::default();
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+
+    paths.sort();
+
+    let mut summary = Summary::default();
+    let mut boards = Vec::new();
+
+    for path in &paths {
+        let name = path.display().to_string();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                summary.boards += 1;
+                summary.skipped.push((name, e.to_string()));
+                continue;
+            }
+        };
+
+        for (i, board) in Board::parse_all(&text, &tiles).into_iter().enumerate() {
+            let name = match i {
+                0 => name.clone(),
+                i => format!("{name}#{}", i + 1),
+            };
+
+            summary.boards += 1;
+
+            match board.and_then(|b| b.validate().map(|()| b)) {
+                Ok(board) => boards.push((name, board)),
+                Err(e) => summary.skipped.push((name, e.to_string())),
+            }
+        }
+    }
+
+    let (width, height) = size.unwrap_or_else(|| {
+        boards.iter().fold((0, 0), |(w, h), (_, b)| {
+            let width = b.tiles.iter().map(Vec::len).max().unwrap_or(0);
+
+            (w.max(width), h.max(b.tiles.len()))
+        })
+    });
+
+    let cells = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+    let header: Vec<String> = ["board", "step", "rules", "move"]
+        .into_iter()
+        .map(str::to_string)
+        .chain(cells.flat_map(|(x, y)| CHANNELS.map(|c| format!("{c}_{x}_{y}"))))
+        .collect();
+
+    writeln!(out, "{}", header.join(","))?;
+
+    for (name, board) in boards {
+        let fits = board.tiles.len() <= height && board.tiles.iter().all(|r| r.len() <= width);
+
+        if !fits {
+            summary
+                .skipped
+                .push((name, format!("bigger than {width} × {height}")));
+            continue;
+        }
+
+        let outcome = analysis::shortest(&board, Some(Instant::now() + time_limit), &tiles);
+
+        let solution = match outcome.solution {
+            Some(_) if outcome.timed_out => {
+                summary
+                    .skipped
+                    .push((name, "not proven shortest in time".to_string()));
+                continue;
+            }
+            Some(solution) => solution,
+            None if outcome.timed_out => {
+                summary
+                    .skipped
+                    .push((name, "not solved in time".to_string()));
+                continue;
+            }
+            None => {
+                summary.skipped.push((name, "unsolvable".to_string()));
+                continue;
+            }
+        };
+
+        let id = board.id();
+        let rules = match board.rules != Default::default() {
+            true => board.rules.words(),
+            false => String::new(),
+        };
+        let mut state = board.place_snake(solution.start);
+
+        for (step, &dir) in solution.moves.iter().enumerate() {
+            let label = MOVES.iter().position(|&d| d == dir).unwrap_or_default();
+
+            write!(out, "{id},{step},{rules},{label}")?;
+
+            for y in 0..height {
+                for x in 0..width {
+                    let tile = state.tiles.get(y).and_then(|row| row.get(x));
+                    let hot = match tile {
+                        None | Some(Tile::Rock | Tile::Custom(_)) => 0,
+                        Some(Tile::Cherry) => 1,
+                        Some(Tile::SnakeBody) => 2,
+                        Some(Tile::SnakeHead) => 3,
+                        Some(Tile::Empty) => CHANNELS.len(),
+                    };
+
+                    for channel in 0..CHANNELS.len() {
+                        write!(out, ",{}", u8::from(channel == hot))?;
+                    }
+                }
+            }
+
+            writeln!(out)?;
+            summary.rows += 1;
+
+            state = state.move_snake(dir, &tiles).expect("the snake was placed");
+        }
+
+        summary.solved += 1;
+    }
+
+    Ok(summary)
+}
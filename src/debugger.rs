@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::order::StartOrder;
+use crate::tiles::TileRegistry;
+use crate::{search_starts, Board, Constraints, Dir, Position};
+
+/// A breadth-first search played the way [`crate::solve`] plays it, with every state kept,
+/// so that it can be stepped through forwards and backwards afterwards.
+pub struct Recording {
+    /// Every state in the order it was queued, across all starts.
+    pub states: Vec<Board>,
+    /// How many moves from its start each state is.
+    pub depths: Vec<usize>,
+    pub steps: Vec<Step>,
+    /// The search stopped after the last step because it hit the step limit rather than
+    /// finishing.
+    pub truncated: bool,
+}
+
+/// One state taken off the queue and expanded.
+pub struct Step {
+    pub start: Position,
+    /// The expanded state, as an index into [`Recording::states`].
+    pub state: usize,
+    /// Each move tried: where it led, and whether that was a state not seen before.
+    pub moves: Vec<(Dir, usize, bool)>,
+    /// The states known to this start's search once the step was done.
+    pub known: Range<usize>,
+    /// The expanded state has every cherry eaten.
+    pub solved: bool,
+}
+
+impl Recording {
+    /// The states waiting in the queue after `step`, first to be expanded first.
+    pub fn queue(&self, step: usize) -> Range<usize> {
+        let s = &self.steps[step];
+
+        s.state + 1..s.known.end
+    }
+
+    /// The states in the visited set after `step`.
+    pub fn visited(&self, step: usize) -> Range<usize> {
+        self.steps[step].known.clone()
+    }
+}
+
+/// Record the search of `board` for up to `limit` steps.
+pub fn record(board: &Board, tiles: &TileRegistry, limit: usize) -> Recording {
+    let mut recording = Recording {
+        states: Vec::new(),
+        depths: Vec::new(),
+        steps: Vec::new(),
+        truncated: false,
+    };
+
+    for start in search_starts(board, Constraints::default(), StartOrder::default(), tiles) {
+        let first = recording.states.len();
+        let mut index = HashMap::from([(board.place_snake(start), first)]);
+        let mut next = first;
+
+        recording.states.push(board.place_snake(start));
+        recording.depths.push(0);
+
+        while next < recording.states.len() {
+            if recording.steps.len() == limit {
+                recording.truncated = true;
+
+                return recording;
+            }
+
+            let state = recording.states[next].clone();
+            let depth = recording.depths[next];
+            let mut moves = Vec::new();
+
+            for (dir, m) in state.moves(tiles) {
+                match index.get(&m) {
+                    Some(&seen) => moves.push((dir, seen, false)),
+                    None => {
+                        let i = recording.states.len();
+
+                        index.insert(m.clone(), i);
+                        recording.states.push(m);
+                        recording.depths.push(depth + 1);
+                        moves.push((dir, i, true));
+                    }
+                }
+            }
+
+            let solved = tiles.solved(&state);
+
+            recording.steps.push(Step {
+                start,
+                state: next,
+                moves,
+                known: first..recording.states.len(),
+                solved,
+            });
+
+            if solved {
+                return recording;
+            }
+
+            next += 1;
+        }
+    }
+
+    recording
+}
@@ -0,0 +1,61 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong between reading a board and solving it.
+#[derive(Debug)]
+pub enum SnakeError {
+    /// The board has a character that isn't a known tile. `line` and `column` count from 1.
+    Parse {
+        line: usize,
+        column: usize,
+        glyph: char,
+    },
+    /// The board's `rules:` header names a rule that doesn't exist.
+    Rules {
+        line: usize,
+        rule: String,
+    },
+    /// The board's `tiles:` header has a pair that isn't `G=T` for a tile `T` knows.
+    Tiles {
+        line: usize,
+        pair: String,
+    },
+    /// The board parsed, but can't be searched as it stands.
+    InvalidState(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for SnakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnakeError::Parse {
+                line,
+                column,
+                glyph,
+            } => write!(f, "Unknown tile {glyph:?} at line {line}, column {column}"),
+            SnakeError::Rules { line, rule } => write!(f, "Unknown rule {rule:?} on line {line}"),
+            SnakeError::Tiles { line, pair } => write!(
+                f,
+                "Unknown tile alias {pair:?} on line {line}, expected GLYPH=TILE for a known TILE"
+            ),
+            SnakeError::InvalidState(reason) => write!(f, "Invalid board: {reason}"),
+            SnakeError::Io(e) => write!(f, "I/O error: {e}"),
+        }
+    }
+}
+
+impl Error for SnakeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnakeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnakeError {
+    fn from(e: io::Error) -> Self {
+        SnakeError::Io(e)
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::{self, Write};
+
+use crate::api::{moves_json, position_json};
+use crate::json::Json;
+use crate::{Event, ProgressSink};
+
+/// Writes every search [`Event`] as one JSON object per line, for analysis scripts. Write
+/// errors are kept until [`EventLog::finish`] rather than interrupting the search.
+pub struct EventLog<W: Write> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> EventLog<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, error: None }
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error {
+            Some(e) => Err(e),
+            None => self.out.flush(),
+        }
+    }
+}
+
+impl<W: Write> ProgressSink for EventLog<W> {
+    fn report(&mut self, event: Event) {
+        if self.error.is_some() {
+            return;
+        }
+
+        if let Err(e) = writeln!(self.out, "{}", event_json(&event)) {
+            self.error = Some(e);
+        }
+    }
+}
+
+fn event_json(event: &Event) -> Json {
+    match event {
+        Event::Start(p) => Json::obj([("event", "start".into()), ("start", position_json(*p))]),
+        Event::Progress {
+            depth,
+            states,
+            queued,
+            explored,
+            memory,
+            cherries_left,
+            ..
+        } => Json::obj([
+            ("event", "progress".into()),
+            ("depth", (*depth).into()),
+            ("states", (*states).into()),
+            ("queued", (*queued).into()),
+            ("explored", (*explored).into()),
+            ("memory_bytes", (*memory).into()),
+            ("cherries_left", (*cherries_left).into()),
+        ]),
+        Event::Layer {
+            depth,
+            states,
+            elapsed,
+        } => Json::obj([
+            ("event", "layer".into()),
+            ("depth", (*depth).into()),
+            ("states", (*states).into()),
+            ("elapsed_ms", (elapsed.as_secs_f64() * 1000.0).into()),
+        ]),
+        Event::Expanded {
+            depth,
+            head,
+            cherries_left,
+        } => Json::obj([
+            ("event", "expanded".into()),
+            ("depth", (*depth).into()),
+            ("head", position_json(*head)),
+            ("cherries_left", (*cherries_left).into()),
+        ]),
+        Event::Solved(solution) => Json::obj([
+            ("event", "solved".into()),
+            ("start", position_json(solution.start)),
+            ("moves", moves_json(&solution.moves)),
+        ]),
+        Event::Abandoned {
+            start,
+            states,
+            timed_out,
+        } => Json::obj([
+            ("event", "abandoned".into()),
+            ("start", position_json(*start)),
+            ("states", (*states).into()),
+            ("timed_out", (*timed_out).into()),
+        ]),
+    }
+}
@@ -0,0 +1,202 @@
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::tiles::TileRegistry;
+use crate::{check, solve_within, Board, Invalid};
+
+pub use crate::Dir;
+
+/// The exports `uniffi/snake.udl` describes, generated from it with a blank line after a
+/// doc comment that clippy would otherwise reject.
+#[allow(clippy::empty_line_after_doc_comments)]
+mod scaffolding {
+    use super::*;
+
+    uniffi::include_scaffolding!("snake");
+}
+
+pub(crate) use scaffolding::UniFfiTag;
+
+/// A cell, as `uniffi/snake.udl` has it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub start: Position,
+    pub moves: Vec<Dir>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SolveResult {
+    pub solution: Option<Solution>,
+    pub timed_out: bool,
+    pub states: u64,
+}
+
+#[derive(Debug)]
+pub enum BoardError {
+    Invalid { message: String },
+}
+
+/// Why [`validate`] turned a solution down.
+#[derive(Debug)]
+pub enum InvalidSolution {
+    BadBoard { message: String },
+    BadStart { start: Position },
+    Stuck { move_index: u32, dir: Dir },
+    Incomplete { cherries_left: u32 },
+}
+
+impl From<crate::Position> for Position {
+    fn from((x, y): crate::Position) -> Self {
+        Self {
+            x: x as u32,
+            y: y as u32,
+        }
+    }
+}
+
+impl From<Position> for crate::Position {
+    fn from(p: Position) -> Self {
+        (p.x as usize, p.y as usize)
+    }
+}
+
+impl From<crate::Solution> for Solution {
+    fn from(solution: crate::Solution) -> Self {
+        Self {
+            start: solution.start.into(),
+            moves: solution.moves,
+        }
+    }
+}
+
+impl From<Solution> for crate::Solution {
+    fn from(solution: Solution) -> Self {
+        Self {
+            start: solution.start.into(),
+            moves: solution.moves,
+        }
+    }
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::Invalid { message } => f.write_str(message),
+        }
+    }
+}
+
+impl Error for BoardError {}
+
+impl fmt::Display for InvalidSolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidSolution::BadBoard { message } => f.write_str(message),
+            InvalidSolution::BadStart { start } => {
+                write!(
+                    f,
+                    "{}, {} is not a cherry or the snake's head",
+                    start.x, start.y
+                )
+            }
+            InvalidSolution::Stuck { move_index, dir } => {
+                write!(f, "Move {move_index} can't go {dir:?}")
+            }
+            InvalidSolution::Incomplete { cherries_left } => {
+                write!(f, "{cherries_left} cherries are left")
+            }
+        }
+    }
+}
+
+impl Error for InvalidSolution {}
+
+fn board(text: &str) -> Result<Board, BoardError> {
+    let board = Board::new(text).and_then(|b| b.validate().map(|()| b));
+
+    board.map_err(|e| BoardError::Invalid {
+        message: e.to_string(),
+    })
+}
+
+pub fn solve(board: String, time_limit_ms: u64) -> Result<SolveResult, BoardError> {
+    let board = self::board(&board)?;
+    let deadline = Instant::now() + Duration::from_millis(time_limit_ms);
+    let outcome = solve_within(&board, Some(deadline), &TileRegistry::default());
+
+    Ok(SolveResult {
+        solution: outcome.solution.map(Solution::from),
+        timed_out: outcome.timed_out,
+        states: outcome.states as u64,
+    })
+}
+
+pub fn validate(board: String, solution: Solution) -> Result<(), InvalidSolution> {
+    let board = self::board(&board)
+        .map_err(|BoardError::Invalid { message }| InvalidSolution::BadBoard { message })?;
+    let start = solution.start.into();
+
+    check(&board, start, &solution.moves, &TileRegistry::default()).map_err(|e| match e {
+        Invalid::BadStart(start) => InvalidSolution::BadStart {
+            start: start.into(),
+        },
+        Invalid::Stuck(i, dir) => InvalidSolution::Stuck {
+            move_index: i as u32,
+            dir,
+        },
+        Invalid::Incomplete(n) => InvalidSolution::Incomplete {
+            cherries_left: n as u32,
+        },
+    })
+}
+
+pub fn trail(board: String, solution: Solution) -> Result<Vec<Position>, BoardError> {
+    let board = self::board(&board)?;
+    let cells = crate::trail(&board, &solution.into(), &TileRegistry::default());
+
+    Ok(cells.into_iter().map(Position::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solutions_validate_and_leave_a_trail() {
+        let result = solve("c.c\nc..".to_string(), 5000).unwrap();
+        let solution = result.solution.unwrap();
+
+        assert!(validate("c.c\nc..".to_string(), solution.clone()).is_ok());
+        assert_eq!(
+            trail("c.c\nc..".to_string(), solution.clone()).unwrap()[0],
+            solution.start
+        );
+    }
+
+    #[test]
+    fn a_move_that_goes_nowhere_says_which() {
+        let solution = Solution {
+            start: Position { x: 0, y: 0 },
+            moves: vec![Dir::Right],
+        };
+
+        assert!(matches!(
+            validate("crc".to_string(), solution),
+            Err(InvalidSolution::Stuck {
+                move_index: 0,
+                dir: Dir::Right
+            })
+        ));
+        assert!(matches!(
+            solve("cxc".to_string(), 100),
+            Err(BoardError::Invalid { .. })
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use crate::rng::Rng;
+use crate::tiles::TileRegistry;
+use crate::{solve_within, Board, Solution, Tile};
+
+/// The widest and tallest board [`check_request`] lets through.
+pub const MAX_SIDE: usize = 256;
+/// The most attempts [`check_request`] lets through.
+pub const MAX_ATTEMPTS: usize = 10_000;
+
+/// Why a request to [`generate`] from outside, whose sizes could be anything, can't be
+/// met, before anything is allocated for it.
+pub fn check_request(
+    width: usize,
+    height: usize,
+    rocks: usize,
+    attempts: usize,
+) -> Result<(), String> {
+    if !(1..=MAX_SIDE).contains(&width) || !(1..=MAX_SIDE).contains(&height) {
+        return Err(format!(
+            "Boards must be 1 to {MAX_SIDE} cells wide and tall, not {width} x {height}"
+        ));
+    }
+
+    if rocks >= width * height {
+        return Err(format!(
+            "{rocks} rocks leave no room for cherries on a {width} x {height} board"
+        ));
+    }
+
+    if attempts > MAX_ATTEMPTS {
+        return Err(format!("At most {MAX_ATTEMPTS} attempts, not {attempts}"));
+    }
+
+    Ok(())
+}
+
+pub struct Generated {
+    pub board: Board,
+    pub solution: Solution,
+}
+
+/// Scatter `rocks` rocks over a `width` x `height` board until a solvable layout turns up,
+/// giving each candidate at most `time_limit` to be solved.
+pub fn generate(
+    width: usize,
+    height: usize,
+    rocks: usize,
+    rng: &mut Rng,
+    attempts: usize,
+    time_limit: Duration,
+) -> Option<Generated> {
+    let cells = width * height;
+
+    if cells == 0 || rocks >= cells {
+        return None;
+    }
+
+    (0..attempts).find_map(|_| {
+        let mut tiles = vec![vec![Tile::Cherry; width]; height];
+        let mut placed = 0;
+
+        while placed < rocks {
+            let i = rng.below(cells);
+            let tile = &mut tiles[i / width][i % width];
+
+            if *tile == Tile::Cherry {
+                *tile = Tile::Rock;
+                placed += 1;
+            }
+        }
+
+        let board = Board::from_tiles(tiles);
+        let solution = solve_within(
+            &board,
+            Some(Instant::now() + time_limit),
+            &TileRegistry::default(),
+        )
+        .solution?;
+
+        Some(Generated { board, solution })
+    })
+}
+
+pub fn board_text(board: &Board) -> String {
+    board
+        .tiles
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|t| match t {
+                    Tile::Rock => 'r',
+                    Tile::Empty => '.',
+                    _ => 'c',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_too_big_to_meet_are_turned_down() {
+        assert!(check_request(6, 6, 6, 100).is_ok());
+        assert!(check_request(100_000, 100_000, 0, 1).is_err());
+        assert!(check_request(usize::MAX, 2, 0, 1).is_err());
+        assert!(check_request(0, 6, 0, 1).is_err());
+        assert!(check_request(2, 2, 4, 1).is_err());
+        assert!(check_request(6, 6, 6, MAX_ATTEMPTS + 1).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::{Board, Dir, Trace};
+
+/// The states a search visited and the moves between them, gathered as a [`Trace`] and
+/// written out in Graphviz's DOT language.
+#[derive(Debug, Default)]
+pub struct SearchGraph {
+    ids: HashMap<Board, usize>,
+    /// Each state's head and how many cherries it has left, by id.
+    nodes: Vec<(Option<(usize, usize)>, usize)>,
+    edges: Vec<(usize, usize, Dir, &'static str)>,
+    limit: Option<usize>,
+    /// Moves left out for leading to a state past the limit.
+    dropped: usize,
+}
+
+impl SearchGraph {
+    /// A graph that keeps the first `limit` states it's told of, or all of them.
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            ..Self::default()
+        }
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// How many moves were left out because the graph was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    fn id(&mut self, board: &Board) -> Option<usize> {
+        if let Some(&id) = self.ids.get(board) {
+            return Some(id);
+        }
+
+        if self.limit.is_some_and(|n| self.nodes.len() >= n) {
+            return None;
+        }
+
+        let id = self.nodes.len();
+
+        self.ids.insert(board.clone(), id);
+        self.nodes
+            .push((board.get_snake_head(), board.cherry_count()));
+
+        Some(id)
+    }
+
+    /// The graph in DOT: a node for each state labelled with its head and the cherries it
+    /// has left, and an edge for each move labelled with its direction. Moves to states
+    /// found before are dashed and ones cut off for stranding cherries are grey; solved
+    /// states are drawn with a double border.
+    pub fn write_dot(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "digraph search {{")?;
+        writeln!(out, "  node [shape=box, fontname=\"sans-serif\"];")?;
+        writeln!(out, "  edge [fontname=\"sans-serif\"];")?;
+
+        for (id, &(head, left)) in self.nodes.iter().enumerate() {
+            let (x, y) = head.unwrap_or_default();
+            let peripheries = match left {
+                0 => 2,
+                _ => 1,
+            };
+
+            writeln!(
+                out,
+                "  n{id} [label=\"{x},{y}\\n{left} left\", peripheries={peripheries}];"
+            )?;
+        }
+
+        for &(from, to, dir, outcome) in &self.edges {
+            let style = match outcome {
+                "seen" => ", style=dashed",
+                "stranded" => ", color=grey, fontcolor=grey",
+                _ => "",
+            };
+
+            writeln!(
+                out,
+                "  n{from} -> n{to} [label=\"{}\"{style}];",
+                dir.letter()
+            )?;
+        }
+
+        writeln!(out, "}}")
+    }
+}
+
+impl Trace for SearchGraph {
+    fn tried(&mut self, from: &Board, _: usize, dir: Dir, to: &Board, outcome: &str) {
+        // Bumping into something leaves the state as it was
+        let outcome = match outcome {
+            "stuck" => return,
+            "new" => "new",
+            "seen" => "seen",
+            _ => "stranded",
+        };
+
+        match (self.id(from), self.id(to)) {
+            (Some(from), Some(to)) => self.edges.push((from, to, dir, outcome)),
+            _ => self.dropped += 1,
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::astar::{self, longest_line};
+use crate::order::StartOrder;
+use crate::tiles::TileRegistry;
+use crate::{search_starts, solution, Board, Constraints, Outcome, Partial, SearchStats, Tile};
+
+/// How [`solve_greedy`] ranks states, most promising first. None of them need be a lower
+/// bound, since the greedy search doesn't promise a shortest solution anyway.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Heuristic {
+    /// Fewest cherries left.
+    Cherries,
+    /// Fewest straight lines needed to eat the cherries left, as A* estimates it.
+    Lines,
+    /// Fewest cherries left, with each cherry that only one neighbour leads into counting
+    /// for a whole line more: only one of them can be where the snake ends up.
+    #[default]
+    DeadEnds,
+}
+
+impl FromStr for Heuristic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cherries" => Ok(Heuristic::Cherries),
+            "lines" => Ok(Heuristic::Lines),
+            "dead-ends" => Ok(Heuristic::DeadEnds),
+            _ => Err(format!(
+                "Unknown heuristic {s:?}, expected cherries, lines or dead-ends"
+            )),
+        }
+    }
+}
+
+impl Heuristic {
+    fn estimate(self, board: &Board, longest: usize) -> usize {
+        match self {
+            Heuristic::Cherries => board.cherry_count(),
+            Heuristic::Lines => astar::heuristic(board, longest),
+            Heuristic::DeadEnds => board.cherry_count() + longest * dead_ends(board),
+        }
+    }
+}
+
+/// Cherries with at most one cherry or the snake's head next to them.
+fn dead_ends(board: &Board) -> usize {
+    let open = |p: (usize, usize)| {
+        matches!(
+            board.tiles[p.1][p.0],
+            Tile::Cherry | Tile::Empty | Tile::SnakeHead
+        )
+    };
+
+    board
+        .tiles
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, t)| matches!(t, Tile::Cherry))
+                .map(move |(x, _)| (x, y))
+        })
+        .filter(|&p| {
+            board
+                .rules
+                .dirs()
+                .iter()
+                .filter_map(|&dir| board.neighbour(p, dir))
+                .filter(|&n| open(n))
+                .count()
+                <= 1
+        })
+        .count()
+}
+
+/// Some solution from any start, found by always expanding the state `heuristic` likes
+/// best. Usually far quicker than [`crate::solve`] to find one, but nothing says it's the
+/// shortest, and on an unsolvable board it still has to look at every state to say so.
+pub fn solve_greedy(
+    board: &Board,
+    heuristic: Heuristic,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+) -> Outcome {
+    let roots = search_starts(board, Constraints::default(), StartOrder::default(), tiles)
+        .into_iter()
+        .map(|p| board.place_snake(p));
+
+    best_first(board, roots, heuristic, deadline, None, tiles)
+}
+
+/// [`solve_greedy`] carrying on from `state`, a board the snake is already on, giving up
+/// after `max_states` states if given. The solution starts where the head is now.
+pub fn solve_greedy_from(
+    state: &Board,
+    heuristic: Heuristic,
+    deadline: Option<Instant>,
+    max_states: Option<usize>,
+    tiles: &TileRegistry,
+) -> Outcome {
+    best_first(
+        state,
+        std::iter::once(state.clone()),
+        heuristic,
+        deadline,
+        max_states,
+        tiles,
+    )
+}
+
+fn best_first(
+    board: &Board,
+    roots: impl Iterator<Item = Board>,
+    heuristic: Heuristic,
+    deadline: Option<Instant>,
+    max_states: Option<usize>,
+    tiles: &TileRegistry,
+) -> Outcome {
+    let started = Instant::now();
+    let mut stats = SearchStats::default();
+    let longest = longest_line(board);
+
+    let mut parents: HashMap<Board, Option<Board>> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+    // As in A*, the heap refers to boards by when they were queued, and ties go to the
+    // oldest
+    let mut queued: Vec<Option<Board>> = Vec::new();
+
+    for start in roots {
+        parents.insert(start.clone(), None);
+        heap.push(Reverse((heuristic.estimate(&start, longest), queued.len())));
+        queued.push(Some(start));
+    }
+
+    let mut states: usize = 0;
+    let mut timed_out = false;
+    let mut best: Option<(usize, Board)> = None;
+
+    while let Some(Reverse((_, i))) = heap.pop() {
+        let Some(b) = queued[i].take() else {
+            continue;
+        };
+
+        states += 1;
+
+        let out_of_time =
+            states.is_multiple_of(1024) && deadline.is_some_and(|d| Instant::now() >= d);
+
+        if out_of_time || max_states.is_some_and(|n| states > n) {
+            timed_out = true;
+            break;
+        }
+
+        let cherries_left = b.cherry_count();
+
+        if let Some(head) = b.get_snake_head() {
+            *stats.heat.entry(head).or_default() += 1;
+        }
+
+        if best.as_ref().is_none_or(|(left, _)| cherries_left < *left) {
+            best = Some((cherries_left, b.clone()));
+        }
+
+        if tiles.solved(&b) {
+            let solution = solution(&b, &parents, tiles);
+
+            stats.depth = Some(solution.moves.len());
+            stats.elapsed = started.elapsed();
+
+            return Outcome {
+                solution: Some(solution),
+                partial: None,
+                states,
+                timed_out: false,
+                stats,
+            };
+        }
+
+        for (_, m) in b.moves(tiles) {
+            if parents.contains_key(&m) {
+                if m != b {
+                    stats.duplicates += 1;
+                }
+
+                continue;
+            }
+
+            parents.insert(m.clone(), Some(b.clone()));
+            heap.push(Reverse((heuristic.estimate(&m, longest), queued.len())));
+            queued.push(Some(m));
+        }
+
+        stats.peak_queue = stats.peak_queue.max(heap.len());
+    }
+
+    stats.elapsed = started.elapsed();
+
+    Outcome {
+        solution: None,
+        partial: best.map(|(cherries_left, b)| Partial {
+            solution: solution(&b, &parents, tiles),
+            cherries_left,
+        }),
+        states,
+        timed_out,
+        stats,
+    }
+}
@@ -0,0 +1,303 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use tonic::{Request, Response, Status};
+
+use crate::api::SolveRequest;
+use crate::generate::{board_text, check_request, generate};
+use crate::order::StartOrder;
+use crate::rng::Rng;
+use crate::tiles::TileRegistry;
+use crate::{check, Board, Invalid, Position};
+
+/// The messages and service of `proto/snake.proto`.
+pub mod proto {
+    tonic::include_proto!("clever_snake.v1");
+}
+
+use proto::solver_server::{Solver, SolverServer};
+use proto::validate_response::Detail;
+
+/// Serve the `Solver` service on `addr` until the process is stopped, giving each request
+/// at most `time_limit`, as `snake serve` does over HTTP.
+pub fn serve(addr: &str, time_limit: Duration) -> io::Result<()> {
+    let addr = addr
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{addr}: {e}")))?;
+
+    tokio::runtime::Runtime::new()?.block_on(async {
+        tonic::transport::Server::builder()
+            .add_service(SolverServer::new(Service { time_limit }))
+            .serve(addr)
+            .await
+            .map_err(io::Error::other)
+    })
+}
+
+struct Service {
+    time_limit: Duration,
+}
+
+impl Service {
+    /// `requested` milliseconds capped by the server's limit, with zero meaning no more
+    /// than the limit.
+    fn limit(&self, requested: u64) -> Duration {
+        match requested {
+            0 => self.time_limit,
+            ms => Duration::from_millis(ms).min(self.time_limit),
+        }
+    }
+}
+
+/// Run `work` off the async threads, as every search is.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, Status> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+}
+
+fn board(board: Option<proto::Board>) -> Result<Board, String> {
+    let board = Board::new(&board.ok_or("Missing board")?.text).map_err(|e| e.to_string())?;
+
+    board.validate().map_err(|e| e.to_string())?;
+
+    Ok(board)
+}
+
+/// The board, start and moves a validate request asks about.
+fn solution(request: proto::ValidateRequest) -> Result<(Board, Position, Vec<crate::Dir>), String> {
+    let board = board(request.board)?;
+    let solution = request.solution.ok_or("Missing solution")?;
+    let start = solution
+        .start
+        .map(|p| (p.x as usize, p.y as usize))
+        .ok_or("Missing start")?;
+    let moves = solution
+        .moves
+        .iter()
+        .map(|&m| {
+            proto::Dir::try_from(m)
+                .map_err(|_| format!("Unknown direction {m}"))
+                .and_then(crate::Dir::try_from)
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((board, start, moves))
+}
+
+fn solution_proto(solution: &crate::Solution) -> proto::Solution {
+    let (x, y) = solution.start;
+
+    proto::Solution {
+        start: Some(proto::Position {
+            x: x as u32,
+            y: y as u32,
+        }),
+        moves: solution
+            .moves
+            .iter()
+            .map(|&dir| proto::Dir::from(dir) as i32)
+            .collect(),
+    }
+}
+
+impl From<crate::Dir> for proto::Dir {
+    fn from(dir: crate::Dir) -> Self {
+        match dir {
+            crate::Dir::Up => Self::Up,
+            crate::Dir::Down => Self::Down,
+            crate::Dir::Right => Self::Right,
+            crate::Dir::Left => Self::Left,
+            crate::Dir::UpLeft => Self::UpLeft,
+            crate::Dir::UpRight => Self::UpRight,
+            crate::Dir::DownLeft => Self::DownLeft,
+            crate::Dir::DownRight => Self::DownRight,
+        }
+    }
+}
+
+impl TryFrom<proto::Dir> for crate::Dir {
+    type Error = String;
+
+    fn try_from(dir: proto::Dir) -> Result<Self, String> {
+        match dir {
+            proto::Dir::Unspecified => Err("Moves must have a direction".to_string()),
+            proto::Dir::Up => Ok(Self::Up),
+            proto::Dir::Down => Ok(Self::Down),
+            proto::Dir::Right => Ok(Self::Right),
+            proto::Dir::Left => Ok(Self::Left),
+            proto::Dir::UpLeft => Ok(Self::UpLeft),
+            proto::Dir::UpRight => Ok(Self::UpRight),
+            proto::Dir::DownLeft => Ok(Self::DownLeft),
+            proto::Dir::DownRight => Ok(Self::DownRight),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Solver for Service {
+    async fn solve(
+        &self,
+        request: Request<proto::SolveRequest>,
+    ) -> Result<Response<proto::SolveResponse>, Status> {
+        let request = request.into_inner();
+        let request = SolveRequest {
+            board: board(request.board).map_err(Status::invalid_argument)?,
+            tiles: TileRegistry::default(),
+            limit: self.limit(request.options.map_or(0, |o| o.time_limit_ms)),
+            shortest: false,
+            max_states: None,
+            max_memory: None,
+            order: StartOrder::default(),
+        };
+
+        blocking(move || {
+            let started = Instant::now();
+            let outcome = request.run(started);
+
+            proto::SolveResponse {
+                solution: outcome.solution.as_ref().map(solution_proto),
+                timed_out: outcome.timed_out,
+                states: outcome.states as u64,
+                elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+            }
+        })
+        .await
+        .map(Response::new)
+    }
+
+    async fn validate(
+        &self,
+        request: Request<proto::ValidateRequest>,
+    ) -> Result<Response<proto::ValidateResponse>, Status> {
+        let request = request.into_inner();
+        let (board, start, moves) = solution(request).map_err(Status::invalid_argument)?;
+
+        blocking(move || {
+            let (reason, detail) = match check(&board, start, &moves, &TileRegistry::default()) {
+                Ok(()) => {
+                    return proto::ValidateResponse {
+                        valid: true,
+                        ..Default::default()
+                    }
+                }
+                Err(Invalid::BadStart(_)) => (
+                    "Start is not on a cherry or the snake's head".to_string(),
+                    None,
+                ),
+                Err(Invalid::Stuck(i, dir)) => (
+                    format!("Snake can't move {dir:?}"),
+                    Some(Detail::MoveIndex(i as u32)),
+                ),
+                Err(Invalid::Incomplete(n)) => (
+                    "Cherries remain".to_string(),
+                    Some(Detail::CherriesLeft(n as u32)),
+                ),
+            };
+
+            proto::ValidateResponse {
+                valid: false,
+                reason,
+                detail,
+            }
+        })
+        .await
+        .map(Response::new)
+    }
+
+    async fn generate(
+        &self,
+        request: Request<proto::GenerateRequest>,
+    ) -> Result<Response<proto::GenerateResponse>, Status> {
+        let request = request.into_inner();
+        // Unset fields read as zero, so zero means the same defaults the HTTP API has
+        let or = |n: u32, default: usize| if n == 0 { default } else { n as usize };
+        let width = or(request.width, 6);
+        let height = or(request.height, 6);
+        let rocks = or(request.rocks, width.saturating_mul(height) / 6);
+        let attempts = or(request.attempts, 100);
+
+        check_request(width, height, rocks, attempts).map_err(Status::invalid_argument)?;
+
+        let limit = self.limit(request.time_limit_ms);
+        let mut rng = request.seed.map_or_else(Rng::from_clock, Rng::new);
+
+        let generated = blocking(move || {
+            generate(width, height, rocks, &mut rng, attempts, limit).map(|g| (g, rng.seed()))
+        })
+        .await?;
+        let (generated, seed) = generated.ok_or_else(|| {
+            Status::not_found(format!("No solvable board found in {attempts} attempts"))
+        })?;
+
+        Ok(Response::new(proto::GenerateResponse {
+            board: Some(proto::Board {
+                text: board_text(&generated.board),
+            }),
+            seed,
+            solution: Some(solution_proto(&generated.solution)),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<T>(future: impl std::future::Future<Output = T>) -> T {
+        tokio::runtime::Runtime::new().unwrap().block_on(future)
+    }
+
+    fn text(text: &str) -> Option<proto::Board> {
+        Some(proto::Board {
+            text: text.to_string(),
+        })
+    }
+
+    #[test]
+    fn solutions_come_back_valid() {
+        let service = Service {
+            time_limit: Duration::from_secs(5),
+        };
+
+        let solved = run(service.solve(Request::new(proto::SolveRequest {
+            board: text("c.c\nc.."),
+            options: None,
+        })))
+        .unwrap()
+        .into_inner();
+
+        assert!(!solved.timed_out);
+
+        let checked = run(service.validate(Request::new(proto::ValidateRequest {
+            board: text("c.c\nc.."),
+            solution: solved.solution,
+        })))
+        .unwrap()
+        .into_inner();
+
+        assert!(checked.valid, "{}", checked.reason);
+    }
+
+    #[test]
+    fn a_move_that_goes_nowhere_says_which() {
+        let service = Service {
+            time_limit: Duration::from_secs(5),
+        };
+
+        let checked = run(service.validate(Request::new(proto::ValidateRequest {
+            board: text("crc"),
+            solution: Some(proto::Solution {
+                start: Some(proto::Position { x: 0, y: 0 }),
+                moves: vec![proto::Dir::Right as i32],
+            }),
+        })))
+        .unwrap()
+        .into_inner();
+
+        assert!(!checked.valid);
+        assert_eq!(checked.detail, Some(Detail::MoveIndex(0)));
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::str::FromStr;
+
+/// The hash function a search files the states it has seen under.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashKind {
+    /// std's SipHash, which is slower but hard to make collide on purpose.
+    #[default]
+    Std,
+    /// [`Fx`], which is quicker on the few words a state hashes as.
+    Fx,
+}
+
+impl FromStr for HashKind {
+    type Err = String;
+
+    /// `std` or `fx`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "std" => Ok(HashKind::Std),
+            "fx" => Ok(HashKind::Fx),
+            _ => Err(format!("Unknown hasher {s:?}, expected std or fx")),
+        }
+    }
+}
+
+/// The name [`HashKind::from_str`] reads back.
+impl fmt::Display for HashKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HashKind::Std => "std",
+            HashKind::Fx => "fx",
+        })
+    }
+}
+
+/// The hash rustc uses for its own tables: each word is mixed in with a rotate, an xor and
+/// a multiply, a word at a time rather than a byte at a time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fx(u64);
+
+impl Fx {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+    fn add(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(Self::SEED);
+    }
+}
+
+impl Hasher for Fx {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut words = bytes.chunks_exact(8);
+
+        for word in words.by_ref() {
+            self.add(u64::from_le_bytes(word.try_into().expect("chunks of 8")));
+        }
+
+        let rest = words.remainder();
+
+        if !rest.is_empty() {
+            let mut word = [0; 8];
+
+            word[..rest.len()].copy_from_slice(rest);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, n: u8) {
+        self.add(n.into());
+    }
+
+    fn write_u16(&mut self, n: u16) {
+        self.add(n.into());
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.add(n.into());
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.add(n);
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.add(n as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Builds [`Fx`] hashers for a `HashMap`.
+pub type FxBuildHasher = BuildHasherDefault<Fx>;
@@ -0,0 +1,98 @@
+use std::iter;
+use std::time::{Duration, Instant};
+
+use crate::greedy::{self, Heuristic};
+use crate::tiles::TileRegistry;
+use crate::{search, Board, Constraints, Dir};
+
+/// The most a [`hint`] may spend. Both limits are strict: the answer comes back once
+/// either is reached, however little it had time to find out.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    pub time: Duration,
+    pub states: usize,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            time: Duration::from_millis(200),
+            states: 100_000,
+        }
+    }
+}
+
+/// What to play next, from the surest kind of answer the budget allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    /// The first move of a shortest solution from here, with how many moves it takes.
+    Optimal(Dir, usize),
+    /// The first move of some solution, found by following the most promising moves.
+    Greedy(Dir, usize),
+    /// A move that leaves the fewest cherries cut off, with nothing known about whether
+    /// the board can still be solved after it.
+    Nudge(Dir),
+    /// The board can't be solved from here any more.
+    Lost,
+    /// The board is solved, or the snake isn't on the board.
+    Done,
+}
+
+/// A move to make on `board`, a game in progress. Half the budget goes to looking for a
+/// shortest solution from here, the rest to any solution at all, and if neither turns up
+/// the hint falls back on the move that strands the fewest cherries.
+pub fn hint(board: &Board, budget: Budget, tiles: &TileRegistry) -> Hint {
+    let started = Instant::now();
+
+    let Some(head) = board.get_snake_head() else {
+        return Hint::Done;
+    };
+
+    if tiles.solved(board) {
+        return Hint::Done;
+    }
+
+    let constraints = Constraints {
+        max_states: Some(budget.states / 2),
+        ..Constraints::default()
+    };
+    let outcome = search(
+        board,
+        iter::once(head),
+        constraints,
+        Some(started + budget.time / 2),
+        tiles,
+        &mut (),
+        None,
+    );
+
+    match outcome.solution {
+        Some(solution) if !solution.moves.is_empty() => {
+            return Hint::Optimal(solution.moves[0], solution.moves.len())
+        }
+        None if !outcome.timed_out => return Hint::Lost,
+        _ => {}
+    }
+
+    let outcome = greedy::solve_greedy_from(
+        board,
+        Heuristic::default(),
+        Some(started + budget.time),
+        Some(budget.states - budget.states / 2),
+        tiles,
+    );
+
+    if let Some(solution) = outcome.solution.filter(|s| !s.moves.is_empty()) {
+        return Hint::Greedy(solution.moves[0], solution.moves.len());
+    }
+
+    if !outcome.timed_out {
+        return Hint::Lost;
+    }
+
+    board
+        .moves(tiles)
+        .filter(|(_, m)| m.get_snake_head() != Some(head))
+        .min_by_key(|(_, m)| (m.stranded_cherries(), m.cherry_count()))
+        .map_or(Hint::Lost, |(dir, _)| Hint::Nudge(dir))
+}
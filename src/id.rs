@@ -0,0 +1,76 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::symmetry::Symmetry;
+use crate::{Board, Tile};
+
+/// A puzzle's identity, from its rules and tiles alone, so that it stays the same however
+/// the board file is named, moved or laid out. Written as 16 hex digits.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BoardId(pub u64);
+
+impl fmt::Display for BoardId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for BoardId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match u64::from_str_radix(s, 16) {
+            Ok(n) if s.len() == 16 => Ok(Self(n)),
+            _ => Err(format!("{s:?} isn't a board id, which is 16 hex digits")),
+        }
+    }
+}
+
+/// FNV-1a, which unlike std's hashers is fixed, so ids written down today still match
+/// tomorrow's builds.
+fn fnv1a(bytes: impl Iterator<Item = u8>) -> u64 {
+    bytes.fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl Board {
+    /// The board's [`BoardId`]. Where the snake is counts, so a puzzle and a state partway
+    /// through solving it have different ids.
+    pub fn id(&self) -> BoardId {
+        let tile = |t: &Tile| match t {
+            Tile::Rock => vec![b'r'],
+            Tile::Cherry => vec![b'c'],
+            Tile::SnakeBody => vec![b's'],
+            Tile::SnakeHead => vec![b'S'],
+            Tile::Custom(n) => vec![b'#', *n],
+            Tile::Empty => vec![b'.'],
+        };
+
+        let rows = self
+            .tiles
+            .iter()
+            .flat_map(|row| row.iter().flat_map(tile).chain([b'\n']));
+
+        BoardId(fnv1a(
+            self.rules
+                .words()
+                .into_bytes()
+                .into_iter()
+                .chain([b'\n'])
+                .chain(rows),
+        ))
+    }
+
+    /// The smallest [`Board::id`] among the board's mirror images and turns, which is the
+    /// same for a board and any of them. Ragged boards only have their own.
+    pub fn symmetric_id(&self) -> BoardId {
+        Symmetry::ALL
+            .into_iter()
+            .filter_map(|s| self.transformed(s))
+            .map(|image| image.id())
+            .chain([self.id()])
+            .min()
+            .expect("there's always the board's own id")
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+use crate::astar::{heuristic, longest_line};
+use crate::order::StartOrder;
+use crate::tiles::TileRegistry;
+use crate::{
+    search_starts, Board, Constraints, Dir, Outcome, Partial, Position, SearchStats, Solution,
+};
+
+/// A depth-first search from one start, cut off at `bound` moves plus the
+/// [`heuristic`]. Only the line being followed is held in memory.
+struct Deepening<'a> {
+    tiles: &'a TileRegistry,
+    deadline: Option<Instant>,
+    longest: usize,
+    bound: usize,
+    start: Position,
+    moves: Vec<Dir>,
+    /// The states on the line being followed, so that it never loops back on itself.
+    line: HashSet<Board>,
+    states: usize,
+    timed_out: bool,
+    stats: SearchStats,
+    best: Option<Partial>,
+}
+
+impl Deepening<'_> {
+    /// The [`heuristic`], or nothing with [`crate::tiles::Hooks`], whose goal it knows
+    /// nothing of.
+    fn estimate(&self, board: &Board) -> usize {
+        match self.tiles.hooks() {
+            Some(_) => 0,
+            None => heuristic(board, self.longest),
+        }
+    }
+
+    /// Look for a solution below `board` within the bound. If there's none, continue with
+    /// the smallest estimate that went over it, which is the next bound worth trying.
+    fn search(&mut self, board: &Board) -> ControlFlow<(), usize> {
+        let estimate = self.moves.len() + self.estimate(board);
+
+        if estimate > self.bound {
+            return ControlFlow::Continue(estimate);
+        }
+
+        self.states += 1;
+
+        if self.states.is_multiple_of(1024) && self.deadline.is_some_and(|d| Instant::now() >= d) {
+            self.timed_out = true;
+            return ControlFlow::Break(());
+        }
+
+        if let Some(head) = board.get_snake_head() {
+            *self.stats.heat.entry(head).or_default() += 1;
+        }
+
+        let cherries_left = board.cherry_count();
+
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|best| cherries_left < best.cherries_left)
+        {
+            self.best = Some(Partial {
+                solution: Solution {
+                    start: self.start,
+                    moves: self.moves.clone(),
+                },
+                cherries_left,
+            });
+        }
+
+        if self.tiles.solved(board) {
+            return ControlFlow::Break(());
+        }
+
+        let mut next = usize::MAX;
+
+        for (dir, m) in board.moves(self.tiles) {
+            if self.line.contains(&m) {
+                self.stats.duplicates += 1;
+                continue;
+            }
+
+            self.moves.push(dir);
+            self.line.insert(m.clone());
+
+            let over = self.search(&m)?;
+
+            self.line.remove(&m);
+            self.moves.pop();
+
+            next = next.min(over);
+        }
+
+        ControlFlow::Continue(next)
+    }
+}
+
+/// The shortest solution from any start, like [`crate::astar::solve_astar`], but by
+/// iterative deepening: depth-first searches from every start with a bound on the moves
+/// that grows each round. States are expanded again every round, so it's slower, but its
+/// memory only grows with the length of the solution, so it can finish boards whose
+/// breadth-first frontier wouldn't fit.
+pub fn solve_ida(board: &Board, deadline: Option<Instant>, tiles: &TileRegistry) -> Outcome {
+    let starts = search_starts(board, Constraints::default(), StartOrder::default(), tiles);
+
+    solve_ida_from(board, &starts, deadline, tiles)
+}
+
+/// [`solve_ida`] from `starts` only.
+pub fn solve_ida_from(
+    board: &Board,
+    starts: &[Position],
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+) -> Outcome {
+    let started = Instant::now();
+    let longest = longest_line(board);
+
+    let mut deepening = Deepening {
+        tiles,
+        deadline,
+        longest,
+        bound: 0,
+        start: (0, 0),
+        moves: Vec::new(),
+        line: HashSet::new(),
+        states: 0,
+        timed_out: false,
+        stats: SearchStats::default(),
+        best: None,
+    };
+
+    deepening.bound = starts
+        .iter()
+        .map(|&p| deepening.estimate(&board.place_snake(p)))
+        .min()
+        .unwrap_or(0);
+
+    // Stops when a round finds nothing over the bound, so there's nowhere left to look
+    while deepening.bound < usize::MAX {
+        let mut next = usize::MAX;
+
+        for &p in starts {
+            let start = board.place_snake(p);
+
+            deepening.start = p;
+            deepening.line = HashSet::from([start.clone()]);
+
+            match deepening.search(&start) {
+                ControlFlow::Continue(over) => next = next.min(over),
+                ControlFlow::Break(()) if deepening.timed_out => {
+                    deepening.stats.elapsed = started.elapsed();
+
+                    return Outcome {
+                        solution: None,
+                        partial: deepening.best,
+                        states: deepening.states,
+                        timed_out: true,
+                        stats: deepening.stats,
+                    };
+                }
+                ControlFlow::Break(()) => {
+                    let solution = Solution {
+                        start: p,
+                        moves: deepening.moves,
+                    };
+
+                    deepening.stats.depth = Some(solution.moves.len());
+                    deepening.stats.elapsed = started.elapsed();
+
+                    return Outcome {
+                        solution: Some(solution),
+                        partial: None,
+                        states: deepening.states,
+                        timed_out: false,
+                        stats: deepening.stats,
+                    };
+                }
+            }
+        }
+
+        deepening.bound = next;
+    }
+
+    deepening.stats.elapsed = started.elapsed();
+
+    Outcome {
+        solution: None,
+        partial: deepening.best,
+        states: deepening.states,
+        timed_out: false,
+        stats: deepening.stats,
+    }
+}
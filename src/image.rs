@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use ::image::codecs::png::PngEncoder;
+use ::image::{ColorType, ImageEncoder};
+
+use crate::tiles::TileRegistry;
+use crate::{Board, Solution, Tile};
+
+/// The colours a board is drawn in, as RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub background: [u8; 3],
+    pub rock: [u8; 3],
+    pub cherry: [u8; 3],
+    pub body: [u8; 3],
+    pub head: [u8; 3],
+    pub custom: [u8; 3],
+}
+
+impl Default for Palette {
+    /// The colours of [`crate::svg::render`].
+    fn default() -> Self {
+        Self {
+            background: [0xf4, 0xf1, 0xe8],
+            rock: [0x77, 0x77, 0x77],
+            cherry: [0xdd, 0x33, 0x33],
+            body: [0x88, 0xcc, 0x88],
+            head: [0x11, 0x66, 0x11],
+            custom: [0xbb, 0xbb, 0xdd],
+        }
+    }
+}
+
+impl Palette {
+    /// Light on dark, for slides.
+    pub fn dark() -> Self {
+        Self {
+            background: [0x1e, 0x1e, 0x24],
+            rock: [0x55, 0x55, 0x60],
+            cherry: [0xff, 0x55, 0x55],
+            body: [0x44, 0xaa, 0x55],
+            head: [0xaa, 0xff, 0x99],
+            custom: [0x66, 0x66, 0xaa],
+        }
+    }
+
+    /// Greys only, for print.
+    pub fn mono() -> Self {
+        Self {
+            background: [0xff, 0xff, 0xff],
+            rock: [0x22, 0x22, 0x22],
+            cherry: [0x99, 0x99, 0x99],
+            body: [0xcc, 0xcc, 0xcc],
+            head: [0x55, 0x55, 0x55],
+            custom: [0xee, 0xee, 0xee],
+        }
+    }
+
+    /// The colours in the order [`Palette::index`] numbers them.
+    fn colors(&self) -> [[u8; 3]; 6] {
+        [
+            self.background,
+            self.rock,
+            self.cherry,
+            self.body,
+            self.head,
+            self.custom,
+        ]
+    }
+
+    fn index(tile: Tile) -> u8 {
+        match tile {
+            Tile::Empty => 0,
+            Tile::Rock => 1,
+            Tile::Cherry => 2,
+            Tile::SnakeBody => 3,
+            Tile::SnakeHead => 4,
+            Tile::Custom(_) => 5,
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// `light`, `dark` or `mono`, optionally followed by overrides like
+    /// `dark,cherry=#ff8800,rock=#000000`. Overrides alone start from `light`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = Palette::default();
+
+        for (i, part) in s.split(',').map(str::trim).enumerate() {
+            let Some((name, color)) = part.split_once('=') else {
+                palette = match part {
+                    "light" if i == 0 => Palette::default(),
+                    "dark" if i == 0 => Palette::dark(),
+                    "mono" if i == 0 => Palette::mono(),
+                    _ => {
+                        return Err(format!(
+                            "Unknown palette {part:?}, expected light, dark or mono"
+                        ))
+                    }
+                };
+                continue;
+            };
+
+            let color = parse_color(color)
+                .ok_or_else(|| format!("Expected a colour like #ff8800, not {color:?}"))?;
+
+            match name {
+                "background" => palette.background = color,
+                "rock" => palette.rock = color,
+                "cherry" => palette.cherry = color,
+                "body" => palette.body = color,
+                "head" => palette.head = color,
+                "custom" => palette.custom = color,
+                _ => {
+                    return Err(format!(
+                        "Unknown colour {name:?}, expected background, rock, cherry, body, head or custom"
+                    ))
+                }
+            }
+        }
+
+        Ok(palette)
+    }
+}
+
+fn parse_color(s: &str) -> Option<[u8; 3]> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// A board drawn as pixels, each an index into a [`Palette`]'s colours.
+struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Raster {
+    /// `board` with each cell `cell` pixels square. Cherries are dots on the background and
+    /// empty floor is left as background; everything else fills its cell.
+    fn draw(board: &Board, cell: usize) -> Self {
+        let width = board.tiles.iter().map(Vec::len).max().unwrap_or(0) * cell;
+        let height = board.tiles.len() * cell;
+        let mut pixels = vec![0; width * height];
+        let radius = cell as f64 / 4.0;
+
+        for (y, row) in board.tiles.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                for dy in 0..cell {
+                    for dx in 0..cell {
+                        let from_center = |d: usize| d as f64 + 0.5 - cell as f64 / 2.0;
+                        let inside = match tile {
+                            Tile::Cherry => from_center(dx).hypot(from_center(dy)) <= radius,
+                            _ => true,
+                        };
+
+                        if inside {
+                            pixels[(y * cell + dy) * width + x * cell + dx] = Palette::index(tile);
+                        }
+                    }
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// The board before the first move of `solution` and after each move.
+fn steps(board: &Board, solution: &Solution, tiles: &TileRegistry) -> Vec<Board> {
+    let mut states = vec![board.place_snake(solution.start)];
+
+    for &dir in &solution.moves {
+        let Some(next) = states[states.len() - 1].clone().move_snake(dir, tiles) else {
+            break;
+        };
+
+        states.push(next);
+    }
+
+    states
+}
+
+/// One PNG for each step of `solution` on `board`: the snake on its start, then after each
+/// move.
+pub fn png_frames(
+    board: &Board,
+    solution: &Solution,
+    palette: &Palette,
+    cell: usize,
+    tiles: &TileRegistry,
+) -> Vec<Vec<u8>> {
+    steps(board, solution, tiles)
+        .iter()
+        .map(|b| png(&Raster::draw(b, cell), palette))
+        .collect()
+}
+
+/// `solution` played out on `board` as an animated GIF that loops, showing each step for
+/// `delay`.
+pub fn gif(
+    board: &Board,
+    solution: &Solution,
+    palette: &Palette,
+    cell: usize,
+    delay: Duration,
+    tiles: &TileRegistry,
+) -> Vec<u8> {
+    let frames: Vec<Raster> = steps(board, solution, tiles)
+        .iter()
+        .map(|b| Raster::draw(b, cell))
+        .collect();
+    let (width, height) = frames.first().map_or((0, 0), |f| (f.width, f.height));
+    let short = |n: usize| (n.min(u16::MAX as usize) as u16).to_le_bytes();
+
+    let mut out = b"GIF89a".to_vec();
+
+    out.extend(short(width));
+    out.extend(short(height));
+    // A global table of 8 colours, the palette's 6 and black
+    out.extend([0xf2, 0, 0]);
+
+    for color in palette.colors().into_iter().chain([[0; 3]; 2]) {
+        out.extend(color);
+    }
+
+    // Loop forever
+    out.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    let centiseconds = short((delay.as_millis() / 10) as usize);
+
+    for frame in &frames {
+        out.extend([0x21, 0xf9, 0x04, 0x00]);
+        out.extend(centiseconds);
+        out.extend([0, 0]);
+
+        out.push(0x2c);
+        out.extend([0, 0, 0, 0]);
+        out.extend(short(frame.width));
+        out.extend(short(frame.height));
+        out.push(0);
+
+        out.push(3);
+
+        for block in lzw(&frame.pixels, 3).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend(block);
+        }
+
+        out.push(0);
+    }
+
+    out.push(0x3b);
+    out
+}
+
+/// GIF's variable-width LZW, starting at `min_size` + 1 bits a code.
+fn lzw(pixels: &[u8], min_size: u32) -> Vec<u8> {
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+
+    let mut out = Bits::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_size + 1;
+    let mut prefix: Option<u16> = None;
+
+    out.push(clear, width);
+
+    for &pixel in pixels {
+        let Some(p) = prefix else {
+            prefix = Some(pixel.into());
+            continue;
+        };
+
+        if let Some(&code) = table.get(&(p, pixel)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        out.push(p, width);
+
+        // The decoder adds its entry a code behind, so the width grows once the code
+        // after the next stops fitting
+        if next == 1 << width && width < 12 {
+            width += 1;
+        }
+
+        if next < 4096 {
+            table.insert((p, pixel), next);
+            next += 1;
+        } else {
+            out.push(clear, width);
+            table.clear();
+            next = end + 1;
+            width = min_size + 1;
+        }
+
+        prefix = Some(pixel.into());
+    }
+
+    if let Some(p) = prefix {
+        out.push(p, width);
+
+        if next == 1 << width && width < 12 {
+            width += 1;
+        }
+    }
+
+    out.push(end, width);
+    out.finish()
+}
+
+/// Codes packed least significant bit first, as GIF wants them.
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    pending: u32,
+    held: u32,
+}
+
+impl Bits {
+    fn push(&mut self, code: u16, width: u32) {
+        self.pending |= u32::from(code) << self.held;
+        self.held += width;
+
+        while self.held >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.held -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.held > 0 {
+            self.bytes.push(self.pending as u8);
+        }
+
+        self.bytes
+    }
+}
+
+/// `raster` as a PNG in the colours of `palette`.
+fn png(raster: &Raster, palette: &Palette) -> Vec<u8> {
+    let colors = palette.colors();
+    let rgb: Vec<u8> = raster
+        .pixels
+        .iter()
+        .flat_map(|&i| colors[i as usize])
+        .collect();
+    let mut out = Vec::new();
+
+    PngEncoder::new(&mut out)
+        .write_image(
+            &rgb,
+            raster.width as u32,
+            raster.height as u32,
+            ColorType::Rgb8,
+        )
+        .expect("Boards have at least one cell, and a Vec takes any PNG");
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dir;
+
+    /// What a GIF decoder makes of [`lzw`]'s codes.
+    fn unlzw(bytes: &[u8], min_size: u32) -> Vec<u8> {
+        let clear = 1usize << min_size;
+        let end = clear + 1;
+        let fresh = || -> Vec<Vec<u8>> { (0..=end).map(|c| vec![c as u8]).collect() };
+
+        let mut table = fresh();
+        let mut width = min_size + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        let mut bit = 0;
+
+        loop {
+            let code = (0..width as usize)
+                .map(|i| (bytes[(bit + i) / 8] >> ((bit + i) % 8) & 1) as usize)
+                .enumerate()
+                .fold(0, |code, (i, b)| code | b << i);
+
+            bit += width as usize;
+
+            if code == clear {
+                table = fresh();
+                width = min_size + 1;
+                prev = None;
+                continue;
+            }
+
+            if code == end {
+                return out;
+            }
+
+            let entry = match (table.get(code), &prev) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(p)) if code == table.len() => [p.as_slice(), &p[..1]].concat(),
+                _ => panic!("Code {code} isn't in the table"),
+            };
+
+            if let Some(p) = prev {
+                if table.len() < 4096 {
+                    table.push([p.as_slice(), &entry[..1]].concat());
+                }
+            }
+
+            if table.len() == 1 << width && width < 12 {
+                width += 1;
+            }
+
+            out.extend(&entry);
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_codes_decode_to_the_pixels() {
+        // Enough pixels, and varied enough, to fill the table and start it again
+        let mut seed = 1u32;
+        let noisy: Vec<u8> = (0..40_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 16) as u8 % 6
+            })
+            .collect();
+
+        for pixels in [vec![], vec![2], vec![0; 5000], noisy] {
+            assert_eq!(unlzw(&lzw(&pixels, 3), 3), pixels);
+        }
+    }
+
+    #[test]
+    fn gif_frames_hold_each_step() {
+        let tiles = TileRegistry::default();
+        let board = Board::new("c.c\nc..").unwrap();
+        let solution = Solution {
+            start: (0, 1),
+            moves: vec![Dir::Up, Dir::Right],
+        };
+        let gif = gif(
+            &board,
+            &solution,
+            &Palette::default(),
+            4,
+            Duration::from_millis(200),
+            &tiles,
+        );
+
+        // Header, screen, colour table and looping extension, then each frame's control
+        // extension, descriptor and code size
+        let mut at = 6 + 7 + 8 * 3 + 19;
+        let mut frames = Vec::new();
+
+        while gif[at] == 0x21 {
+            at += 8 + 10 + 1;
+
+            let mut data = Vec::new();
+
+            while gif[at] > 0 {
+                data.extend(&gif[at + 1..at + 1 + gif[at] as usize]);
+                at += 1 + gif[at] as usize;
+            }
+
+            at += 1;
+            frames.push(unlzw(&data, 3));
+        }
+
+        let steps: Vec<Vec<u8>> = steps(&board, &solution, &tiles)
+            .iter()
+            .map(|b| Raster::draw(b, 4).pixels)
+            .collect();
+
+        assert_eq!(frames, steps);
+        assert_eq!(gif[at], 0x3b);
+    }
+
+    #[test]
+    fn png_frames_decode_to_the_palettes_colours() {
+        let tiles = TileRegistry::default();
+        let board = Board::new("cr").unwrap();
+        let solution = Solution {
+            start: (0, 0),
+            moves: Vec::new(),
+        };
+        let palette = Palette::dark();
+
+        let frames = png_frames(&board, &solution, &palette, 3, &tiles);
+        let decoded = ::image::load_from_memory(&frames[0]).unwrap().to_rgb8();
+
+        assert_eq!(decoded.dimensions(), (6, 3));
+        assert_eq!(decoded.get_pixel(1, 1).0, palette.head);
+        assert_eq!(decoded.get_pixel(4, 1).0, palette.rock);
+    }
+}
@@ -0,0 +1,417 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn obj<K: Into<String>>(fields: impl IntoIterator<Item = (K, Json)>) -> Self {
+        Json::Obj(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Obj(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, Json)]> {
+        match self {
+            Json::Obj(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Num(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Arr(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: input.char_indices().peekable(),
+            depth: 0,
+        };
+
+        let value = parser.value()?;
+
+        parser.skip_whitespace();
+
+        match parser.chars.next() {
+            None => Ok(value),
+            Some((i, c)) => Err(format!("Unexpected {c:?} at offset {i}")),
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Num(n as f64)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(n: u64) -> Self {
+        Json::Num(n as f64)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Self {
+        Json::Num(n)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::Str(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::Str(s)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(items: Vec<T>) -> Self {
+        Json::Arr(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Num(n) if n.is_finite() => write!(f, "{n}"),
+            Json::Num(_) => write!(f, "null"),
+            Json::Str(s) => write_str(f, s),
+            Json::Arr(items) => {
+                write!(f, "[")?;
+
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write!(f, "{item}")?;
+                }
+
+                write!(f, "]")
+            }
+            Json::Obj(fields) => {
+                write!(f, "{{")?;
+
+                for (i, (k, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write_str(f, k)?;
+                    write!(f, ":{v}")?;
+                }
+
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_str(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+/// How deeply arrays and objects may nest, well short of where parsing them would overflow
+/// the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    /// The arrays and objects the parser is inside.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((i, c)) => Err(format!("Expected {expected:?}, found {c:?} at offset {i}")),
+            None => Err(format!("Expected {expected:?}, found end of input")),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        word.chars().try_for_each(|c| self.expect(c))?;
+
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+
+        match self.chars.peek().copied() {
+            None => Err("Unexpected end of input".to_string()),
+            Some((_, 'n')) => self.literal("null", Json::Null),
+            Some((_, 't')) => self.literal("true", Json::Bool(true)),
+            Some((_, 'f')) => self.literal("false", Json::Bool(false)),
+            Some((_, '"')) => self.string().map(Json::Str),
+            Some((_, '[' | '{')) if self.depth == MAX_DEPTH => {
+                Err(format!("Nested more than {MAX_DEPTH} deep"))
+            }
+            Some((_, '[')) => {
+                self.depth += 1;
+                let array = self.array();
+                self.depth -= 1;
+                array
+            }
+            Some((_, '{')) => {
+                self.depth += 1;
+                let object = self.object();
+                self.depth -= 1;
+                object
+            }
+            Some((_, c)) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some((i, c)) => Err(format!("Unexpected {c:?} at offset {i}")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let mut text = String::new();
+
+        while let Some((_, c)) = self
+            .chars
+            .next_if(|(_, c)| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            text.push(c);
+        }
+
+        text.parse()
+            .map(Json::Num)
+            .map_err(|_| format!("Invalid number {text:?}"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut s = String::new();
+
+        loop {
+            match self.chars.next() {
+                None => return Err("Unterminated string".to_string()),
+                Some((_, '"')) => return Ok(s),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 'r')) => s.push('\r'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, 'b')) => s.push('\u{8}'),
+                    Some((_, 'f')) => s.push('\u{c}'),
+                    Some((_, 'u')) => {
+                        let mut code = self.hex4()?;
+
+                        // Characters past the first 64k come as a surrogate pair
+                        if (0xd800..0xdc00).contains(&code) {
+                            let low = match (self.chars.next(), self.chars.next()) {
+                                (Some((_, '\\')), Some((_, 'u'))) => self.hex4()?,
+                                _ => return Err(format!("Unpaired surrogate \\u{code:04x}")),
+                            };
+
+                            if !(0xdc00..0xe000).contains(&low) {
+                                return Err(format!("Unpaired surrogate \\u{code:04x}"));
+                            }
+
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        }
+
+                        let c = char::from_u32(code)
+                            .ok_or_else(|| format!("Unpaired surrogate \\u{code:04x}"))?;
+
+                        s.push(c);
+                    }
+                    Some((_, c)) => s.push(c),
+                    None => return Err("Unterminated string".to_string()),
+                },
+                Some((_, c)) => s.push(c),
+            }
+        }
+    }
+
+    /// The four hex digits after a `\u`.
+    fn hex4(&mut self) -> Result<u32, String> {
+        let code: String = (0..4)
+            .filter_map(|_| self.chars.next())
+            .map(|(_, c)| c)
+            .collect();
+
+        Some(&code)
+            .filter(|c| c.len() == 4 && c.chars().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|c| u32::from_str_radix(c, 16).ok())
+            .ok_or_else(|| format!("Invalid escape \\u{code}"))
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        self.skip_whitespace();
+
+        let mut items = Vec::new();
+
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(Json::Arr(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(Json::Arr(items)),
+                Some((i, c)) => return Err(format!("Unexpected {c:?} at offset {i}")),
+                None => return Err("Unterminated array".to_string()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        self.skip_whitespace();
+
+        let mut fields = Vec::new();
+
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(Json::Obj(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+
+            let key = self.string()?;
+
+            self.skip_whitespace();
+            self.expect(':')?;
+
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(Json::Obj(fields)),
+                Some((i, c)) => return Err(format!("Unexpected {c:?} at offset {i}")),
+                None => return Err("Unterminated object".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_round_trip() {
+        let text = r#"{"a":[1,2.5,-3],"b":{"c":null,"d":true},"e":"x\"y\n"}"#;
+        let json = Json::parse(text).unwrap();
+
+        assert_eq!(json.to_string(), text);
+        assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+    }
+
+    #[test]
+    fn escapes_decode_including_surrogate_pairs() {
+        let json = Json::parse(r#""\u00e9 \ud83d\ude00 \t""#).unwrap();
+
+        assert_eq!(json.as_str(), Some("\u{e9} \u{1f600} \t"));
+    }
+
+    #[test]
+    fn bad_escapes_are_errors() {
+        for text in [
+            r#""\ud83d""#,
+            r#""\ud83d\u0041""#,
+            r#""\ude00""#,
+            r#""\u12""#,
+            r#""\u+123""#,
+        ] {
+            assert!(Json::parse(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error_not_a_crash() {
+        let deep = "[".repeat(200_000);
+
+        assert!(Json::parse(&deep).unwrap_err().contains("Nested"));
+
+        let ok = "[".repeat(MAX_DEPTH) + "]".repeat(MAX_DEPTH).as_str();
+
+        assert!(Json::parse(&ok).is_ok());
+    }
+
+    #[test]
+    fn trailing_input_and_truncation_are_errors() {
+        for text in ["{} x", "[1,", "{\"a\"", "tru", "\"abc"] {
+            assert!(Json::parse(text).is_err(), "{text}");
+        }
+    }
+}
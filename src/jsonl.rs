@@ -0,0 +1,65 @@
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use crate::api;
+use crate::json::Json;
+use crate::session::Session;
+
+/// Answer one JSON request per input line with one JSON response per output line until
+/// stdin closes. Each request names its `op` (`solve`, `validate`, `generate`, or `open`
+/// and `toggle` for an editing session) and may carry an `id`, which is echoed back.
+pub fn run(time_limit: Duration) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+    let mut session = None;
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = respond(&line, time_limit, &mut session);
+
+        writeln!(stdout, "{response}")?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+fn respond(line: &str, time_limit: Duration, session: &mut Option<Session>) -> Json {
+    let request = match Json::parse(line) {
+        Ok(request) => request,
+        Err(e) => return Json::obj([("error", Json::Str(format!("Invalid JSON: {e}")))]),
+    };
+
+    let result = match request.get("op").and_then(Json::as_str) {
+        Some("solve") => api::solve(&request, time_limit),
+        Some("validate") => api::validate(&request),
+        Some("generate") => api::generate(&request, time_limit),
+        Some("open") => api::open_session(&request, time_limit).map(|(opened, result)| {
+            *session = Some(opened);
+            result
+        }),
+        Some("toggle") => match session {
+            Some(session) => api::toggle(session, &request, time_limit),
+            None => Err("No session is open".to_string()),
+        },
+        Some(op) => Err(format!("Unknown op {op:?}")),
+        None => Err("Missing \"op\"".to_string()),
+    };
+
+    let mut fields = match result {
+        Ok(Json::Obj(fields)) => fields,
+        Ok(other) => vec![("result".to_string(), other)],
+        Err(e) => vec![("error".to_string(), Json::Str(e))],
+    };
+
+    if let Some(id) = request.get("id") {
+        fields.insert(0, ("id".to_string(), id.clone()));
+    }
+
+    Json::Obj(fields)
+}
@@ -0,0 +1,1892 @@
+//! A solver for snake puzzles: boards of rocks and cherries where a snake, placed on any
+//! cherry, slides in straight lines eating cherries until it's blocked, and has to eat
+//! them all.
+
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io::{Read, Write};
+use std::iter::successors;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+pub mod analysis;
+pub mod api;
+pub mod astar;
+#[cfg(feature = "cli")]
+pub mod batch;
+mod bitboard;
+#[cfg(feature = "cli")]
+pub mod cache;
+#[cfg(feature = "chat")]
+pub mod chat;
+pub mod compare;
+pub mod dataset;
+pub mod debugger;
+pub mod error;
+pub mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod generate;
+pub mod graph;
+pub mod greedy;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hashing;
+pub mod hint;
+pub mod id;
+pub mod ida;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod json;
+#[cfg(feature = "cli")]
+pub mod jsonl;
+pub mod metrics;
+pub mod objective;
+pub mod order;
+pub mod parallel;
+pub mod pddl;
+#[cfg(feature = "serde")]
+mod persist;
+pub mod profile;
+#[cfg(feature = "cli")]
+pub mod progress;
+pub mod puzzles;
+pub mod rng;
+pub mod rules;
+#[cfg(feature = "script")]
+pub mod script;
+#[cfg(feature = "cli")]
+pub mod server;
+pub mod session;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod svg;
+pub mod symmetry;
+pub mod tiles;
+pub mod transform;
+pub mod tune;
+#[cfg(feature = "cli")]
+pub mod websocket;
+pub mod widget;
+#[cfg(feature = "gui")]
+pub mod window;
+#[cfg(feature = "cli")]
+pub mod worker;
+pub mod xsb;
+mod zobrist;
+
+pub use error::SnakeError;
+// The bindings' scaffolding looks for its tag at the crate root
+#[cfg(feature = "ffi")]
+use ffi::UniFfiTag;
+use hashing::{FxBuildHasher, HashKind};
+use order::StartOrder;
+use rules::{Movement, RuleSet};
+use tiles::TileRegistry;
+
+/// Diagnostics from inside the search, only with the `debug-log` feature.
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "debug-log") {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Time the rest of the enclosing block as a profiling span named `$name`. Does nothing
+/// without the `profile` feature.
+macro_rules! span {
+    ($name:literal) => {
+        let _span = cfg!(feature = "profile").then(|| profile::Span::enter($name));
+    };
+}
+
+pub type Position = (usize, usize);
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tile {
+    Rock,
+    Cherry,
+    /// Open floor, which the snake may cross but needn't: only cherries have to be eaten.
+    Empty,
+    SnakeBody,
+    SnakeHead,
+    Custom(u8),
+}
+
+/// With the `serde` feature, written as its rules and rows: see `persist.rs`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "persist::BoardForm", try_from = "persist::BoardForm")
+)]
+pub struct Board {
+    /// The tiles' [`zobrist::grid_key`], first so that comparing boards that differ
+    /// usually stops here.
+    key: u64,
+    tiles: Vec<Vec<Tile>>,
+    rules: RuleSet,
+    /// Under [`RuleSet::decay`], each body cell still solid and the moves until it clears,
+    /// sorted by cell. Always empty otherwise.
+    ages: Vec<(Position, usize)>,
+}
+
+/// Hashes the key in place of the tiles it's kept in step with, so that looking a board up
+/// doesn't go over every cell.
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+        self.rules.hash(state);
+        self.ages.hash(state);
+    }
+}
+
+/// The board as a board file, the way [`Board::to_text`] writes it with the default tiles.
+/// For colour, draw it with a [`widget::BoardWidget`] instead.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_text(&TileRegistry::default()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dir {
+    Up,
+    Down,
+    Right,
+    Left,
+    /// Only with [`RuleSet::diagonal`], as are the rest.
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Dir {
+    /// The direction's initial, as accepted by [`Dir::from_str`]. Diagonals are the keys
+    /// around S on a QWERTY keyboard: Q, E, Z and C.
+    pub fn letter(self) -> char {
+        match self {
+            Dir::Up => 'U',
+            Dir::Down => 'D',
+            Dir::Right => 'R',
+            Dir::Left => 'L',
+            Dir::UpLeft => 'Q',
+            Dir::UpRight => 'E',
+            Dir::DownLeft => 'Z',
+            Dir::DownRight => 'C',
+        }
+    }
+
+    /// How far one step in this direction moves along x and y.
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Dir::Up => (0, -1),
+            Dir::Down => (0, 1),
+            Dir::Right => (1, 0),
+            Dir::Left => (-1, 0),
+            Dir::UpLeft => (-1, -1),
+            Dir::UpRight => (1, -1),
+            Dir::DownLeft => (-1, 1),
+            Dir::DownRight => (1, 1),
+        }
+    }
+}
+
+impl FromStr for Dir {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Up" | "U" => Ok(Dir::Up),
+            "Down" | "D" => Ok(Dir::Down),
+            "Right" | "R" => Ok(Dir::Right),
+            "Left" | "L" => Ok(Dir::Left),
+            "UpLeft" | "Q" => Ok(Dir::UpLeft),
+            "UpRight" | "E" => Ok(Dir::UpRight),
+            "DownLeft" | "Z" => Ok(Dir::DownLeft),
+            "DownRight" | "C" => Ok(Dir::DownRight),
+            _ => Err(format!("Unknown direction {s:?}")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Invalid {
+    BadStart(Position),
+    Stuck(usize, Dir),
+    Incomplete(usize),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Solution {
+    pub start: Position,
+    pub moves: Vec<Dir>,
+}
+
+/// Moves written as their letters, like `URDL` or `U R R D L`: spaces and commas between
+/// them are ignored.
+pub fn parse_moves(moves: &str) -> Result<Vec<Dir>, String> {
+    moves
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .map(|c| c.to_string().parse())
+        .collect()
+}
+
+/// The start and the moves' letters, like `2,1 RDLUR`, which is how [`Solution::from_str`]
+/// reads them.
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (x, y) = self.start;
+        let letters: String = self.moves.iter().map(|d| d.letter()).collect();
+
+        write!(f, "{x},{y} {letters}")
+    }
+}
+
+impl FromStr for Solution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (start, moves) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+
+        let start = start
+            .split_once(',')
+            .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+            .ok_or(format!("Expected a start like 2,1, not {start:?}"))?;
+
+        Ok(Solution {
+            start,
+            moves: parse_moves(moves)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub solution: Option<Solution>,
+    /// Without a solution, the line that ate the most cherries.
+    pub partial: Option<Partial>,
+    pub states: usize,
+    /// The deadline or state budget ran out before the search could finish.
+    pub timed_out: bool,
+    pub stats: SearchStats,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Partial {
+    pub solution: Solution,
+    pub cherries_left: usize,
+}
+
+/// How the search went, for reports and tuning.
+#[derive(Debug, Clone, Default)]
+pub struct SearchStats {
+    /// Every starting position tried, in order.
+    pub starts: Vec<StartStats>,
+    /// Moves in the solution, if one was found.
+    pub depth: Option<usize>,
+    /// Boards generated again after already being queued.
+    pub duplicates: usize,
+    /// Boards dropped because some cherry could no longer be reached.
+    pub stranded: usize,
+    /// How many expanded states had the snake's head on each cell.
+    pub heat: HashMap<Position, usize>,
+    /// The most bytes the queue and visited map were estimated to hold at once.
+    pub peak_memory: usize,
+    /// The most states waiting to be expanded at once.
+    pub peak_queue: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct StartStats {
+    pub start: Position,
+    pub expanded: usize,
+    pub solved: bool,
+    pub timed_out: bool,
+    /// The search from this start outgrew [`Constraints::max_memory`] and was dropped for
+    /// the next start's. Such a start also counts as timed out, since it wasn't finished.
+    pub out_of_memory: bool,
+    /// The deepest layer the search from this start reached.
+    pub depth: usize,
+    /// See [`Board::state_bound`].
+    pub bound: u64,
+    pub elapsed: Duration,
+}
+
+impl StartStats {
+    /// The share of the bound on reachable states that was expanded.
+    pub fn explored(&self) -> f64 {
+        self.expanded as f64 / self.bound.max(1) as f64
+    }
+}
+
+impl SearchStats {
+    /// The branching factor `b` a uniform tree would need to hold as many nodes as the
+    /// solving start expanded within the solution depth: `N + 1 = 1 + b + ... + b^d`.
+    pub fn branching_factor(&self) -> Option<f64> {
+        let depth = self.depth.filter(|&d| d > 0)? as i32;
+        let nodes = self.starts.last()?.expanded as f64;
+        let tree = |b: f64| (0..=depth).map(|i| b.powi(i)).sum::<f64>();
+
+        let (mut lo, mut hi) = (1.0, nodes.max(1.0));
+
+        for _ in 0..64 {
+            let mid = (lo + hi) / 2.0;
+
+            if tree(mid) < nodes + 1.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        Some(lo)
+    }
+}
+
+impl Board {
+    pub fn new(input: &str) -> Result<Self, SnakeError> {
+        Self::parse(input, &TileRegistry::default())
+    }
+
+    /// Parse a board that may contain custom tiles registered in `tiles`, and may start with
+    /// a [`RuleSet`] header and a `tiles:` header of glyphs the board uses in place of the
+    /// usual ones, as [`TileRegistry::parse_aliases`] reads them, in either order.
+    pub fn parse(input: &str, tiles: &TileRegistry) -> Result<Self, SnakeError> {
+        span!("parse");
+
+        // Blank lines before the board, so that errors can point at the right line
+        let blank = |s: &str| s[..s.len() - s.trim_start().len()].matches('\n').count();
+
+        let mut trimmed = input.trim();
+        let mut skipped = blank(input);
+        let mut rules = RuleSet::default();
+        let mut aliases = Vec::new();
+
+        loop {
+            let (header, board) = trimmed.split_once('\n').unwrap_or((trimmed, ""));
+
+            if let Some(words) = header.strip_prefix(RuleSet::HEADER) {
+                rules = RuleSet::parse(words, skipped + 1)?;
+            } else if let Some(pairs) = header.strip_prefix(TileRegistry::HEADER) {
+                aliases = tiles
+                    .parse_aliases(pairs)
+                    .map_err(|pair| SnakeError::Tiles {
+                        line: skipped + 1,
+                        pair,
+                    })?;
+            } else {
+                break;
+            }
+
+            skipped += 1 + blank(board);
+            trimmed = board.trim();
+        }
+
+        let tile = |glyph: char| match aliases.iter().find(|(g, _)| *g == glyph) {
+            Some(&(_, tile)) => Some(tile),
+            None => tiles.tile(glyph),
+        };
+
+        let tiles = trimmed
+            .lines()
+            .enumerate()
+            .map(|(y, line)| {
+                let indent = line.len() - line.trim_start().len();
+
+                line.trim()
+                    .chars()
+                    .enumerate()
+                    .map(|(x, glyph)| {
+                        tile(glyph).ok_or(SnakeError::Parse {
+                            line: skipped + y + 1,
+                            column: indent + x + 1,
+                            glyph,
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            key: zobrist::grid_key(&tiles),
+            tiles,
+            rules,
+            ages: Vec::new(),
+        })
+    }
+
+    /// A board of `tiles` played by the default rules.
+    pub(crate) fn from_tiles(tiles: Vec<Vec<Tile>>) -> Self {
+        Self {
+            key: zobrist::grid_key(&tiles),
+            tiles,
+            rules: RuleSet::default(),
+            ages: Vec::new(),
+        }
+    }
+
+    pub fn rules(&self) -> RuleSet {
+        self.rules
+    }
+
+    /// The same board, played by `rules` instead.
+    pub fn with_rules(self, rules: RuleSet) -> Self {
+        Self { rules, ..self }
+    }
+
+    /// Parse every board in `input`, where boards are separated by blank lines, each on its
+    /// own so that one bad board doesn't hide the rest. Errors give lines in `input` as a
+    /// whole.
+    pub fn parse_all(input: &str, tiles: &TileRegistry) -> Vec<Result<Self, SnakeError>> {
+        let lines: Vec<&str> = input.lines().collect();
+        let mut boards = Vec::new();
+        let mut start = None;
+
+        // A blank line past the end closes the last board
+        for (i, line) in lines.iter().chain([&""]).enumerate() {
+            match (line.trim().is_empty(), start) {
+                (false, None) => start = Some(i),
+                (true, Some(from)) => {
+                    // Padded with the lines before it, so that errors point at the right one
+                    let text = "\n".repeat(from) + lines[from..i].join("\n").as_str();
+
+                    boards.push(Self::parse(&text, tiles));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        boards
+    }
+
+    /// Read and parse a board, which may contain custom tiles registered in `tiles`.
+    pub fn read(mut reader: impl Read, tiles: &TileRegistry) -> Result<Self, SnakeError> {
+        let mut input = String::new();
+
+        reader.read_to_string(&mut input)?;
+
+        Self::parse(&input, tiles)
+    }
+
+    /// Check that the board can be searched: at most one head, and no body without one.
+    pub fn validate(&self) -> Result<(), SnakeError> {
+        let count = |tile| self.tiles.iter().flatten().filter(|&&t| t == tile).count();
+
+        match (count(Tile::SnakeHead), count(Tile::SnakeBody)) {
+            (0, 0) | (1, _) => Ok(()),
+            (0, _) => Err(SnakeError::InvalidState(
+                "the snake has a body but no head".to_string(),
+            )),
+            (n, _) => Err(SnakeError::InvalidState(format!(
+                "the board has {n} snake heads"
+            ))),
+        }
+    }
+
+    pub fn cherry_count(&self) -> usize {
+        self.tiles
+            .iter()
+            .map(|row| row.iter().filter(|t| matches!(t, Tile::Cherry)).count())
+            .sum()
+    }
+
+    /// How many cherries can no longer be eaten. The head only ever moves onto cherries and
+    /// empty floor, so a cherry that no path of those joins to the head is cut off for
+    /// good. Always none where the body can be crossed, or with custom tiles, which might
+    /// do anything.
+    pub fn stranded_cherries(&self) -> usize {
+        let Some(head) = self.get_snake_head() else {
+            return 0;
+        };
+
+        let custom = self
+            .tiles
+            .iter()
+            .flatten()
+            .any(|t| matches!(t, Tile::Custom(_)));
+
+        if custom || !self.rules.body_blocks || self.rules.decay.is_some() {
+            return 0;
+        }
+
+        let mut seen: Vec<Vec<bool>> = self
+            .tiles
+            .iter()
+            .map(|row| vec![false; row.len()])
+            .collect();
+        let mut stack = vec![head];
+        let mut reached = 0;
+
+        while let Some(p) = stack.pop() {
+            for &dir in self.rules.dirs() {
+                let Some((x, y)) = self.neighbour(p, dir) else {
+                    continue;
+                };
+
+                let tile = self.tiles[y][x];
+
+                if matches!(tile, Tile::Cherry | Tile::Empty) && !seen[y][x] {
+                    seen[y][x] = true;
+                    reached += usize::from(tile == Tile::Cherry);
+                    stack.push((x, y));
+                }
+            }
+        }
+
+        self.cherry_count() - reached
+    }
+
+    pub fn starting_positions(&self) -> impl Iterator<Item = Position> + Clone + '_ {
+        self.tiles.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(x, t)| matches!(t, Tile::Cherry).then_some((x, y)))
+        })
+    }
+
+    /// Where a search can start: the snake's head if the board is from a game in progress,
+    /// otherwise any cherry.
+    pub fn starts(&self) -> impl Iterator<Item = Position> + Clone + '_ {
+        let head = self.get_snake_head();
+
+        self.starting_positions()
+            .filter(move |_| head.is_none())
+            .chain(head)
+    }
+
+    /// The board with the snake at `start`, if it can start there: any of
+    /// [`Board::starts`], so on a game in progress only the head it already has.
+    pub fn start_at(&self, start: Position) -> Option<Self> {
+        match self.get_snake_head() {
+            Some(head) => (head == start).then(|| self.clone()),
+            None => self
+                .starts()
+                .any(|p| p == start)
+                .then(|| self.place_snake(start)),
+        }
+    }
+
+    /// An upper bound on the states a search from `start` can reach: the head is on one of
+    /// the cells connected to `start`, and of the cherries and empty floor among them any
+    /// subset other than the start itself may be under the body. Saturates rather than
+    /// overflowing.
+    pub fn state_bound(&self, start: Position, tiles: &TileRegistry) -> u64 {
+        let region = self.region(start, tiles);
+        let cherries = region
+            .iter()
+            .filter(|&&(x, y)| matches!(self.tiles[y][x], Tile::Cherry | Tile::Empty))
+            .count() as u32;
+
+        let subsets = 1u64
+            .checked_shl(cherries.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+
+        (region.len() as u64).saturating_mul(subsets)
+    }
+
+    /// `start` and every enterable cell connected to it by the moves the rules allow.
+    pub fn region(&self, start: Position, tiles: &TileRegistry) -> HashSet<Position> {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+
+        while let Some(p) = stack.pop() {
+            for &dir in self.rules.dirs() {
+                let Some((nx, ny)) = self.neighbour(p, dir) else {
+                    continue;
+                };
+
+                if self.enterable((nx, ny), tiles) && seen.insert((nx, ny)) {
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        seen
+    }
+
+    /// The cell one step from `(x, y)` in `dir`, coming back on at the opposite edge if the
+    /// rules wrap. `None` if that's off the board.
+    fn neighbour(&self, (x, y): Position, dir: Dir) -> Option<Position> {
+        let (dx, dy) = dir.delta();
+        let step = |from: usize, by: isize, len: usize| match self.rules.wrap {
+            true => (from as isize + by)
+                .checked_rem_euclid(len as isize)
+                .map(|n| n as usize),
+            false => from.checked_add_signed(by).filter(|&n| n < len),
+        };
+
+        let ny = step(y, dy, self.tiles.len())?;
+        let nx = step(x, dx, self.tiles[ny].len())?;
+
+        Some((nx, ny))
+    }
+
+    /// Whether the snake can move onto the cell at `(x, y)`: its own body only if the rules
+    /// let it cross it, or it has cleared.
+    fn enterable(&self, (x, y): Position, tiles: &TileRegistry) -> bool {
+        match self.tiles[y][x] {
+            Tile::SnakeBody => match self.rules.decay {
+                _ if !self.rules.body_blocks => true,
+                Some(_) => !self.ages.iter().any(|&(p, _)| p == (x, y)),
+                None => false,
+            },
+            tile => tiles.behavior(tile).enterable(),
+        }
+    }
+
+    pub fn place_snake(&self, p: Position) -> Self {
+        let mut board = self.clone();
+
+        board.set(p, Tile::SnakeHead);
+        board
+    }
+
+    /// Put `tile` at `(x, y)`, keeping the key up to date.
+    pub(crate) fn set(&mut self, (x, y): Position, tile: Tile) {
+        let old = std::mem::replace(&mut self.tiles[y][x], tile);
+
+        self.key ^= zobrist::key((x, y), old) ^ zobrist::key((x, y), tile);
+    }
+
+    pub fn get_snake_head(&self) -> Option<Position> {
+        self.tiles.iter().enumerate().find_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .find_map(|(x, t)| matches!(t, Tile::SnakeHead).then_some(x))
+                .map(|x| (x, y))
+        })
+    }
+
+    /// Move snake, letting `tiles` decide what it can slide through. `None` if the snake
+    /// hasn't been placed.
+    pub fn move_snake(self, dir: Dir, tiles: &TileRegistry) -> Option<Self> {
+        self.slide(dir, tiles, None)
+    }
+
+    /// [`Board::move_snake`], also adding every cell the head enters to `cells`.
+    fn slide(
+        mut self,
+        dir: Dir,
+        tiles: &TileRegistry,
+        mut cells: Option<&mut Vec<Position>>,
+    ) -> Option<Self> {
+        let start = self.get_snake_head()?;
+        let (mut sx, mut sy) = start;
+
+        debug_log!("Snake wants to move {dir:?}.");
+
+        loop {
+            debug_log!("Snake is currently at ({sx}, {sy}).");
+
+            let Some((nx, ny)) = self.neighbour((sx, sy), dir) else {
+                debug_log!("Snake is at the wall. Snake remains at ({sx}, {sy}).");
+
+                break;
+            };
+
+            // Wrapping round a board the body doesn't block could otherwise go on forever
+            if (nx, ny) == start {
+                debug_log!("Snake has come all the way round. Snake remains at ({sx}, {sy}).");
+
+                break;
+            }
+
+            debug_log!("Snake is trying to move to ({nx}, {ny}).");
+
+            let tile = self.tiles[ny][nx];
+
+            if !self.enterable((nx, ny), tiles) {
+                debug_log!("The way is blocked by {tile:?}. Snake remains at ({sx}, {sy}).");
+
+                break;
+            }
+
+            debug_log!("The way is clear. Snake proceeds.");
+
+            let behavior = tiles.behavior(tile);
+
+            self.set((sx, sy), Tile::SnakeBody);
+            self.set((nx, ny), Tile::SnakeHead);
+
+            if let Some(n) = self.rules.decay {
+                // One more than it has left, as every age drops by one once the move is over
+                self.ages.retain(|&(p, _)| p != (sx, sy));
+                self.ages.push(((sx, sy), n + 1));
+            }
+
+            behavior.on_enter(&mut self, (nx, ny));
+
+            if let Some(cells) = cells.as_mut() {
+                cells.push((nx, ny));
+            }
+
+            if behavior.stops_slide() || self.rules.movement == Movement::Step {
+                debug_log!("{tile:?} stops the snake. Snake remains at ({nx}, {ny}).");
+
+                (sx, sy) = (nx, ny);
+                break;
+            }
+
+            (sx, sy) = (nx, ny);
+        }
+
+        // Standing still isn't a move, so the body only ages if the snake went somewhere
+        if self.rules.decay.is_some() && (sx, sy) != start {
+            self.ages.retain_mut(|(_, age)| {
+                *age -= 1;
+                *age > 0
+            });
+            self.ages.sort_unstable();
+        }
+
+        if let (Some(hooks), true) = (tiles.hooks(), (sx, sy) != start) {
+            hooks.after_move(&mut self, dir);
+        }
+
+        self.into()
+    }
+
+    pub fn moves<'a>(&'a self, tiles: &'a TileRegistry) -> impl Iterator<Item = (Dir, Self)> + 'a {
+        self.rules.dirs().iter().filter_map(move |&dir| {
+            self.clone()
+                .move_snake(dir, tiles)
+                .inspect(|new_board| {
+                    debug_log!("{} cherries left.", new_board.cherry_count());
+                })
+                .map(|new_board| (dir, new_board))
+        })
+    }
+
+    /// The board after playing `moves`, or [`Invalid::Stuck`] at the first that doesn't
+    /// move the snake at all, as every move does on a board it hasn't been placed on.
+    pub fn apply_moves(&self, moves: &[Dir], tiles: &TileRegistry) -> Result<Board, Invalid> {
+        moves
+            .iter()
+            .enumerate()
+            .try_fold(self.clone(), |b, (i, &dir)| {
+                let head = b.get_snake_head();
+
+                b.move_snake(dir, tiles)
+                    .filter(|m| m.get_snake_head() != head)
+                    .ok_or(Invalid::Stuck(i, dir))
+            })
+    }
+
+    /// Each direction the snake can move in, with where its head would end up. Directions
+    /// where it can't move at all are left out, as is everything if it hasn't been placed.
+    pub fn legal_moves(&self, tiles: &TileRegistry) -> Vec<(Dir, Position)> {
+        let Some(head) = self.get_snake_head() else {
+            return Vec::new();
+        };
+
+        self.moves(tiles)
+            .filter_map(|(dir, board)| Some((dir, board.get_snake_head()?)))
+            .filter(|&(_, end)| end != head)
+            .collect()
+    }
+}
+
+/// A search state written as `depth x,y eaten`, where `x,y` is the head and `eaten` lists
+/// the cells the body covers as `x,y;x,y;...` in reading order, or `-` if there are none.
+/// Stable and free of whitespace inside fields, for trace logs and snapshot tests.
+pub struct StateDump<'a> {
+    pub board: &'a Board,
+    pub depth: usize,
+}
+
+impl fmt::Display for StateDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cell = |(x, y): Position| format!("{x},{y}");
+
+        let eaten: Vec<String> = self
+            .board
+            .tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, t)| **t == Tile::SnakeBody)
+                    .map(move |(x, _)| cell((x, y)))
+            })
+            .collect();
+
+        let head = self.board.get_snake_head().map_or("-".to_string(), cell);
+        let eaten = match eaten.is_empty() {
+            true => "-".to_string(),
+            false => eaten.join(";"),
+        };
+
+        write!(f, "{} {head} {eaten}", self.depth)
+    }
+}
+
+/// Where a traced search tells of every move it tries. Any [`Write`] takes them as lines of
+/// the state as a [`StateDump`], then `dir x,y outcome` for the move and where it took the
+/// head.
+pub trait Trace {
+    /// `dir` was tried from `from`, `depth` moves from the start, and led to `to`. The
+    /// outcome is `new`, `seen`, `stuck` or `stranded`.
+    fn tried(&mut self, from: &Board, depth: usize, dir: Dir, to: &Board, outcome: &str);
+}
+
+impl<W: Write + ?Sized> Trace for W {
+    fn tried(&mut self, from: &Board, depth: usize, dir: Dir, to: &Board, outcome: &str) {
+        let state = StateDump { board: from, depth };
+        let (nx, ny) = to.get_snake_head().unwrap_or_default();
+
+        let _ = writeln!(self, "{state} {} {nx},{ny} {outcome}", dir.letter());
+    }
+}
+
+/// The line that led to `board`. Each move is found by replaying the moves from its parent,
+/// since with wrapping or diagonals the heads alone don't say which way the snake went.
+fn solution(
+    board: &Board,
+    history: &HashMap<Board, Option<Board>>,
+    tiles: &TileRegistry,
+) -> Solution {
+    line(&Whole { board, tiles }, board, history)
+}
+
+/// [`solution`] for states of any [`Space`].
+fn line<S: Space>(
+    space: &S,
+    state: &S::State,
+    history: &HashMap<S::State, Option<S::State>>,
+) -> Solution {
+    let mut path: Vec<&S::State> = successors(Some(state), {
+        |s| history.get(s).and_then(|sp| sp.as_ref())
+    })
+    .collect();
+
+    path.reverse();
+
+    let start = space
+        .head(path[0])
+        .expect("Search states always have a snake");
+
+    let moves = path
+        .windows(2)
+        .map(|window| {
+            space
+                .moves(window[0])
+                .into_iter()
+                .find(|(_, m)| m == window[1])
+                .map(|(dir, _)| dir)
+                .expect("Every state was reached by a move from its parent")
+        })
+        .collect();
+
+    Solution { start, moves }
+}
+
+/// Replay `moves` from `start`, checking that every move goes somewhere and that all
+/// cherries end up eaten.
+pub fn check(
+    board: &Board,
+    start: Position,
+    moves: &[Dir],
+    tiles: &TileRegistry,
+) -> Result<(), Invalid> {
+    let board = board
+        .start_at(start)
+        .ok_or(Invalid::BadStart(start))?
+        .apply_moves(moves, tiles)?;
+
+    match tiles.solved(&board) {
+        true => Ok(()),
+        false => Err(Invalid::Incomplete(board.cherry_count())),
+    }
+}
+
+/// Every cell the snake's head passes through while playing `solution`, in order,
+/// starting with the start cell.
+pub fn trail(board: &Board, solution: &Solution, tiles: &TileRegistry) -> Vec<Position> {
+    let mut board = board.place_snake(solution.start);
+    let mut cells = vec![solution.start];
+
+    for &dir in &solution.moves {
+        let Some(next) = board.slide(dir, tiles, Some(&mut cells)) else {
+            break;
+        };
+
+        board = next;
+    }
+
+    cells
+}
+
+/// `solution` as single-cell steps rather than slides: a slide across three cells becomes
+/// three steps in the same direction.
+pub fn steps(board: &Board, solution: &Solution, tiles: &TileRegistry) -> Vec<Dir> {
+    let mut board = board.place_snake(solution.start);
+    let mut steps = Vec::new();
+
+    for &dir in &solution.moves {
+        let mut cells = Vec::new();
+
+        let Some(next) = board.slide(dir, tiles, Some(&mut cells)) else {
+            break;
+        };
+
+        steps.extend(std::iter::repeat_n(dir, cells.len()));
+        board = next;
+    }
+
+    steps
+}
+
+/// Runs of the same direction, with their lengths.
+pub fn compress(moves: &[Dir]) -> Vec<(Dir, usize)> {
+    moves
+        .chunk_by(|a, b| a == b)
+        .map(|run| (run[0], run.len()))
+        .collect()
+}
+
+/// Solve `board`, trying every starting position in turn until one works.
+pub fn solve(board: &Board) -> Result<Outcome, SnakeError> {
+    board.validate()?;
+
+    Ok(solve_within(board, None, &TileRegistry::default()))
+}
+
+/// Search every starting position in turn, giving up once `deadline` passes.
+pub fn solve_within(board: &Board, deadline: Option<Instant>, tiles: &TileRegistry) -> Outcome {
+    solve_watched(board, deadline, tiles, &mut ())
+}
+
+/// How a search reached one of its states: the index of the state it moved from and the
+/// move it made, or `None` for where the snake was placed.
+type Step = Option<(u32, Dir)>;
+
+/// Each state's [`Step`], in the order the search first saw them. The visited map gives a
+/// state's index here, so that it holds each state once rather than with its parent too.
+struct Steps(Vec<Step>);
+
+impl Steps {
+    /// The line from `start` to the state at `index`. The moves are written down, so unlike
+    /// [`line`] nothing needs replaying.
+    fn line(&self, start: Position, mut index: u32) -> Solution {
+        let mut moves = Vec::new();
+
+        while let Some((parent, dir)) = self.0[index as usize] {
+            moves.push(dir);
+            index = parent;
+        }
+
+        moves.reverse();
+
+        Solution { start, moves }
+    }
+
+    /// Add `step`, returning its index.
+    fn push(&mut self, step: Step) -> u32 {
+        self.0.push(step);
+        (self.0.len() - 1) as u32
+    }
+}
+
+/// The queue of a breadth-first search: each state with its index in [`Steps`] and depth.
+type Queue<S> = VecDeque<(<S as Space>::State, u32, usize)>;
+
+/// Roughly how many bytes a search's queue, visited map and steps hold, counting what each
+/// state owns on the heap and the spare capacity of every collection.
+fn search_memory<S: Space, H>(
+    space: &S,
+    visited: &HashMap<S::State, u32, H>,
+    steps: &Steps,
+    queue: &Queue<S>,
+) -> usize {
+    use std::mem::size_of;
+
+    let heap = space.heap_size();
+
+    // Each visited entry owns its state and index, plus a control byte
+    let visited_bytes =
+        visited.capacity() * (size_of::<(S::State, u32)>() + 1) + visited.len() * heap;
+    let steps_bytes = steps.0.capacity() * size_of::<Step>();
+    let queue_bytes = queue.capacity() * size_of::<(S::State, u32, usize)>() + queue.len() * heap;
+
+    visited_bytes + steps_bytes + queue_bytes
+}
+
+/// Something a caller watching the search may want to show.
+#[derive(Debug, Clone)]
+pub enum Event {
+    Start(Position),
+    Progress {
+        depth: usize,
+        states: usize,
+        queued: usize,
+        /// Estimated share of the current start's reachable states already expanded.
+        explored: f64,
+        /// See [`search_memory`].
+        memory: usize,
+        cherries_left: usize,
+        best: Solution,
+    },
+    /// Every state `depth` moves from the current start has been expanded, so any solution
+    /// from it needs more moves than that.
+    Layer {
+        depth: usize,
+        /// States expanded from the current start so far.
+        states: usize,
+        elapsed: Duration,
+    },
+    /// A state was taken off the queue. Sent for every state, so watchers that don't need
+    /// it should return quickly.
+    Expanded {
+        depth: usize,
+        head: Position,
+        cherries_left: usize,
+    },
+    Solved(Solution),
+    /// The search from `start` ended without a solution, after expanding `states` states.
+    Abandoned {
+        start: Position,
+        states: usize,
+        timed_out: bool,
+    },
+}
+
+/// Where a search reports its [`Event`]s, so that the solver itself never writes to the
+/// terminal. Any closure taking an `Event` is one, and `()` ignores them all.
+pub trait ProgressSink {
+    fn report(&mut self, event: Event);
+
+    /// Whether the search should give up, as if its deadline had passed. Checked as often
+    /// as the deadline is.
+    fn cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(Event)> ProgressSink for F {
+    fn report(&mut self, event: Event) {
+        self(event)
+    }
+}
+
+impl ProgressSink for () {
+    fn report(&mut self, _: Event) {}
+}
+
+impl<S: ProgressSink> ProgressSink for Option<S> {
+    fn report(&mut self, event: Event) {
+        if let Some(sink) = self {
+            sink.report(event);
+        }
+    }
+}
+
+/// Like [`solve_within`], but reports each starting position as it's tried and, every 1024
+/// states, how deep the search is and the line that has eaten the most cherries so far.
+pub fn solve_watched(
+    board: &Board,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    watch: &mut dyn ProgressSink,
+) -> Outcome {
+    solve_traced(
+        board,
+        Constraints::default(),
+        StartOrder::default(),
+        deadline,
+        tiles,
+        watch,
+        None,
+    )
+}
+
+/// Like [`solve_within`], but giving up as if out of time once `cancel` is set, say by
+/// another thread. The outcome is timed out, with the best line and the statistics so far.
+pub fn solve_cancelable(
+    board: &Board,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    cancel: &AtomicBool,
+) -> Outcome {
+    solve_watched(board, deadline, tiles, &mut parallel::Cancel(cancel))
+}
+
+/// How far a search has got, as [`solve_with`] reports it: the periodic part of its
+/// [`Event`]s, for callers that only want to show progress.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchProgress {
+    /// States expanded so far, from every start.
+    pub states: usize,
+    pub queued: usize,
+    pub depth: usize,
+    /// What the best line so far left uneaten.
+    pub cherries_left: usize,
+    /// See [`StartStats::explored`].
+    pub explored: f64,
+    /// See [`SearchStats::peak_memory`].
+    pub memory: usize,
+}
+
+/// Like [`solve_within`], calling `progress` every 1024 states with how far the search has
+/// got. [`solve_watched`] reports every other [`Event`] as well.
+pub fn solve_with(
+    board: &Board,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    mut progress: impl FnMut(SearchProgress),
+) -> Outcome {
+    solve_watched(board, deadline, tiles, &mut |event: Event| {
+        if let Event::Progress {
+            depth,
+            states,
+            queued,
+            explored,
+            memory,
+            cherries_left,
+            ..
+        } = event
+        {
+            progress(SearchProgress {
+                states,
+                queued,
+                depth,
+                cherries_left,
+                explored,
+                memory,
+            });
+        }
+    })
+}
+
+/// Like [`solve_watched`], additionally telling `trace` of every move tried from every
+/// expanded state. Only solutions that meet `constraints` count, and starts are tried in
+/// `order`.
+pub fn solve_traced(
+    board: &Board,
+    constraints: Constraints,
+    order: StartOrder,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    watch: &mut dyn ProgressSink,
+    trace: Option<&mut dyn Trace>,
+) -> Outcome {
+    let starts = search_starts(board, constraints, order, tiles).into_iter();
+
+    search(board, starts, constraints, deadline, tiles, watch, trace)
+}
+
+/// [`canonical_starts`], or every start if `tiles` has [`tiles::Hooks`], which can play
+/// differently on a mirrored board.
+pub(crate) fn search_starts(
+    board: &Board,
+    constraints: Constraints,
+    order: StartOrder,
+    tiles: &TileRegistry,
+) -> Vec<Position> {
+    match tiles.hooks() {
+        Some(_) => order.sort(board, board.starts().collect()),
+        None => canonical_starts(board, constraints, order),
+    }
+}
+
+/// The starts worth trying, in `order`.
+fn canonical_starts(board: &Board, constraints: Constraints, order: StartOrder) -> Vec<Position> {
+    // Starts that mirror one already tried can't succeed where it failed, as long as the
+    // mirror leaves the end where it is
+    let symmetries: Vec<_> = symmetry::pruning(board)
+        .into_iter()
+        .filter(|&s| constraints.end.is_none_or(|e| symmetry::fixes(s, e, board)))
+        .collect();
+
+    order
+        .sort(board, board.starts().collect())
+        .into_iter()
+        .filter(|&p| symmetry::is_canonical(p, &symmetries, board))
+        .collect()
+}
+
+/// Search from `start` only, for solutions of at most `max_depth` moves.
+pub fn solve_from(
+    board: &Board,
+    start: Position,
+    max_depth: Option<usize>,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+) -> Outcome {
+    let constraints = Constraints {
+        max_depth,
+        ..Constraints::default()
+    };
+
+    search(
+        board,
+        [start].into_iter(),
+        constraints,
+        deadline,
+        tiles,
+        &mut (),
+        None,
+    )
+}
+
+/// What a search's solutions must satisfy beyond eating every cherry.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Constraints {
+    /// Don't look past this many moves.
+    pub max_depth: Option<usize>,
+    /// The cell the snake's head must finish on.
+    pub end: Option<Position>,
+    /// Give up after expanding this many states in total, like a deadline that doesn't
+    /// depend on how fast the machine is.
+    pub max_states: Option<usize>,
+    /// Drop a start once its search is estimated to hold more than this many bytes, as
+    /// [`SearchStats::peak_memory`] counts them, and go on to the next start.
+    pub max_memory: Option<usize>,
+    /// Queue states with more [`Board::stranded_cherries`] than the best line so far left
+    /// uneaten, which the search otherwise drops. They can't lead to a solution,
+    /// so this only makes the search slower, but shows what pruning saves.
+    pub no_prune: bool,
+    /// How the states already seen are hashed.
+    pub hasher: HashKind,
+}
+
+/// How a search holds its states. The whole [`Board`] always works, but a state is
+/// usually far less than a board: see [`bitboard::Packed`].
+trait Space {
+    type State: Clone + Eq + Hash;
+
+    /// The board being solved, before the snake is placed.
+    fn board(&self) -> &Board;
+    fn tiles(&self) -> &TileRegistry;
+    fn place(&self, p: Position) -> Self::State;
+    fn cherry_count(&self, state: &Self::State) -> usize;
+    /// Whether the puzzle is solved in `state`, ignoring any end it has to reach.
+    fn solved(&self, state: &Self::State) -> bool {
+        self.cherry_count(state) == 0
+    }
+    fn head(&self, state: &Self::State) -> Option<Position>;
+    fn moves(&self, state: &Self::State) -> Vec<(Dir, Self::State)>;
+    /// See [`Board::stranded_cherries`].
+    fn stranded(&self, state: &Self::State) -> usize;
+    fn unpack(&self, state: &Self::State) -> Board;
+    /// Bytes each state owns beyond its own size, for [`search_memory`].
+    fn heap_size(&self) -> usize;
+}
+
+/// States as whole boards, which is all that works with custom tiles.
+struct Whole<'a> {
+    board: &'a Board,
+    tiles: &'a TileRegistry,
+}
+
+impl Space for Whole<'_> {
+    type State = Board;
+
+    fn board(&self) -> &Board {
+        self.board
+    }
+
+    fn tiles(&self) -> &TileRegistry {
+        self.tiles
+    }
+
+    fn place(&self, p: Position) -> Board {
+        self.board.place_snake(p)
+    }
+
+    fn cherry_count(&self, state: &Board) -> usize {
+        state.cherry_count()
+    }
+
+    fn solved(&self, state: &Board) -> bool {
+        self.tiles.solved(state)
+    }
+
+    fn head(&self, state: &Board) -> Option<Position> {
+        state.get_snake_head()
+    }
+
+    fn moves(&self, state: &Board) -> Vec<(Dir, Board)> {
+        state.moves(self.tiles).collect()
+    }
+
+    fn stranded(&self, state: &Board) -> usize {
+        state.stranded_cherries()
+    }
+
+    fn unpack(&self, state: &Board) -> Board {
+        state.clone()
+    }
+
+    fn heap_size(&self) -> usize {
+        use std::mem::size_of;
+
+        let rows = self.board.tiles.len();
+        let cells: usize = self.board.tiles.iter().map(Vec::len).sum();
+
+        rows * size_of::<Vec<Tile>>() + cells * size_of::<Tile>()
+    }
+}
+
+/// Search with packed states where the board allows them, since they take a fraction of
+/// the memory and hash far faster, and whole boards otherwise, filing them under the
+/// constraints' hasher.
+fn search(
+    board: &Board,
+    starts: impl Iterator<Item = Position>,
+    constraints: Constraints,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    watch: &mut dyn ProgressSink,
+    trace: Option<&mut dyn Trace>,
+) -> Outcome {
+    match constraints.hasher {
+        HashKind::Std => {
+            search_hashed::<RandomState>(board, starts, constraints, deadline, tiles, watch, trace)
+        }
+        HashKind::Fx => search_hashed::<FxBuildHasher>(
+            board,
+            starts,
+            constraints,
+            deadline,
+            tiles,
+            watch,
+            trace,
+        ),
+    }
+}
+
+fn search_hashed<H: BuildHasher + Default>(
+    board: &Board,
+    starts: impl Iterator<Item = Position>,
+    constraints: Constraints,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    watch: &mut dyn ProgressSink,
+    trace: Option<&mut dyn Trace>,
+) -> Outcome {
+    match bitboard::Layout::new(board, tiles) {
+        Some(layout) => search_in::<_, H>(&layout, starts, constraints, deadline, watch, trace),
+        None => search_in::<_, H>(
+            &Whole { board, tiles },
+            starts,
+            constraints,
+            deadline,
+            watch,
+            trace,
+        ),
+    }
+}
+
+fn search_in<S: Space, H: BuildHasher + Default>(
+    space: &S,
+    mut starts: impl Iterator<Item = Position>,
+    Constraints {
+        max_depth,
+        end,
+        max_states,
+        max_memory,
+        no_prune,
+        hasher: _,
+    }: Constraints,
+    deadline: Option<Instant>,
+    watch: &mut dyn ProgressSink,
+    mut trace: Option<&mut dyn Trace>,
+) -> Outcome {
+    let started = Instant::now();
+    let mut stats = SearchStats::default();
+    let mut states = 0;
+    let mut timed_out = false;
+    // Whether any start was dropped for using too much memory
+    let mut incomplete = false;
+    let mut partial: Option<Partial> = None;
+    // Hooks may not need every cherry eaten, or may put back ones that were
+    let prune = !no_prune && space.tiles().hooks().is_none();
+
+    span!("solve");
+
+    let solution = starts.find_map(|p| {
+        span!("start");
+
+        let bound = space.board().state_bound(p, space.tiles());
+        let board = space.place(p);
+
+        watch.report(Event::Start(p));
+        stats.starts.push(StartStats {
+            start: p,
+            expanded: 0,
+            solved: false,
+            timed_out: false,
+            out_of_memory: false,
+            depth: 0,
+            bound,
+            elapsed: Duration::ZERO,
+        });
+
+        let start_time = Instant::now();
+        let finish = |stats: &mut SearchStats, solved, timed_out, memory: usize| {
+            stats.peak_memory = stats.peak_memory.max(memory);
+
+            if let Some(s) = stats.starts.last_mut() {
+                s.solved = solved;
+                s.timed_out = timed_out;
+                s.elapsed = start_time.elapsed();
+            }
+        };
+
+        let mut best = (space.cherry_count(&board), 0);
+        let mut steps = Steps(vec![None]);
+        let mut visited = HashMap::with_hasher(H::default());
+        visited.insert(board.clone(), 0);
+        let mut queue: Queue<S> = VecDeque::from([(board, 0, 0)]);
+        // What earlier starts' best lines left uneaten
+        let earlier = partial.as_ref().map_or(usize::MAX, |p| p.cherries_left);
+
+        let mut keep_partial = |best: (usize, u32), steps: &Steps| {
+            if partial.as_ref().is_none_or(|p| best.0 < p.cherries_left) {
+                partial = Some(Partial {
+                    solution: steps.line(p, best.1),
+                    cherries_left: best.0,
+                });
+            }
+        };
+
+        while let Some((b, index, depth)) = {
+            span!("queue");
+            queue.pop_front()
+        } {
+            states += 1;
+
+            if let Some(s) = stats.starts.last_mut() {
+                if depth > s.depth {
+                    watch.report(Event::Layer {
+                        depth: s.depth,
+                        states: s.expanded,
+                        elapsed: start_time.elapsed(),
+                    });
+                }
+
+                s.expanded += 1;
+                s.depth = s.depth.max(depth);
+            }
+
+            let cherries_left = space.cherry_count(&b);
+            let head = space.head(&b);
+
+            if let Some(head) = head {
+                *stats.heat.entry(head).or_default() += 1;
+
+                watch.report(Event::Expanded {
+                    depth,
+                    head,
+                    cherries_left,
+                });
+            }
+
+            if cherries_left < best.0 {
+                best = (cherries_left, index);
+            }
+
+            let mut out_of_memory = false;
+
+            if states % 1024 == 0 {
+                let memory = search_memory(space, &visited, &steps, &queue);
+
+                out_of_memory = max_memory.is_some_and(|m| memory > m);
+
+                stats.peak_memory = stats.peak_memory.max(memory);
+
+                watch.report(Event::Progress {
+                    depth,
+                    states,
+                    queued: queue.len(),
+                    explored: stats.starts.last().map_or(0.0, StartStats::explored),
+                    memory,
+                    cherries_left: best.0,
+                    best: steps.line(p, best.1),
+                });
+
+                timed_out = deadline.is_some_and(|d| Instant::now() >= d) || watch.cancelled();
+            }
+
+            if timed_out || max_states.is_some_and(|n| states > n) {
+                timed_out = true;
+                finish(
+                    &mut stats,
+                    false,
+                    true,
+                    search_memory(space, &visited, &steps, &queue),
+                );
+                keep_partial(best, &steps);
+                watch.report(Event::Abandoned {
+                    start: p,
+                    states: stats.starts.last().map_or(0, |s| s.expanded),
+                    timed_out: true,
+                });
+
+                return Some(None);
+            }
+
+            if out_of_memory {
+                incomplete = true;
+                finish(
+                    &mut stats,
+                    false,
+                    true,
+                    search_memory(space, &visited, &steps, &queue),
+                );
+                keep_partial(best, &steps);
+
+                if let Some(s) = stats.starts.last_mut() {
+                    s.out_of_memory = true;
+                }
+
+                watch.report(Event::Abandoned {
+                    start: p,
+                    states: stats.starts.last().map_or(0, |s| s.expanded),
+                    timed_out: true,
+                });
+
+                return None;
+            }
+
+            debug_log!("\n{} moves tried.", visited.len());
+
+            if space.solved(&b) && end.is_none_or(|e| head == Some(e)) {
+                stats.depth = Some(depth);
+                finish(
+                    &mut stats,
+                    true,
+                    false,
+                    search_memory(space, &visited, &steps, &queue),
+                );
+
+                let solution = steps.line(p, index);
+
+                watch.report(Event::Solved(solution.clone()));
+
+                return Some(solution.into());
+            }
+
+            if max_depth.is_some_and(|d| depth >= d) {
+                continue;
+            }
+
+            let successors = {
+                span!("successors");
+                space.moves(&b)
+            };
+
+            for (dir, m) in successors {
+                let seen = {
+                    span!("hashing");
+                    visited.contains_key(&m)
+                };
+                // Even eating every cherry it can still reach, it wouldn't beat the best line
+                // so far, so it's no use as a partial answer either
+                let stranded = !seen && prune && {
+                    span!("pruning");
+                    space.stranded(&m) >= best.0.min(earlier)
+                };
+
+                if let Some(out) = trace.as_mut() {
+                    let outcome = match seen {
+                        false if stranded => "stranded",
+                        false => "new",
+                        true if m == b => "stuck",
+                        true => "seen",
+                    };
+
+                    out.tried(&space.unpack(&b), depth, dir, &space.unpack(&m), outcome);
+                }
+
+                if stranded {
+                    // Kept as seen, so that it's only checked once
+                    let next = steps.push(Some((index, dir)));
+
+                    visited.insert(m, next);
+                    stats.stranded += 1;
+                } else if !seen {
+                    let next = steps.push(Some((index, dir)));
+
+                    {
+                        span!("hashing");
+                        visited.insert(m.clone(), next);
+                    }
+                    {
+                        span!("queue");
+                        queue.push_back((m, next, depth + 1));
+                    }
+
+                    debug_log!("Added one to queue.");
+                } else if m != b {
+                    stats.duplicates += 1;
+                }
+            }
+
+            stats.peak_queue = stats.peak_queue.max(queue.len());
+        }
+
+        finish(
+            &mut stats,
+            false,
+            false,
+            search_memory(space, &visited, &steps, &queue),
+        );
+        keep_partial(best, &steps);
+        watch.report(Event::Abandoned {
+            start: p,
+            states: stats.starts.last().map_or(0, |s| s.expanded),
+            timed_out: false,
+        });
+
+        None
+    });
+
+    stats.elapsed = started.elapsed();
+
+    let solution = solution.flatten();
+    let timed_out = timed_out || (incomplete && solution.is_none());
+
+    Outcome {
+        partial: partial.filter(|_| solution.is_none()),
+        solution,
+        states,
+        timed_out,
+        stats,
+    }
+}
+
+pub fn bytes(n: usize) -> String {
+    match n {
+        n if n < 1 << 10 => format!("{n} B"),
+        n if n < 1 << 20 => format!("{:.1} KiB", n as f64 / 1024.0),
+        n if n < 1 << 30 => format!("{:.1} MiB", n as f64 / (1 << 20) as f64),
+        n => format!("{:.2} GiB", n as f64 / (1 << 30) as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every built-in puzzle, parsed with the default tiles.
+    fn puzzles() -> Vec<(&'static str, Board)> {
+        puzzles::PUZZLES
+            .iter()
+            .map(|p| (p.name, p.board(&TileRegistry::default()).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn moving_keeps_the_key_of_a_fresh_board() {
+        let tiles = TileRegistry::default();
+
+        for (name, board) in puzzles() {
+            let start = board.starting_positions().next().unwrap();
+            let mut state = board.place_snake(start);
+
+            for &dir in board.rules.dirs().iter().cycle().take(12) {
+                state = state.move_snake(dir, &tiles).unwrap();
+
+                let fresh = Board::parse(&state.to_text(&tiles), &tiles).unwrap();
+
+                assert_eq!(state.key, fresh.key, "{name} after {dir:?}");
+                assert_eq!(state, fresh, "{name} after {dir:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn solutions_of_games_in_progress_check_from_the_head() {
+        let tiles = TileRegistry::default();
+        let board = Board::new("sScc\ncccc\ncccc").unwrap();
+        let solution = solve_within(&board, None, &tiles).solution.unwrap();
+
+        assert_eq!(solution.start, (1, 0));
+        assert!(check(&board, solution.start, &solution.moves, &tiles).is_ok());
+        assert!(matches!(
+            check(&board, (2, 0), &solution.moves, &tiles),
+            Err(Invalid::BadStart((2, 0)))
+        ));
+    }
+
+    #[test]
+    fn a_longest_search_cut_short_keeps_what_it_found() {
+        let tiles = TileRegistry::default();
+        let board = Board::new(include_str!("../puzzles/orchard.txt")).unwrap();
+        let outcome = analysis::longest(&board, Some(Instant::now()), &tiles);
+        let partial = outcome.partial.unwrap();
+
+        assert!(outcome.timed_out && outcome.solution.is_none());
+        assert_eq!(partial.cherries_left, 0);
+        assert!(check(
+            &board,
+            partial.solution.start,
+            &partial.solution.moves,
+            &tiles
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn custom_tiles_keep_mirrored_starts() {
+        let mut tiles = TileRegistry::default();
+        tiles.register(
+            't',
+            tiles::Declared {
+                enterable: true,
+                stops_slide: true,
+            },
+        );
+
+        let plain = Board::parse("ccc\ncrc\nccc", &tiles).unwrap();
+        let custom = Board::parse("ccc\ncrc\nctc", &tiles).unwrap();
+        let starts = |board: &Board| {
+            canonical_starts(board, Constraints::default(), StartOrder::default()).len()
+        };
+
+        assert_eq!(starts(&plain), 2);
+        assert_eq!(starts(&custom), custom.starts().count());
+    }
+
+    #[test]
+    fn lines_rebuilt_from_steps_replay() {
+        let tiles = TileRegistry::default();
+
+        for (name, board) in puzzles() {
+            let outcome = solve_within(&board, None, &tiles);
+            let solution = outcome.solution.expect(name);
+            let end = board
+                .place_snake(solution.start)
+                .apply_moves(&solution.moves, &tiles)
+                .unwrap();
+
+            assert_eq!(end.cherry_count(), 0, "{name}");
+            assert_eq!(outcome.stats.depth, Some(solution.moves.len()), "{name}");
+        }
+
+        // Cherries on either side of a wall of rocks can't all be eaten
+        let board = Board::new("ccc\nrrr\ncc").unwrap();
+        let partial = solve_within(&board, None, &tiles).partial.unwrap();
+        let end = board
+            .place_snake(partial.solution.start)
+            .apply_moves(&partial.solution.moves, &tiles)
+            .unwrap();
+
+        assert_eq!(end.cherry_count(), partial.cherries_left);
+    }
+
+    /// The search through whole boards and, where the board allows it, through packed
+    /// states, from every start.
+    fn both_spaces(board: &Board, tiles: &TileRegistry) -> (Outcome, Option<Outcome>) {
+        let starts = || board.starts();
+        let whole = search_in::<_, RandomState>(
+            &Whole { board, tiles },
+            starts(),
+            Constraints::default(),
+            None,
+            &mut (),
+            None,
+        );
+        let packed = bitboard::Layout::new(board, tiles).map(|layout| {
+            search_in::<_, RandomState>(
+                &layout,
+                starts(),
+                Constraints::default(),
+                None,
+                &mut (),
+                None,
+            )
+        });
+
+        (whole, packed)
+    }
+
+    /// The solution, the states expanded from each start and the depth, for comparing.
+    fn summary(outcome: &Outcome) -> String {
+        let expanded: Vec<usize> = outcome.stats.starts.iter().map(|s| s.expanded).collect();
+
+        format!(
+            "{:?} {expanded:?} {:?}",
+            outcome.solution.as_ref().map(|s| (s.start, &s.moves)),
+            outcome.stats.depth
+        )
+    }
+
+    #[test]
+    fn packed_states_search_like_whole_boards() {
+        let tiles = TileRegistry::default();
+        let boards = [
+            "cccc\ncrcc\ncccc",
+            "rules: wrap\ncccr\ncrcc\ncccc",
+            "rules: step\nccc\ncrc\nccc",
+            "rules: step wrap\ncccc\nrcc.\ncccc",
+            "rules: diagonal\ncccc\ncrcc\ncc.c",
+            "rules: step diagonal\nccc\nc.c\nccr",
+            "rules: pass-through\ncccc\ncrcc\ncccc",
+            "rules: slide wrap diagonal\nccrc\ncccc\ncccc",
+            "ccc.\nc..c\ncccc",
+        ];
+
+        for text in boards {
+            let board = Board::new(text).unwrap();
+            let (whole, packed) = both_spaces(&board, &tiles);
+            let packed = packed.expect(text);
+
+            assert_eq!(summary(&whole), summary(&packed), "{text}");
+            assert_eq!(whole.states, packed.states, "{text}");
+        }
+
+        for (name, board) in puzzles() {
+            let (whole, packed) = both_spaces(&board, &tiles);
+
+            assert_eq!(summary(&whole), summary(&packed.unwrap()), "{name}");
+        }
+    }
+
+    #[test]
+    fn boards_packing_cant_hold_search_whole() {
+        let mut tiles = TileRegistry::default();
+        tiles.register(
+            't',
+            tiles::Declared {
+                enterable: true,
+                stops_slide: true,
+            },
+        );
+
+        let boards = ["rules: decay=2\ncccc\ncrcc\ncccc", "cctc\ncrcc\ncccc"];
+
+        for text in boards {
+            let board = Board::parse(text, &tiles).unwrap();
+            let (whole, packed) = both_spaces(&board, &tiles);
+            let outcome = search(
+                &board,
+                board.starts(),
+                Constraints::default(),
+                None,
+                &tiles,
+                &mut (),
+                None,
+            );
+
+            assert!(packed.is_none(), "{text}");
+            assert_eq!(summary(&whole), summary(&outcome), "{text}");
+        }
+    }
+}
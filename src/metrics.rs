@@ -0,0 +1,119 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::time::Duration;
+
+use crate::Outcome;
+
+/// Upper bounds, in seconds, of the solve latency histogram buckets.
+const BUCKETS: [f64; 11] = [
+    0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Counters for the hosted solver, shared by every connection and rendered in the
+/// Prometheus text format.
+#[derive(Default)]
+pub struct Metrics {
+    solved: AtomicU64,
+    unsolvable: AtomicU64,
+    timed_out: AtomicU64,
+    bad_requests: AtomicU64,
+    nodes: AtomicU64,
+    latency_buckets: [AtomicU64; BUCKETS.len()],
+    latency_count: AtomicU64,
+    latency_sum_micros: AtomicU64,
+}
+
+impl Metrics {
+    pub fn record(&self, outcome: &Outcome, elapsed: Duration) {
+        let result = match outcome {
+            Outcome {
+                solution: Some(_), ..
+            } => &self.solved,
+            Outcome {
+                timed_out: true, ..
+            } => &self.timed_out,
+            _ => &self.unsolvable,
+        };
+
+        result.fetch_add(1, Relaxed);
+        self.nodes.fetch_add(outcome.states as u64, Relaxed);
+
+        let seconds = elapsed.as_secs_f64();
+
+        for (bucket, &bound) in self.latency_buckets.iter().zip(&BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Relaxed);
+            }
+        }
+
+        self.latency_count.fetch_add(1, Relaxed);
+        self.latency_sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Relaxed);
+    }
+
+    pub fn record_bad_request(&self) {
+        self.bad_requests.fetch_add(1, Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "snake_solves_total",
+            "counter",
+            "Solve requests by result.",
+        );
+
+        for (result, counter) in [
+            ("solved", &self.solved),
+            ("unsolvable", &self.unsolvable),
+            ("timed_out", &self.timed_out),
+        ] {
+            let value = counter.load(Relaxed);
+            let _ = writeln!(out, "snake_solves_total{{result=\"{result}\"}} {value}");
+        }
+
+        for (name, help, counter) in [
+            (
+                "snake_bad_requests_total",
+                "Requests rejected as invalid.",
+                &self.bad_requests,
+            ),
+            (
+                "snake_nodes_expanded_total",
+                "Search states expanded.",
+                &self.nodes,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            let _ = writeln!(out, "{name} {}", counter.load(Relaxed));
+        }
+
+        let name = "snake_solve_duration_seconds";
+
+        header(&mut out, name, "histogram", "Time spent solving.");
+
+        for (bucket, bound) in self.latency_buckets.iter().zip(BUCKETS) {
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{le=\"{bound}\"}} {}",
+                bucket.load(Relaxed)
+            );
+        }
+
+        let count = self.latency_count.load(Relaxed);
+        let sum = self.latency_sum_micros.load(Relaxed) as f64 / 1e6;
+
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {count}");
+        let _ = writeln!(out, "{name}_sum {sum}");
+        let _ = writeln!(out, "{name}_count {count}");
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
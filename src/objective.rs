@@ -0,0 +1,412 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::ops::Add;
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::astar::longest_line;
+use crate::order::StartOrder;
+use crate::rules::{Movement, RuleSet};
+use crate::tiles::TileRegistry;
+use crate::{
+    search_starts, trail, Board, Constraints, Dir, Outcome, Partial, Position, SearchStats,
+    Solution,
+};
+
+/// Everything a solution can be scored on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cost {
+    pub moves: u64,
+    /// Cells the head slid across.
+    pub cells: u64,
+    /// Direction changes.
+    pub turns: u64,
+}
+
+impl Cost {
+    /// Whether `self` is at least as good as `other` on every count.
+    fn dominates(&self, other: &Cost) -> bool {
+        self.moves <= other.moves && self.cells <= other.cells && self.turns <= other.turns
+    }
+}
+
+impl Add for Cost {
+    type Output = Cost;
+
+    fn add(self, other: Cost) -> Cost {
+        Cost {
+            moves: self.moves + other.moves,
+            cells: self.cells + other.cells,
+            turns: self.turns + other.turns,
+        }
+    }
+}
+
+/// What makes one solution better than another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Fewest moves. Plain BFS already finds these, per start.
+    Moves,
+    /// Fewest direction changes. With only the built-in tiles every slide runs until it's
+    /// blocked, so every move after the first is a turn; this only differs from `Moves` on
+    /// boards with tiles that stop the slide.
+    Turns,
+    /// Fewest cells slid across.
+    Cells,
+    /// The lowest weighted sum of moves, cells and turns.
+    Weighted(Cost),
+}
+
+impl Objective {
+    fn score(self, cost: Cost) -> u64 {
+        match self {
+            Objective::Moves => cost.moves,
+            Objective::Turns => cost.turns,
+            Objective::Cells => cost.cells,
+            Objective::Weighted(w) => {
+                w.moves * cost.moves + w.cells * cost.cells + w.turns * cost.turns
+            }
+        }
+    }
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    /// `moves`, `turns`, `cells`, or weights for each of those as `MOVES,CELLS,TURNS`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "moves" => Ok(Objective::Moves),
+            "turns" => Ok(Objective::Turns),
+            "cells" => Ok(Objective::Cells),
+            _ => {
+                let weights: Vec<u64> = s
+                    .split(',')
+                    .map(|w| w.trim().parse().ok())
+                    .collect::<Option<_>>()
+                    .unwrap_or_default();
+
+                match weights[..] {
+                    [moves, cells, turns] => Ok(Objective::Weighted(Cost {
+                        moves,
+                        cells,
+                        turns,
+                    })),
+                    _ => Err(format!(
+                        "Unknown objective {s:?}, expected moves, turns, cells or MOVES,CELLS,TURNS weights"
+                    )),
+                }
+            }
+        }
+    }
+}
+
+/// The number of direction changes in `moves`.
+pub fn turns(moves: &[Dir]) -> usize {
+    moves.windows(2).filter(|w| w[0] != w[1]).count()
+}
+
+/// The number of cells the head slides across while playing `solution`. Eaten cherries
+/// block the snake, so on boards of only rocks and cherries this is always one less than
+/// the number of cherries; empty floor, which needn't all be crossed, and custom tiles
+/// that can be crossed more than once make it vary.
+pub fn cells(board: &Board, solution: &Solution, tiles: &TileRegistry) -> usize {
+    trail(board, solution, tiles).len() - 1
+}
+
+/// Every move from `board` with the number of cells its slide crosses, counted along the
+/// slide itself so that slides that wrap round an edge count the cells they actually cross.
+fn slides<'a>(
+    board: &'a Board,
+    tiles: &'a TileRegistry,
+) -> impl Iterator<Item = (Dir, Board, usize)> + 'a {
+    board.rules.dirs().iter().filter_map(move |&dir| {
+        let mut cells = Vec::new();
+
+        board
+            .clone()
+            .slide(dir, tiles, Some(&mut cells))
+            .map(|next| (dir, next, cells.len()))
+    })
+}
+
+/// What moving in `dir` across `distance` cells adds, after last moving in `last`.
+fn step(distance: usize, last: Option<Dir>, dir: Dir) -> Cost {
+    Cost {
+        moves: 1,
+        cells: distance as u64,
+        turns: u64::from(last.is_some_and(|l| l != dir)),
+    }
+}
+
+/// A search state: the board, the direction it was entered by (since turns depend on it),
+/// and the state it was reached from.
+type Node = (Board, Option<Dir>, Option<usize>);
+
+/// Mirrored starts are left out, unless `tiles` has [`crate::tiles::Hooks`]: their best
+/// solutions cost the same as their mirror images'.
+fn roots(board: &Board, tiles: &TileRegistry) -> Vec<Node> {
+    search_starts(board, Constraints::default(), StartOrder::default(), tiles)
+        .into_iter()
+        .map(|p| (board.place_snake(p), None, None))
+        .collect()
+}
+
+/// Find the solution that is cheapest under `objective` across every starting position,
+/// with Dijkstra's algorithm. Ties go to the solution with fewer moves.
+pub fn solve_optimal(
+    board: &Board,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    objective: Objective,
+) -> Outcome {
+    let started = Instant::now();
+
+    let mut nodes = roots(board, tiles);
+    let mut best: HashMap<(Board, Option<Dir>), (u64, Cost)> = nodes
+        .iter()
+        .map(|(b, dir, _)| ((b.clone(), *dir), (0, Cost::default())))
+        .collect();
+    let mut heap: BinaryHeap<_> = (0..nodes.len())
+        .map(|i| Reverse((0, Cost::default(), i)))
+        .collect();
+
+    let mut states = 0;
+    let mut timed_out = false;
+    let mut solution = None;
+
+    while let Some(Reverse((score, cost, i))) = heap.pop() {
+        let (b, last, _) = nodes[i].clone();
+
+        if best
+            .get(&(b.clone(), last))
+            .is_some_and(|&c| c < (score, cost))
+        {
+            continue;
+        }
+
+        states += 1;
+
+        if states % 1024 == 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+            timed_out = true;
+            break;
+        }
+
+        if tiles.solved(&b) {
+            solution = Some(path(&nodes, i));
+            break;
+        }
+
+        for (dir, m, distance) in slides(&b, tiles) {
+            if m == b {
+                continue;
+            }
+
+            let cost = cost + step(distance, last, dir);
+            let next = (objective.score(cost), cost);
+            let key = (m, Some(dir));
+
+            if best.get(&key).is_some_and(|&c| c <= next) {
+                continue;
+            }
+
+            best.insert(key.clone(), next);
+            heap.push(Reverse((next.0, next.1, nodes.len())));
+            nodes.push((key.0, key.1, Some(i)));
+        }
+    }
+
+    Outcome {
+        stats: SearchStats {
+            depth: solution.as_ref().map(|s: &Solution| s.moves.len()),
+            elapsed: started.elapsed(),
+            ..SearchStats::default()
+        },
+        solution,
+        partial: None,
+        states,
+        timed_out,
+    }
+}
+
+/// Every Pareto-best solution: those no other solution beats on moves, cells and turns at
+/// once, ordered by moves. Also returns whether `deadline` cut the search short, in which
+/// case the list may be incomplete.
+pub fn pareto(
+    board: &Board,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+) -> (Vec<(Cost, Solution)>, bool) {
+    let mut nodes = roots(board, tiles);
+    let mut labels: HashMap<(Board, Option<Dir>), Vec<Cost>> = HashMap::new();
+    let mut heap: BinaryHeap<_> = (0..nodes.len())
+        .map(|i| Reverse((Cost::default(), i)))
+        .collect();
+    let mut found: Vec<(Cost, Solution)> = Vec::new();
+    let mut states = 0usize;
+
+    while let Some(Reverse((cost, i))) = heap.pop() {
+        let (b, last, _) = nodes[i].clone();
+
+        // Costs only grow, so anything a known solution beats can't lead to a better one
+        if found.iter().any(|(c, _)| c.dominates(&cost)) {
+            continue;
+        }
+
+        let settled = labels.entry((b.clone(), last)).or_default();
+
+        if settled.iter().any(|c| c.dominates(&cost)) {
+            continue;
+        }
+
+        settled.push(cost);
+        states += 1;
+
+        if states.is_multiple_of(1024) && deadline.is_some_and(|d| Instant::now() >= d) {
+            return (found, true);
+        }
+
+        if tiles.solved(&b) {
+            found.push((cost, path(&nodes, i)));
+            continue;
+        }
+
+        for (dir, m, distance) in slides(&b, tiles) {
+            if m == b {
+                continue;
+            }
+
+            let cost = cost + step(distance, last, dir);
+
+            heap.push(Reverse((cost, nodes.len())));
+            nodes.push((m, Some(dir), Some(i)));
+        }
+    }
+
+    (found, false)
+}
+
+fn path(nodes: &[Node], mut i: usize) -> Solution {
+    let mut moves = Vec::new();
+
+    while let (_, Some(dir), Some(parent)) = nodes[i] {
+        moves.push(dir);
+        i = parent;
+    }
+
+    moves.reverse();
+
+    let start: Position = nodes[i]
+        .0
+        .get_snake_head()
+        .expect("Search states always have a snake");
+
+    Solution { start, moves }
+}
+
+/// The most cherries that can be eaten in at most `budget` moves from any start, and a line
+/// that eats them, for scoring time-attack play: best-first on how many each state could
+/// still reach, so that the search can stop as soon as nothing left could beat the best
+/// line found. Also returns whether `deadline` cut the search short, in which case a
+/// better line may have been missed. `None` if the board has no cherries to start on.
+pub fn most_eaten(
+    board: &Board,
+    budget: usize,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+) -> (Option<Partial>, bool) {
+    let total = board.cherry_count();
+    // The most a single move can eat: a step eats one, and a slide eats along one line,
+    // which with both wrapping and diagonals can wind across the whole board
+    let reach = match board.rules() {
+        RuleSet {
+            movement: Movement::Step,
+            ..
+        } => 1,
+        RuleSet {
+            wrap: true,
+            diagonal: true,
+            ..
+        } => total,
+        _ => longest_line(board),
+    };
+
+    let mut nodes = roots(board, tiles);
+    let mut depths: HashMap<Board, usize> = HashMap::new();
+    let mut heap: BinaryHeap<_> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, (b, _, _))| (total, total - b.cherry_count(), Reverse(0), Reverse(i)))
+        .collect();
+    let mut best: Option<(usize, usize)> = None;
+    let mut states = 0usize;
+
+    while let Some((bound, eaten, Reverse(depth), Reverse(i))) = heap.pop() {
+        if best.is_some_and(|(most, _)| bound <= most) {
+            break;
+        }
+
+        let b = nodes[i].0.clone();
+
+        if depths.get(&b).is_some_and(|&d| d <= depth) {
+            continue;
+        }
+
+        depths.insert(b.clone(), depth);
+        states += 1;
+
+        if states.is_multiple_of(1024) && deadline.is_some_and(|d| Instant::now() >= d) {
+            return (partial(&nodes, best, total), true);
+        }
+
+        if best.is_none_or(|(most, _)| eaten > most) {
+            best = Some((eaten, i));
+        }
+
+        if depth == budget {
+            continue;
+        }
+
+        for (dir, m) in b.moves(tiles) {
+            if m == b {
+                continue;
+            }
+
+            let left = m.cherry_count();
+            let eaten = total - left;
+            let bound = eaten + left.min((budget - depth - 1) * reach);
+
+            heap.push((bound, eaten, Reverse(depth + 1), Reverse(nodes.len())));
+            nodes.push((m, Some(dir), Some(i)));
+        }
+    }
+
+    (partial(&nodes, best, total), false)
+}
+
+fn partial(nodes: &[Node], best: Option<(usize, usize)>, total: usize) -> Option<Partial> {
+    best.map(|(eaten, i)| Partial {
+        solution: path(nodes, i),
+        cherries_left: total - eaten,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles;
+
+    #[test]
+    fn cells_are_counted_along_wrapping_slides() {
+        let tiles = TileRegistry::default();
+        let board = puzzles::find("wraparound").unwrap().board(&tiles).unwrap();
+        let (front, timed_out) = pareto(&board, None, &tiles);
+
+        assert!(!timed_out);
+        assert!(!front.is_empty());
+
+        for (cost, solution) in front {
+            assert_eq!(cost.cells, cells(&board, &solution, &tiles) as u64);
+        }
+    }
+}
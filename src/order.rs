@@ -0,0 +1,99 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::rng::Rng;
+use crate::{Board, Position, Tile};
+
+/// The order in which a search tries starting cherries. It stops at the first start that
+/// works, so trying likely starts first can save a lot of time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StartOrder {
+    /// Left to right, top to bottom.
+    #[default]
+    RowMajor,
+    /// Nearest the middle of the board first.
+    CenterOut,
+    /// Most cherries among the eight surrounding cells first. Cramped starts, like
+    /// corners, tend to be dead ends.
+    MostOpen,
+    /// Shuffled with this seed.
+    Random(u64),
+}
+
+impl StartOrder {
+    pub fn sort(self, board: &Board, mut starts: Vec<Position>) -> Vec<Position> {
+        let height = board.tiles.len();
+        let width = board.tiles.first().map_or(0, Vec::len);
+
+        match self {
+            StartOrder::RowMajor => starts.sort_by_key(|&(x, y)| (y, x)),
+            StartOrder::CenterOut => starts.sort_by_key(|&(x, y)| {
+                // Doubled, so that the middle of an even-sized board is a whole number
+                let dx = (2 * x).abs_diff(width.saturating_sub(1));
+                let dy = (2 * y).abs_diff(height.saturating_sub(1));
+
+                dx * dx + dy * dy
+            }),
+            StartOrder::MostOpen => starts.sort_by_key(|&p| std::cmp::Reverse(openness(board, p))),
+            StartOrder::Random(seed) => Rng::new(seed).shuffle(&mut starts),
+        }
+
+        starts
+    }
+
+    /// The seed a random order shuffles with, which repeats the order when given back.
+    pub fn seed(self) -> Option<u64> {
+        match self {
+            StartOrder::Random(seed) => Some(seed),
+            _ => None,
+        }
+    }
+}
+
+fn openness(board: &Board, (x, y): Position) -> usize {
+    let rows = y.saturating_sub(1)..=y + 1;
+
+    rows.flat_map(|ny| (x.saturating_sub(1)..=x + 1).map(move |nx| (nx, ny)))
+        .filter(|&n| n != (x, y))
+        .filter(|&(nx, ny)| {
+            matches!(
+                board.tiles.get(ny).and_then(|row| row.get(nx)),
+                Some(Tile::Cherry | Tile::Empty)
+            )
+        })
+        .count()
+}
+
+/// The name [`StartOrder::from_str`] reads back, with a random order's seed.
+impl fmt::Display for StartOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartOrder::RowMajor => f.write_str("row-major"),
+            StartOrder::CenterOut => f.write_str("center-out"),
+            StartOrder::MostOpen => f.write_str("most-open"),
+            StartOrder::Random(seed) => write!(f, "random:{seed}"),
+        }
+    }
+}
+
+impl FromStr for StartOrder {
+    type Err = String;
+
+    /// `row-major`, `center-out`, `most-open`, or `random` with an optional `:SEED`. Without
+    /// one, the seed comes from the clock.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "row-major" => Ok(StartOrder::RowMajor),
+            None if s == "center-out" => Ok(StartOrder::CenterOut),
+            None if s == "most-open" => Ok(StartOrder::MostOpen),
+            None if s == "random" => Ok(StartOrder::Random(Rng::from_clock().seed())),
+            Some(("random", seed)) => seed
+                .parse()
+                .map(StartOrder::Random)
+                .map_err(|_| format!("Invalid seed {seed:?}")),
+            _ => Err(format!(
+                "Unknown start order {s:?}, expected row-major, center-out, most-open or random[:SEED]"
+            )),
+        }
+    }
+}
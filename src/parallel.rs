@@ -0,0 +1,186 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use crate::order::StartOrder;
+use crate::tiles::TileRegistry;
+use crate::{
+    search, search_starts, Board, Constraints, Event, Outcome, Partial, ProgressSink, SearchStats,
+};
+
+/// Tells a search to stop once another thread has found a solution, or whatever else
+/// sets the flag.
+pub(crate) struct Cancel<'a>(pub(crate) &'a AtomicBool);
+
+impl ProgressSink for Cancel<'_> {
+    fn report(&mut self, _: Event) {}
+
+    fn cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Like [`crate::solve_traced`], but searching from up to `jobs` starts at once. The first
+/// solution found wins and stops the other searches, so on a board with several solvable
+/// starts which one is returned can vary from run to run. `max_states` applies to each start
+/// rather than to the whole search.
+pub fn solve_parallel(
+    board: &Board,
+    constraints: Constraints,
+    order: StartOrder,
+    deadline: Option<Instant>,
+    tiles: &TileRegistry,
+    jobs: usize,
+) -> Outcome {
+    let started = Instant::now();
+    let starts = search_starts(board, constraints, order, tiles);
+    let next = AtomicUsize::new(0);
+    let solved = AtomicBool::new(false);
+    let (sender, results) = mpsc::channel();
+    let jobs = jobs.clamp(1, starts.len().max(1));
+    // The searches run side by side, so they share the memory between them
+    let constraints = Constraints {
+        max_memory: constraints.max_memory.map(|m| m / jobs),
+        ..constraints
+    };
+
+    thread::scope(|scope| {
+        for worker in 0..jobs {
+            let sender = sender.clone();
+            let (starts, next, solved) = (&starts, &next, &solved);
+
+            scope.spawn(move || {
+                while let Some(&p) = starts.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if solved.load(Ordering::Relaxed) {
+                        return;
+                    }
+
+                    let outcome = search(
+                        board,
+                        [p].into_iter(),
+                        constraints,
+                        deadline,
+                        tiles,
+                        &mut Cancel(solved),
+                        None,
+                    );
+
+                    if outcome.solution.is_some() {
+                        solved.store(true, Ordering::Relaxed);
+                    }
+
+                    let _ = sender.send((worker, p, outcome));
+                }
+            });
+        }
+    });
+
+    drop(sender);
+
+    let mut outcomes: Vec<_> = results.into_iter().collect();
+
+    // In the order the starts would have been tried one at a time, but with the one that
+    // solved the board last, as it would have been
+    outcomes.sort_by_key(|(_, p, o)| (o.solution.is_some(), starts.iter().position(|s| s == p)));
+
+    merge(outcomes.into_iter().map(|(w, _, o)| (w, o)), jobs, started)
+}
+
+/// One outcome from the outcomes of searches from single starts, each with the worker of
+/// `jobs` that ran it.
+fn merge(
+    outcomes: impl Iterator<Item = (usize, Outcome)>,
+    jobs: usize,
+    started: Instant,
+) -> Outcome {
+    let mut stats = SearchStats::default();
+    // Each worker searches one start at a time, so its peak is the largest of its starts'
+    let mut peaks = vec![(0, 0); jobs];
+    let mut solution = None;
+    let mut partial: Option<Partial> = None;
+    let mut states = 0;
+    let mut timed_out = false;
+
+    for (worker, outcome) in outcomes {
+        states += outcome.states;
+        timed_out |= outcome.timed_out;
+
+        if solution.is_none() && outcome.solution.is_some() {
+            solution = outcome.solution;
+            stats.depth = outcome.stats.depth;
+        }
+
+        if let Some(p) = outcome.partial {
+            if partial
+                .as_ref()
+                .is_none_or(|best| p.cherries_left < best.cherries_left)
+            {
+                partial = Some(p);
+            }
+        }
+
+        stats.starts.extend(outcome.stats.starts);
+        stats.duplicates += outcome.stats.duplicates;
+        stats.stranded += outcome.stats.stranded;
+
+        let (memory, queue) = &mut peaks[worker];
+
+        *memory = outcome.stats.peak_memory.max(*memory);
+        *queue = outcome.stats.peak_queue.max(*queue);
+
+        for (p, n) in outcome.stats.heat {
+            *stats.heat.entry(p).or_default() += n;
+        }
+    }
+
+    // The workers ran side by side, so their peaks may have added up
+    stats.peak_memory = peaks.iter().map(|&(memory, _)| memory).sum();
+    stats.peak_queue = peaks.iter().map(|&(_, queue)| queue).sum();
+    stats.elapsed = started.elapsed();
+
+    Outcome {
+        partial: partial.filter(|_| solution.is_none()),
+        timed_out: timed_out && solution.is_none(),
+        solution,
+        states,
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(peak_memory: usize, peak_queue: usize) -> Outcome {
+        Outcome {
+            solution: None,
+            partial: None,
+            states: 1,
+            timed_out: false,
+            stats: SearchStats {
+                peak_memory,
+                peak_queue,
+                ..SearchStats::default()
+            },
+        }
+    }
+
+    #[test]
+    fn peaks_add_up_across_workers_but_not_across_one_workers_starts() {
+        let merged = merge(
+            [
+                (0, outcome(100, 10)),
+                (1, outcome(50, 5)),
+                (0, outcome(70, 30)),
+                (1, outcome(60, 1)),
+            ]
+            .into_iter(),
+            2,
+            Instant::now(),
+        );
+
+        assert_eq!(merged.stats.peak_memory, 160);
+        assert_eq!(merged.stats.peak_queue, 35);
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt::Write;
+
+use crate::rules::RuleSet;
+use crate::{Board, Dir, Position, Solution, Tile};
+
+/// A slide is modelled as `start-slide`, any number of `step`s, then one of the `stop-*`
+/// actions, which only apply once the next cell is blocked. A plan therefore can't stop a
+/// slide early, matching `move_snake`. Every direction is declared, but a problem only
+/// links cells diagonally if its board's rules allow diagonal moves.
+pub const DOMAIN: &str = "\
+(define (domain clever-snake)
+  (:requirements :strips :typing)
+  (:types cell dir)
+  (:constants up down left right up-left up-right down-left down-right - dir)
+  (:predicates
+    (adj ?from ?to - cell ?d - dir)
+    (edge ?c - cell ?d - dir)
+    (free ?c - cell)
+    (cherry ?c - cell)
+    (blocked ?c - cell)
+    (at ?c - cell)
+    (eaten ?c - cell)
+    (unplaced)
+    (idle)
+    (sliding ?d - dir))
+
+  (:action place
+    :parameters (?c - cell)
+    :precondition (and (unplaced) (cherry ?c))
+    :effect (and (not (unplaced)) (idle) (at ?c) (not (free ?c)) (eaten ?c)))
+
+  (:action start-slide
+    :parameters (?d - dir)
+    :precondition (idle)
+    :effect (and (not (idle)) (sliding ?d)))
+
+  (:action step
+    :parameters (?from ?to - cell ?d - dir)
+    :precondition (and (sliding ?d) (at ?from) (adj ?from ?to ?d) (free ?to))
+    :effect (and (not (at ?from)) (blocked ?from) (at ?to) (not (free ?to)) (eaten ?to)))
+
+  (:action stop-at-obstacle
+    :parameters (?c ?next - cell ?d - dir)
+    :precondition (and (sliding ?d) (at ?c) (adj ?c ?next ?d) (blocked ?next))
+    :effect (and (not (sliding ?d)) (idle)))
+
+  (:action stop-at-edge
+    :parameters (?c - cell ?d - dir)
+    :precondition (and (sliding ?d) (at ?c) (edge ?c ?d))
+    :effect (and (not (sliding ?d)) (idle))))
+";
+
+fn cell((x, y): Position) -> String {
+    format!("c{x}_{y}")
+}
+
+fn dir_name(dir: Dir) -> &'static str {
+    match dir {
+        Dir::Up => "up",
+        Dir::Down => "down",
+        Dir::Left => "left",
+        Dir::Right => "right",
+        Dir::UpLeft => "up-left",
+        Dir::UpRight => "up-right",
+        Dir::DownLeft => "down-left",
+        Dir::DownRight => "down-right",
+    }
+}
+
+/// Describe `board` as a problem for [`DOMAIN`]. The goal is to eat every cherry and come
+/// to rest. The domain only knows slides that stop at the edge and a body that stays put,
+/// so boards played by any other rules than those and `diagonal` are refused, naming them.
+pub fn problem(board: &Board, name: &str) -> Result<String, String> {
+    let supported = RuleSet {
+        diagonal: board.rules.diagonal,
+        ..RuleSet::default()
+    };
+
+    if board.rules != supported {
+        let words = board.rules.words();
+        let extra: Vec<&str> = words
+            .split_whitespace()
+            .filter(|&w| w != "slide" && w != "diagonal")
+            .collect();
+
+        return Err(format!("PDDL can't express the rules {}", extra.join(" ")));
+    }
+
+    let cells: Vec<(Position, Tile)> = board
+        .tiles
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, &t)| ((x, y), t)))
+        .collect();
+
+    let mut out = String::new();
+
+    let _ = writeln!(out, "(define (problem {name})");
+    let _ = writeln!(out, "  (:domain clever-snake)");
+    let _ = write!(out, "  (:objects");
+
+    for &(p, _) in &cells {
+        let _ = write!(out, " {}", cell(p));
+    }
+
+    let _ = writeln!(out, " - cell)");
+    let _ = writeln!(out, "  (:init");
+    let _ = writeln!(out, "    (unplaced)");
+
+    for &((x, y), t) in &cells {
+        let c = cell((x, y));
+
+        match t {
+            Tile::Cherry => {
+                let _ = writeln!(out, "    (free {c})");
+                let _ = writeln!(out, "    (cherry {c})");
+            }
+            Tile::Empty => {
+                let _ = writeln!(out, "    (free {c})");
+            }
+            _ => {
+                let _ = writeln!(out, "    (blocked {c})");
+            }
+        }
+
+        for &d in board.rules.dirs() {
+            match board.neighbour((x, y), d) {
+                Some(n) => {
+                    let _ = writeln!(out, "    (adj {c} {} {})", cell(n), dir_name(d));
+                }
+                None => {
+                    let _ = writeln!(out, "    (edge {c} {})", dir_name(d));
+                }
+            }
+        }
+    }
+
+    let _ = writeln!(out, "  )");
+    let _ = writeln!(out, "  (:goal (and (idle)");
+
+    for &(p, t) in &cells {
+        if t == Tile::Cherry {
+            let _ = writeln!(out, "    (eaten {})", cell(p));
+        }
+    }
+
+    let _ = writeln!(out, "  )))");
+
+    Ok(out)
+}
+
+fn parse_cell(name: &str) -> Result<Position, String> {
+    name.strip_prefix('c')
+        .and_then(|rest| rest.split_once('_'))
+        .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+        .ok_or_else(|| format!("Invalid cell name {name:?}"))
+}
+
+fn parse_dir(name: &str) -> Result<Dir, String> {
+    match name {
+        "up" => Ok(Dir::Up),
+        "down" => Ok(Dir::Down),
+        "left" => Ok(Dir::Left),
+        "right" => Ok(Dir::Right),
+        "up-left" => Ok(Dir::UpLeft),
+        "up-right" => Ok(Dir::UpRight),
+        "down-left" => Ok(Dir::DownLeft),
+        "down-right" => Ok(Dir::DownRight),
+        _ => Err(format!("Invalid direction {name:?}")),
+    }
+}
+
+/// Read a plan for [`DOMAIN`] back into a [`Solution`]. Accepts the usual planner output
+/// styles: one parenthesised action per line, optionally prefixed by a timestamp and
+/// followed by a duration, with `;` comments. Slides that never take a step are dropped.
+pub fn parse_plan(plan: &str) -> Result<Solution, String> {
+    let mut start = None;
+    let mut moves = Vec::new();
+    let mut slide = None;
+    let mut stepped = false;
+
+    for line in plan.lines() {
+        let line = line.split(';').next().unwrap_or_default();
+
+        let Some(action) = line
+            .split_once('(')
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(action, _)| action.to_lowercase())
+        else {
+            continue;
+        };
+
+        let words: Vec<&str> = action.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["place", c] => start = Some(parse_cell(c)?),
+            ["start-slide", d] => {
+                slide = Some(parse_dir(d)?);
+                stepped = false;
+            }
+            ["step", ..] => stepped = true,
+            ["stop-at-obstacle" | "stop-at-edge", ..] => {
+                let dir = slide.take().ok_or("Slide stopped before it started")?;
+
+                if stepped {
+                    moves.push(dir);
+                }
+            }
+            _ => return Err(format!("Unknown action ({action})")),
+        }
+    }
+
+    let start = start.ok_or("Plan never places the snake")?;
+
+    Ok(Solution { start, moves })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_the_domain_lacks_are_refused() {
+        let diagonal = Board::new("rules: slide diagonal\ncc\ncc").unwrap();
+        let wrap = Board::new("rules: step wrap decay=2\ncc\ncc").unwrap();
+
+        assert!(problem(&diagonal, "diagonal")
+            .unwrap()
+            .contains("(adj c0_0 c1_1 down-right)"));
+        assert_eq!(
+            problem(&wrap, "wrap"),
+            Err("PDDL can't express the rules step wrap decay=2".to_string())
+        );
+    }
+
+    #[test]
+    fn only_cherries_can_be_started_on() {
+        let board = Board::new("c.\ncr").unwrap();
+        let problem = problem(&board, "empties").unwrap();
+
+        assert!(DOMAIN.contains(":precondition (and (unplaced) (cherry ?c))"));
+        assert!(problem.contains("(cherry c0_0)"));
+        assert!(problem.contains("(free c1_0)"));
+        assert!(!problem.contains("(cherry c1_0)"));
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rules::RuleSet;
+use crate::{Board, Tile};
+
+/// Custom tiles are written as this character plus their id: only a
+/// [`crate::tiles::TileRegistry`] knows their glyphs, and there's none to hand here.
+const CUSTOM: u32 = 0xe000;
+
+/// How a [`Board`] is written: `{"rules": "slide", "rows": ["crc", "ccc"]}`, the rules as
+/// [`RuleSet::words`] and each row as it's written in a board file. Without `rules` it gets
+/// the defaults, as a board file without a header does.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BoardForm {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rules: Option<String>,
+    rows: Vec<String>,
+}
+
+fn glyph(tile: Tile) -> char {
+    match tile {
+        Tile::Cherry => 'c',
+        Tile::Empty => '.',
+        Tile::Rock => 'r',
+        Tile::SnakeBody => 's',
+        Tile::SnakeHead => 'S',
+        Tile::Custom(id) => char::from_u32(CUSTOM + u32::from(id)).expect("Ids fit below U+E100"),
+    }
+}
+
+fn tile(glyph: char) -> Option<Tile> {
+    match glyph {
+        'c' => Some(Tile::Cherry),
+        '.' => Some(Tile::Empty),
+        'r' => Some(Tile::Rock),
+        's' => Some(Tile::SnakeBody),
+        'S' => Some(Tile::SnakeHead),
+        _ => u8::try_from(u32::from(glyph).checked_sub(CUSTOM)?)
+            .ok()
+            .map(Tile::Custom),
+    }
+}
+
+impl From<Board> for BoardForm {
+    fn from(board: Board) -> Self {
+        Self {
+            rules: Some(board.rules.words()),
+            rows: board
+                .tiles
+                .iter()
+                .map(|row| row.iter().map(|&t| glyph(t)).collect())
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<BoardForm> for Board {
+    type Error = String;
+
+    fn try_from(form: BoardForm) -> Result<Self, String> {
+        let rules = match &form.rules {
+            None => RuleSet::default(),
+            Some(words) => RuleSet::parse(words, 1).map_err(|e| e.to_string())?,
+        };
+
+        let tiles = form
+            .rows
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|g| tile(g).ok_or_else(|| format!("Unknown tile {g:?}")))
+                    .collect()
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Board::from_tiles(tiles).with_rules(rules))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tiles::{Declared, TileRegistry};
+    use crate::{Board, Dir, Solution};
+
+    #[test]
+    fn boards_and_solutions_come_back_as_they_went() {
+        let mut tiles = TileRegistry::default();
+
+        tiles.register(
+            't',
+            Declared {
+                enterable: true,
+                stops_slide: true,
+            },
+        );
+
+        let board = Board::parse("rules: step wrap\nc.t\nrSs", &tiles).unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+
+        assert_eq!(
+            json,
+            "{\"rules\":\"step wrap\",\"rows\":[\"c.\u{e000}\",\"rSs\"]}"
+        );
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+
+        let solution = Solution {
+            start: (1, 0),
+            moves: vec![Dir::Down, Dir::UpLeft],
+        };
+        let json = serde_json::to_string(&solution).unwrap();
+
+        assert_eq!(json, r#"{"start":[1,0],"moves":["Down","UpLeft"]}"#);
+        assert_eq!(
+            serde_json::from_str::<Solution>(&json).unwrap().moves,
+            solution.moves
+        );
+    }
+
+    #[test]
+    fn boards_without_rules_get_the_defaults() {
+        let board: Board = serde_json::from_str(r#"{"rows": ["crc"]}"#).unwrap();
+
+        assert_eq!(board, Board::new("crc").unwrap());
+        assert!(serde_json::from_str::<Board>(r#"{"rows": ["cxc"]}"#).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+struct Frame {
+    name: &'static str,
+    entered: Instant,
+    children: Duration,
+}
+
+#[derive(Default)]
+struct Profile {
+    stack: Vec<Frame>,
+    /// Time spent in each call stack, excluding time spent in spans nested inside it.
+    self_time: HashMap<Vec<&'static str>, Duration>,
+}
+
+thread_local! {
+    static PROFILE: RefCell<Profile> = RefCell::default();
+}
+
+/// A named region of the solver, timed until it is dropped. Use the `span!` macro rather
+/// than creating these directly, so that they cost nothing without the `profile` feature.
+pub struct Span(());
+
+impl Span {
+    pub fn enter(name: &'static str) -> Self {
+        PROFILE.with_borrow_mut(|p| {
+            p.stack.push(Frame {
+                name,
+                entered: Instant::now(),
+                children: Duration::ZERO,
+            })
+        });
+
+        Self(())
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        PROFILE.with_borrow_mut(|p| {
+            let stack: Vec<&'static str> = p.stack.iter().map(|f| f.name).collect();
+
+            let Some(frame) = p.stack.pop() else {
+                return;
+            };
+
+            let elapsed = frame.entered.elapsed();
+
+            *p.self_time.entry(stack).or_default() += elapsed.saturating_sub(frame.children);
+
+            if let Some(parent) = p.stack.last_mut() {
+                parent.children += elapsed;
+            }
+        });
+    }
+}
+
+/// Write the spans recorded on this thread as folded stacks (`solve;start;queue 1234`, in
+/// microseconds), the input format of `inferno-flamegraph` and `flamegraph.pl`.
+pub fn write_folded(path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+
+    PROFILE.with_borrow(|p| {
+        let mut stacks: Vec<_> = p.self_time.iter().collect();
+
+        stacks.sort();
+
+        for (stack, time) in stacks {
+            writeln!(out, "{} {}", stack.join(";"), time.as_micros())?;
+        }
+
+        Ok::<_, io::Error>(())
+    })?;
+
+    out.flush()
+}
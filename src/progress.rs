@@ -0,0 +1,133 @@
+use std::io::{stderr, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crate::order::StartOrder;
+use crate::tiles::TileRegistry;
+use crate::{bytes, search_starts, Board, Constraints, Event, ProgressSink};
+
+const WIDTH: usize = 20;
+const REDRAW: Duration = Duration::from_millis(100);
+
+/// A one-line progress display for the CLI, redrawn in place on stderr at most every
+/// `REDRAW` while a search runs.
+pub struct ProgressBar {
+    started: Instant,
+    start_time: Instant,
+    drawn: Option<Instant>,
+    starts: usize,
+    start: usize,
+    tty: bool,
+}
+
+impl ProgressBar {
+    /// A bar for a search of `board` under `constraints` by the rules of `tiles`, counting
+    /// only the starts it will actually try.
+    pub fn new(board: &Board, constraints: Constraints, tiles: &TileRegistry) -> Self {
+        Self {
+            started: Instant::now(),
+            start_time: Instant::now(),
+            drawn: None,
+            starts: search_starts(board, constraints, StartOrder::default(), tiles).len(),
+            start: 0,
+            tty: stderr().is_terminal(),
+        }
+    }
+
+    /// Clear the bar so that whatever comes next starts on a clean line.
+    pub fn finish(&self) {
+        if self.tty && self.drawn.is_some() {
+            eprint!("\r\x1b[2K");
+        }
+    }
+
+    /// `explored` is the share of the current start's state bound searched so far, which
+    /// gives a pessimistic estimate of how long that start could still take.
+    fn draw(&self, states: usize, queued: usize, explored: f64, memory: usize) {
+        let rate = states as f64 / self.started.elapsed().as_secs_f64().max(1e-3);
+        let explored = explored.min(1.0);
+        let filled = (explored * WIDTH as f64) as usize;
+        let on_start = self.start_time.elapsed().as_secs_f64();
+
+        let eta = if explored > 0.0 {
+            duration(on_start * (1.0 - explored) / explored)
+        } else {
+            "?".to_string()
+        };
+
+        eprint!(
+            "\r\x1b[2Kstart {}/{}  {states} states  {rate:.0}/s  queue {queued}  {}  [{}{}] {:.3}%  ETA {eta}",
+            self.start,
+            self.starts,
+            bytes(memory),
+            "#".repeat(filled),
+            "-".repeat(WIDTH - filled),
+            explored * 100.0,
+        );
+
+        let _ = stderr().flush();
+    }
+}
+
+impl ProgressSink for ProgressBar {
+    fn report(&mut self, event: Event) {
+        match event {
+            Event::Start(p) => {
+                self.start += 1;
+                self.start_time = Instant::now();
+
+                if !self.tty {
+                    eprintln!("Starting from {p:?} ({}/{})", self.start, self.starts);
+                }
+            }
+            Event::Progress {
+                states,
+                queued,
+                explored,
+                memory,
+                ..
+            } => {
+                if !self.tty || self.drawn.is_some_and(|d| d.elapsed() < REDRAW) {
+                    return;
+                }
+
+                self.drawn = Some(Instant::now());
+                self.draw(states, queued, explored, memory);
+            }
+            Event::Layer {
+                depth,
+                states,
+                elapsed,
+            } => {
+                // Only worth a line once the search has run long enough to be waited on
+                if elapsed < REDRAW {
+                    return;
+                }
+
+                self.finish();
+                eprintln!(
+                    "depth {depth} complete: {} states, {}",
+                    count(states),
+                    duration(elapsed.as_secs_f64())
+                );
+            }
+            Event::Expanded { .. } | Event::Solved(_) | Event::Abandoned { .. } => {}
+        }
+    }
+}
+
+fn count(n: usize) -> String {
+    match n {
+        n if n < 10_000 => n.to_string(),
+        n if n < 10_000_000 => format!("{}k", n / 1000),
+        n => format!("{}M", n / 1_000_000),
+    }
+}
+
+fn duration(seconds: f64) -> String {
+    match seconds as u64 {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m{:02}s", s / 60, s % 60),
+        s if s < 86400 * 365 => format!("{}h{:02}m", s / 3600, s % 3600 / 60),
+        _ => "forever".to_string(),
+    }
+}
@@ -0,0 +1,49 @@
+use crate::tiles::TileRegistry;
+use crate::{Board, SnakeError};
+
+/// A board that ships with the solver, for trying it out without writing one first.
+pub struct Puzzle {
+    pub name: &'static str,
+    pub about: &'static str,
+    /// The board as it's written in a board file.
+    pub text: &'static str,
+}
+
+impl Puzzle {
+    pub fn board(&self, tiles: &TileRegistry) -> Result<Board, SnakeError> {
+        Board::parse(self.text, tiles)
+    }
+}
+
+/// Every built-in puzzle, easiest first. Each is solvable with the default tiles.
+pub const PUZZLES: &[Puzzle] = &[
+    Puzzle {
+        name: "first-bite",
+        about: "A small board with a single rock",
+        text: include_str!("../puzzles/first-bite.txt"),
+    },
+    Puzzle {
+        name: "one-rock",
+        about: "A rock near the edge to slide around",
+        text: include_str!("../puzzles/one-rock.txt"),
+    },
+    Puzzle {
+        name: "orchard",
+        about: "A bigger field with rocks clustered in one corner",
+        text: include_str!("../puzzles/orchard.txt"),
+    },
+    Puzzle {
+        name: "wraparound",
+        about: "Slides leave one edge and come back on the other",
+        text: include_str!("../puzzles/wraparound.txt"),
+    },
+    Puzzle {
+        name: "diagonals",
+        about: "One cell at a time, in eight directions",
+        text: include_str!("../puzzles/diagonals.txt"),
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Puzzle> {
+    PUZZLES.iter().find(|p| p.name == name)
+}
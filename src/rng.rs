@@ -0,0 +1,58 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Small xorshift generator behind everything random, so that any randomized run can be
+/// reproduced from the one seed it started with.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: seed.max(1),
+        }
+    }
+
+    /// A generator seeded from the clock, for runs that weren't given a seed. The seed is
+    /// kept to 53 bits, so that it survives a trip through JSON numbers.
+    pub fn from_clock() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |d| d.as_nanos() as u64 & ((1 << 53) - 1));
+
+        Self::new(seed)
+    }
+
+    /// The seed the generator started from, which repeats the run when given back to
+    /// [`Rng::new`].
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Fisher–Yates.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+
+    /// A generator for one part of a run, seeded from this one, so that how many numbers
+    /// one part draws doesn't change what the others get.
+    pub fn split(&mut self) -> Rng {
+        Rng::new(self.next_u64())
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+
+use crate::{Dir, SnakeError};
+
+/// How far the snake goes on each move.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum Movement {
+    /// On until something stops it.
+    #[default]
+    Slide,
+    /// One cell.
+    Step,
+}
+
+/// The rules a puzzle is played by. Boards carry their own, so puzzles with different rules
+/// can be mixed freely; a board file picks them with a header line such as
+/// `rules: step wrap`, and without one gets the defaults.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct RuleSet {
+    pub movement: Movement,
+    /// Moving off one edge of the board comes back on at the opposite edge.
+    pub wrap: bool,
+    /// The snake can't cross its own body. Without this it may pass back over it.
+    pub body_blocks: bool,
+    /// The body left behind on a move clears this many moves later, leaving ground the
+    /// snake may cross again. Without this the body stays for the rest of the game.
+    pub decay: Option<usize>,
+    /// The snake may also move diagonally.
+    pub diagonal: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            movement: Movement::Slide,
+            wrap: false,
+            body_blocks: true,
+            decay: None,
+            diagonal: false,
+        }
+    }
+}
+
+impl RuleSet {
+    /// The header line that introduces a board's rules.
+    pub const HEADER: &'static str = "rules:";
+
+    /// The words after [`RuleSet::HEADER`]: `slide` or `step`, then any of `wrap`,
+    /// `pass-through` (the body doesn't block), `decay=N` (the body clears after `N` moves)
+    /// and `diagonal`. Anything left out keeps its default.
+    pub fn parse(words: &str, line: usize) -> Result<Self, SnakeError> {
+        let mut rules = Self::default();
+
+        for word in words.split_whitespace() {
+            match word {
+                "slide" => rules.movement = Movement::Slide,
+                "step" => rules.movement = Movement::Step,
+                "wrap" => rules.wrap = true,
+                "pass-through" => rules.body_blocks = false,
+                "diagonal" => rules.diagonal = true,
+                _ if word.starts_with("decay=") => {
+                    rules.decay = Some(
+                        word["decay=".len()..]
+                            .parse()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or_else(|| SnakeError::Rules {
+                                line,
+                                rule: word.to_string(),
+                            })?,
+                    )
+                }
+                _ => {
+                    return Err(SnakeError::Rules {
+                        line,
+                        rule: word.to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// The directions the snake may move in.
+    pub fn dirs(&self) -> &'static [Dir] {
+        use Dir::*;
+
+        match self.diagonal {
+            false => &[Up, Down, Right, Left],
+            true => &[Up, Down, Right, Left, UpLeft, UpRight, DownLeft, DownRight],
+        }
+    }
+
+    /// The words [`RuleSet::parse`] reads, naming every rule that differs from the
+    /// default after the movement.
+    pub fn words(&self) -> String {
+        let movement = match self.movement {
+            Movement::Slide => "slide",
+            Movement::Step => "step",
+        };
+
+        let decay = self.decay.map(|n| format!("decay={n}"));
+        let others = [
+            (self.wrap, "wrap"),
+            (!self.body_blocks, "pass-through"),
+            (decay.is_some(), decay.as_deref().unwrap_or_default()),
+            (self.diagonal, "diagonal"),
+        ];
+
+        others
+            .into_iter()
+            .filter(|&(on, _)| on)
+            .fold(movement.to_string(), |words, (_, word)| words + " " + word)
+    }
+}
+
+/// The header line for these rules, without a trailing newline.
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", Self::HEADER, self.words())
+    }
+}
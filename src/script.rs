@@ -0,0 +1,397 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope, AST};
+
+use crate::tiles::{Declared, Hooks, TileBehavior, TileRegistry};
+use crate::{Board, Dir, Position, Tile};
+
+/// The most operations one call into a script may take, so that a script stuck in a loop
+/// fails instead of hanging the search.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A Rhai script that changes the rules by defining any of:
+///
+/// - `tiles()`, a map from each glyph the script adds to whether its tile can be entered
+///   and whether it stops a slide, like `#{ "t": #{ enterable: true, stops_slide: true } }`.
+///   Both are optional, `true` and `false` by default.
+/// - `on_enter(glyph, x, y)`, called once the head has moved onto one of those tiles, at
+///   `x`, `y`.
+/// - `on_move(dir)`, called after every move, with `dir` its letter.
+/// - `solved()`, whether the puzzle is solved, in place of every cherry being eaten.
+///
+/// Each is called with `this` the board, which has `width`, `height`, `cherries` and
+/// `head` (`[x, y]`, or `()` before the snake is placed), `get(x, y)` for the glyph of a
+/// cell and `set(x, y, glyph)` to change it. What `on_enter` and `on_move` leave `this` as
+/// is the board the move ends in.
+#[derive(Clone)]
+pub struct Script {
+    ast: AST,
+    /// The first error a hook ran into, which the search itself can't report.
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl Script {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let ast = Engine::new().compile(source).map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            ast,
+            error: Arc::default(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| Self::compile(&source))
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Register the script's tiles with `tiles`, and make it play by the script's
+    /// `on_move` and `solved` if it has either. Errors if `tiles()` isn't a map like the
+    /// one [`Script`] describes.
+    pub fn install(&self, tiles: &mut TileRegistry) -> Result<(), String> {
+        let declared = self.declared()?;
+        let mut glyphs = vec![
+            ('c', Tile::Cherry),
+            ('.', Tile::Empty),
+            ('r', Tile::Rock),
+            ('s', Tile::SnakeBody),
+            ('S', Tile::SnakeHead),
+        ];
+
+        // Registered as plain flags first for their ids, which the board's functions need
+        for &(glyph, enterable, stops_slide) in &declared {
+            let tile = tiles
+                .register(
+                    glyph,
+                    Declared {
+                        enterable,
+                        stops_slide,
+                    },
+                )
+                .ok_or("The script adds too many tiles")?;
+
+            glyphs.push((glyph, tile));
+        }
+
+        let runner = Arc::new(Runner {
+            engine: engine(glyphs.into()),
+            ast: self.ast.clone(),
+            error: Arc::clone(&self.error),
+        });
+
+        if self.defines("on_enter") {
+            for (glyph, enterable, stops_slide) in declared {
+                tiles.register(
+                    glyph,
+                    Scripted {
+                        glyph,
+                        enterable,
+                        stops_slide,
+                        runner: Arc::clone(&runner),
+                    },
+                );
+            }
+        }
+
+        let (on_move, solved) = (self.defines("on_move"), self.defines("solved"));
+
+        if on_move || solved {
+            tiles.set_hooks(ScriptHooks {
+                runner,
+                on_move,
+                solved,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The first error any of the script's hooks ran into. A hook that fails leaves the
+    /// board as it was, and a failed `solved()` counts as unsolved.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().ok().and_then(|e| e.clone())
+    }
+
+    fn defines(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+
+    /// Each tile `tiles()` adds: its glyph, whether it can be entered and whether it stops
+    /// a slide.
+    fn declared(&self) -> Result<Vec<(char, bool, bool)>, String> {
+        if !self.defines("tiles") {
+            return Ok(Vec::new());
+        }
+
+        let map: Map = Engine::new()
+            .call_fn(&mut Scope::new(), &self.ast, "tiles", ())
+            .map_err(|e| format!("tiles: {e}"))?;
+
+        map.into_iter()
+            .map(|(glyph, flags)| {
+                let mut chars = glyph.chars();
+                let (Some(glyph), None) = (chars.next(), chars.next()) else {
+                    return Err(format!("tiles: {glyph:?} isn't a single glyph"));
+                };
+                let flags = flags
+                    .try_cast::<Map>()
+                    .ok_or_else(|| format!("tiles: {glyph:?} should map to a map of flags"))?;
+                let flag = |name: &str, default| match flags.get(name) {
+                    Some(value) => value
+                        .as_bool()
+                        .map_err(|_| format!("tiles: {glyph:?}'s {name} should be true or false")),
+                    None => Ok(default),
+                };
+
+                Ok((glyph, flag("enterable", true)?, flag("stops_slide", false)?))
+            })
+            .collect()
+    }
+}
+
+/// The engine hooks run in: one that knows boards, with `glyphs` naming their tiles.
+fn engine(glyphs: Arc<[(char, Tile)]>) -> Engine {
+    let mut engine = Engine::new();
+
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine
+        .register_type_with_name::<Board>("Board")
+        .register_get("width", |b: &mut Board| {
+            b.tiles.iter().map(Vec::len).max().unwrap_or(0) as i64
+        })
+        .register_get("height", |b: &mut Board| b.tiles.len() as i64)
+        .register_get("cherries", |b: &mut Board| b.cherry_count() as i64)
+        .register_get("head", |b: &mut Board| match b.get_snake_head() {
+            Some((x, y)) => Dynamic::from_array(vec![(x as i64).into(), (y as i64).into()]),
+            None => Dynamic::UNIT,
+        });
+
+    let names = Arc::clone(&glyphs);
+
+    engine.register_fn(
+        "get",
+        move |b: &mut Board, x: i64, y: i64| -> Result<String, Box<EvalAltResult>> {
+            let (x, y) = cell(b, x, y)?;
+            let tile = b.tiles[y][x];
+
+            Ok(names
+                .iter()
+                .find(|&&(_, t)| t == tile)
+                .map_or(String::new(), |(glyph, _)| glyph.to_string()))
+        },
+    );
+    engine.register_fn(
+        "set",
+        move |b: &mut Board, x: i64, y: i64, glyph: &str| -> Result<(), Box<EvalAltResult>> {
+            let at = cell(b, x, y)?;
+            // The script's own tiles come last, and take precedence like registered ones do
+            let tile = glyphs
+                .iter()
+                .rev()
+                .find(|&&(g, _)| glyph.chars().eq([g]))
+                .map(|&(_, t)| t)
+                .ok_or_else(|| format!("Unknown tile {glyph:?}"))?;
+
+            b.set(at, tile);
+
+            Ok(())
+        },
+    );
+
+    engine
+}
+
+fn cell(board: &Board, x: i64, y: i64) -> Result<Position, Box<EvalAltResult>> {
+    let on_board = usize::try_from(x)
+        .ok()
+        .zip(usize::try_from(y).ok())
+        .filter(|&(x, y)| board.tiles.get(y).is_some_and(|row| x < row.len()));
+
+    on_board.ok_or_else(|| format!("{x}, {y} is off the board").into())
+}
+
+/// A compiled script and the engine to call it with, shared by everything it installed.
+struct Runner {
+    engine: Engine,
+    ast: AST,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl Runner {
+    /// Call `name` with `this` bound to `board`, which ends up as the script left it.
+    /// `None` if the call failed, after noting why.
+    fn call(&self, board: &mut Board, name: &str, args: impl FuncArgs) -> Option<Dynamic> {
+        let mut this = Dynamic::from(board.clone());
+        let mut options = CallFnOptions::new().bind_this_ptr(&mut this);
+
+        // The script's top level has already run, when it was compiled
+        options.eval_ast = false;
+
+        let result =
+            self.engine
+                .call_fn_with_options(options, &mut Scope::new(), &self.ast, name, args);
+
+        match result {
+            Ok(value) => {
+                if let Some(changed) = this.try_cast::<Board>() {
+                    *board = changed;
+                }
+
+                Some(value)
+            }
+            Err(e) => {
+                self.fail(format!("{name}: {e}"));
+                None
+            }
+        }
+    }
+
+    /// Note `error`, unless an earlier one was noted already.
+    fn fail(&self, error: String) {
+        if let Ok(mut first) = self.error.lock() {
+            first.get_or_insert(error);
+        }
+    }
+}
+
+/// A tile the script added, which calls its `on_enter`.
+struct Scripted {
+    glyph: char,
+    enterable: bool,
+    stops_slide: bool,
+    runner: Arc<Runner>,
+}
+
+impl TileBehavior for Scripted {
+    fn enterable(&self) -> bool {
+        self.enterable
+    }
+
+    fn stops_slide(&self) -> bool {
+        self.stops_slide
+    }
+
+    fn on_enter(&self, board: &mut Board, (x, y): Position) {
+        let args = (self.glyph.to_string(), x as i64, y as i64);
+
+        self.runner.call(board, "on_enter", args);
+    }
+}
+
+/// The script's `on_move` and `solved`, for whichever it defines.
+struct ScriptHooks {
+    runner: Arc<Runner>,
+    on_move: bool,
+    solved: bool,
+}
+
+impl Hooks for ScriptHooks {
+    fn after_move(&self, board: &mut Board, dir: Dir) {
+        if self.on_move {
+            self.runner
+                .call(board, "on_move", (dir.letter().to_string(),));
+        }
+    }
+
+    fn solved(&self, board: &Board) -> bool {
+        match self.solved {
+            true => {
+                let solved = self.runner.call(&mut board.clone(), "solved", ());
+
+                solved.is_some_and(|s| {
+                    s.as_bool().unwrap_or_else(|_| {
+                        self.runner
+                            .fail(format!("solved: returned {s}, not true or false"));
+                        false
+                    })
+                })
+            }
+            false => board.cherry_count() == 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::greedy::Heuristic;
+    use crate::objective::Objective;
+    use crate::{check, solve_within};
+
+    fn registry(source: &str) -> TileRegistry {
+        let mut tiles = TileRegistry::default();
+
+        Script::compile(source)
+            .unwrap()
+            .install(&mut tiles)
+            .unwrap();
+        tiles
+    }
+
+    #[test]
+    fn the_search_asks_the_script_whether_its_solved() {
+        let board = Board::new("crc").unwrap();
+        let tiles = registry("fn solved() { this.cherries <= 1 }");
+
+        assert!(solve_within(&board, None, &TileRegistry::default())
+            .solution
+            .is_none());
+        assert!(solve_within(&board, None, &tiles).solution.is_some());
+    }
+
+    #[test]
+    fn every_search_stops_where_the_script_says() {
+        let board = Board::new("c.c\nc.c\nccc").unwrap();
+        let tiles = registry("fn solved() { this.cherries <= 3 }");
+        let shortest = solve_within(&board, None, &tiles).solution.unwrap();
+
+        let found = [
+            crate::astar::solve_astar(&board, None, &tiles),
+            crate::ida::solve_ida(&board, None, &tiles),
+            crate::greedy::solve_greedy(&board, Heuristic::Lines, None, &tiles),
+            crate::analysis::longest(&board, None, &tiles),
+            crate::objective::solve_optimal(&board, None, &tiles, Objective::Moves),
+        ];
+
+        for outcome in found {
+            let solution = outcome.solution.unwrap();
+
+            assert!(check(&board, solution.start, &solution.moves, &tiles).is_ok());
+        }
+
+        let astar = crate::astar::solve_astar(&board, None, &tiles)
+            .solution
+            .unwrap();
+        let ida = crate::ida::solve_ida(&board, None, &tiles)
+            .solution
+            .unwrap();
+
+        assert_eq!(astar.moves.len(), shortest.moves.len());
+        assert_eq!(ida.moves.len(), shortest.moves.len());
+    }
+
+    #[test]
+    fn scripted_tiles_and_moves_change_the_board() {
+        let tiles = registry(
+            r#"
+            fn tiles() { #{ "t": #{ stops_slide: true } } }
+            fn on_enter(glyph, x, y) { this.set(x, y + 1, "r") }
+            fn on_move(dir) { if dir == "R" { this.set(3, 0, "r") } }
+            "#,
+        );
+        let board = Board::parse("c.t.\n....", &tiles).unwrap();
+
+        let moved = board
+            .place_snake((0, 0))
+            .move_snake(Dir::Right, &tiles)
+            .unwrap();
+
+        assert_eq!(moved.get_snake_head(), Some((2, 0)));
+        assert_eq!(moved.tiles[1][2], Tile::Rock);
+        assert_eq!(moved.tiles[0][3], Tile::Rock);
+    }
+}
@@ -0,0 +1,282 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::api;
+use crate::json::Json;
+use crate::metrics::Metrics;
+use crate::websocket;
+
+const MAX_BODY: usize = 1 << 20;
+
+/// The most the request line and headers may take together.
+const MAX_HEADER: usize = 16 << 10;
+
+/// Connections handled at once. As many again may wait for a free worker, and any beyond
+/// that wait to be accepted.
+const WORKERS: usize = 8;
+
+/// How long a read or write on a connection may stall before it's given up on, so that a
+/// client that stops sending or reading can't hold a worker forever.
+pub(crate) const TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Request {
+    method: String,
+    path: String,
+    websocket_key: Option<String>,
+    body: String,
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: Json) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: Json::obj([("error", Json::Str(message.into()))]).to_string(),
+        }
+    }
+}
+
+/// State shared by every connection.
+struct Server {
+    time_limit: Duration,
+    metrics: Metrics,
+}
+
+/// Serve the solver over HTTP, handling connections on a pool of [`WORKERS`] threads. No
+/// solve is allowed to run for longer than `time_limit`.
+pub fn serve(addr: &str, time_limit: Duration) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let server = Arc::new(Server {
+        time_limit,
+        metrics: Metrics::default(),
+    });
+    let (queue, waiting) = mpsc::sync_channel::<TcpStream>(WORKERS);
+    let waiting = Arc::new(Mutex::new(waiting));
+
+    for _ in 0..WORKERS {
+        let (server, waiting) = (Arc::clone(&server), Arc::clone(&waiting));
+
+        thread::spawn(move || loop {
+            // The lock is only held while waiting, not while handling
+            let next = match waiting.lock() {
+                Ok(waiting) => waiting.recv(),
+                Err(_) => return,
+            };
+
+            let Ok(stream) = next else {
+                return;
+            };
+
+            if let Err(e) = handle(stream, &server) {
+                eprintln!("Connection error: {e}");
+            }
+        });
+    }
+
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let stream = match stream.and_then(|s| {
+            s.set_read_timeout(Some(TIMEOUT))?;
+            s.set_write_timeout(Some(TIMEOUT))?;
+            Ok(s)
+        }) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to accept connection: {e}");
+                continue;
+            }
+        };
+
+        // Blocks while every worker is busy and the queue is full, leaving any more
+        // connections waiting to be accepted
+        if queue.send(stream).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, server: &Server) -> io::Result<()> {
+    let response = match read_request(&mut stream)? {
+        Ok(Request {
+            method,
+            path,
+            websocket_key: Some(key),
+            ..
+        }) if method == "GET" && path == "/ws/solve" => {
+            return websocket::stream_solve(stream, &key, server.time_limit, &server.metrics);
+        }
+        Ok(request) => route(&request, server),
+        Err(response) => response,
+    };
+
+    if response.status == 400 {
+        server.metrics.record_bad_request();
+    }
+
+    let body = response.body;
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Error",
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        response.status,
+        response.content_type,
+        body.len()
+    )?;
+
+    stream.flush()
+}
+
+/// Read a line into `line`, taking at most `budget` bytes, which it's reduced by. `false`
+/// if the line didn't end within the budget.
+fn read_line_within(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    budget: &mut usize,
+) -> io::Result<bool> {
+    let read = reader.take(*budget as u64).read_line(line)?;
+
+    *budget -= read;
+
+    Ok(read == 0 || line.ends_with('\n'))
+}
+
+fn read_request(stream: &mut TcpStream) -> io::Result<Result<Request, Response>> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    let mut budget = MAX_HEADER;
+    let too_large = || Ok(Err(Response::error(431, "Request headers too large")));
+
+    if !read_line_within(&mut reader, &mut line, &mut budget)? {
+        return too_large();
+    }
+
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(Err(Response::error(400, "Malformed request line")));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut content_length = 0;
+    let mut websocket_key = None;
+
+    loop {
+        line.clear();
+
+        if !read_line_within(&mut reader, &mut line, &mut budget)? {
+            return too_large();
+        }
+
+        if line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = match value.trim().parse() {
+                    Ok(n) => n,
+                    Err(_) => return Ok(Err(Response::error(400, "Invalid Content-Length"))),
+                };
+            } else if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                websocket_key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return Ok(Err(Response::error(413, "Request body too large")));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let Ok(body) = String::from_utf8(body) else {
+        return Ok(Err(Response::error(400, "Request body is not UTF-8")));
+    };
+
+    Ok(Ok(Request {
+        method,
+        path,
+        websocket_key,
+        body,
+    }))
+}
+
+fn route(request: &Request, server: &Server) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/solve") => respond(solve(&request_json(&request.body), server)),
+        ("POST", "/validate") => respond(
+            Json::parse(&request.body)
+                .map_err(|e| format!("Expected a JSON object: {e}"))
+                .and_then(|json| api::validate(&json)),
+        ),
+        #[cfg(feature = "gui")]
+        ("GET", "/") => Response {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: include_str!("gui.html").to_string(),
+        },
+        ("GET", "/metrics") => Response {
+            status: 200,
+            content_type: "text/plain; version=0.0.4",
+            body: server.metrics.render(),
+        },
+        (_, "/solve" | "/validate") => Response::error(405, "Use POST"),
+        _ => Response::error(404, "Not found"),
+    }
+}
+
+fn solve(request: &Json, server: &Server) -> Result<Json, String> {
+    let request = api::solve_request(request, server.time_limit)?;
+    let started = Instant::now();
+    let outcome = request.run(started);
+    let elapsed = started.elapsed();
+
+    server.metrics.record(&outcome, elapsed);
+
+    Ok(request.result(&outcome, elapsed))
+}
+
+/// Accept either a JSON object with a `board` field or the raw board text.
+fn request_json(body: &str) -> Json {
+    match Json::parse(body) {
+        Ok(json) if json.get("board").is_some() => json,
+        _ => Json::obj([("board", Json::Str(body.to_string()))]),
+    }
+}
+
+fn respond(result: Result<Json, String>) -> Response {
+    match result {
+        Ok(body) => Response::ok(body),
+        Err(e) => Response::error(400, e),
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::analysis::Viability;
+use crate::order::StartOrder;
+use crate::parallel::Cancel;
+use crate::tiles::TileRegistry;
+use crate::{canonical_starts, search, symmetry, Board, Constraints, Position, Solution, Tile};
+
+/// How many recently successful starts to remember.
+const HINTS: usize = 8;
+
+/// A board being edited one tile at a time, as in a puzzle editor, that answers "is it
+/// still solvable?" after every edit faster than solving from scratch would.
+pub struct Session {
+    board: Board,
+    tiles: Arc<TileRegistry>,
+    /// Every board already decided, so that undoing an edit costs nothing.
+    answers: HashMap<Board, Viability>,
+    /// Starts that solved earlier boards, most recent first. A small edit rarely moves the
+    /// solution far, so these are tried before the rest.
+    hints: Vec<Position>,
+}
+
+impl Session {
+    pub fn new(board: Board, tiles: TileRegistry) -> Self {
+        Self {
+            board,
+            tiles: Arc::new(tiles),
+            answers: HashMap::new(),
+            hints: Vec::new(),
+        }
+    }
+
+    /// Turn the rock at `p` into a cherry or the other way round.
+    pub fn toggle(&mut self, (x, y): Position) -> Result<(), String> {
+        let tile = self
+            .board
+            .tiles
+            .get(y)
+            .and_then(|row| row.get(x))
+            .ok_or_else(|| format!("{x}, {y} is off the board"))?;
+
+        let toggled = match tile {
+            Tile::Rock => Tile::Cherry,
+            Tile::Cherry => Tile::Rock,
+            _ => {
+                return Err(format!(
+                    "Only rocks and cherries can be toggled, and {x}, {y} is neither"
+                ))
+            }
+        };
+
+        // Through `set`, so that the key stays in step with the tiles
+        self.board.set((x, y), toggled);
+
+        Ok(())
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Whether the current board can be solved, giving the search up to `limit`.
+    pub fn solvable(&mut self, limit: Duration) -> Viability {
+        if let Some(known) = self.known() {
+            return known;
+        }
+
+        let viability = self.check().run(limit, &AtomicBool::new(false));
+
+        self.record(self.board.clone(), viability.clone());
+
+        viability
+    }
+
+    /// The answer for the current board, if it doesn't need a search: the board was
+    /// decided before, or some cherry can't be reached from the others, which no search
+    /// can fix.
+    pub fn known(&self) -> Option<Viability> {
+        if let Some(known) = self.answers.get(&self.board) {
+            return Some(known.clone());
+        }
+
+        let first = self.board.starting_positions().next()?;
+        let region = self.board.region(first, &self.tiles);
+
+        self.board
+            .starting_positions()
+            .any(|p| !region.contains(&p))
+            .then_some(Viability::Unsolvable)
+    }
+
+    /// What it takes to decide the current board, to be done away from the session, say on
+    /// another thread while the editing carries on.
+    pub fn check(&self) -> Check {
+        Check {
+            board: self.board.clone(),
+            tiles: Arc::clone(&self.tiles),
+            hints: self.hints.clone(),
+        }
+    }
+
+    /// Remember what a [`Check`] found for `board`, which need no longer be the current
+    /// board.
+    pub fn record(&mut self, board: Board, viability: Viability) {
+        if let Viability::Solvable(solution) = &viability {
+            self.hints.retain(|&p| p != solution.start);
+            self.hints.insert(0, solution.start);
+            self.hints.truncate(HINTS);
+        }
+
+        // A timed out search may succeed with a longer limit, so don't remember it
+        if !matches!(viability, Viability::Unknown) {
+            self.answers.insert(board, viability);
+        }
+    }
+}
+
+/// One board of a [`Session`] to decide, with everything the search needs. Its searches
+/// stop early, as if out of time, once `cancel` is set.
+pub struct Check {
+    board: Board,
+    tiles: Arc<TileRegistry>,
+    hints: Vec<Position>,
+}
+
+impl Check {
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Whether the board can be solved, giving the search up to `limit`. Starts that
+    /// solved earlier boards go first.
+    pub fn run(&self, limit: Duration, cancel: &AtomicBool) -> Viability {
+        let board = &self.board;
+        let symmetries = symmetry::pruning(board);
+        let hinted =
+            self.hints.iter().copied().filter(|&(x, y)| {
+                board.tiles.get(y).and_then(|row| row.get(x)) == Some(&Tile::Cherry)
+            });
+        let rest = board
+            .starting_positions()
+            .filter(|p| !self.hints.contains(p));
+        let starts = hinted
+            .chain(rest)
+            .filter(|&p| symmetry::is_canonical(p, &symmetries, board));
+
+        let outcome = search(
+            board,
+            starts,
+            Constraints::default(),
+            Some(Instant::now() + limit),
+            &self.tiles,
+            &mut Cancel(cancel),
+            None,
+        );
+
+        match outcome.solution {
+            Some(solution) => Viability::Solvable(solution),
+            None if outcome.timed_out => Viability::Unknown,
+            None => Viability::Unsolvable,
+        }
+    }
+
+    /// The shortest solution from any start, given `solution`, one found already, and up
+    /// to `limit` to find better. Each start is searched only as deep as the best so far,
+    /// which is what makes this affordable. `None` if it ran out of time.
+    pub fn shortest(
+        &self,
+        solution: Solution,
+        limit: Duration,
+        cancel: &AtomicBool,
+    ) -> Option<Solution> {
+        let board = &self.board;
+        let deadline = Some(Instant::now() + limit);
+        let mut best = solution;
+
+        for p in canonical_starts(board, Constraints::default(), StartOrder::default()) {
+            if best.moves.len() <= 1 {
+                break;
+            }
+
+            let constraints = Constraints {
+                max_depth: Some(best.moves.len() - 1),
+                ..Constraints::default()
+            };
+            let outcome = search(
+                board,
+                std::iter::once(p),
+                constraints,
+                deadline,
+                &self.tiles,
+                &mut Cancel(cancel),
+                None,
+            );
+
+            match outcome.solution {
+                Some(solution) => best = solution,
+                None if outcome.timed_out => return None,
+                None => {}
+            }
+        }
+
+        Some(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggling_keeps_the_key_of_a_fresh_board() {
+        let tiles = TileRegistry::default();
+        let mut session = Session::new(Board::new("cccc\ncrcc\ncccc").unwrap(), tiles);
+
+        session.toggle((1, 1)).unwrap();
+        session.toggle((3, 2)).unwrap();
+
+        let fresh = Board::new("cccc\ncccc\ncccr").unwrap();
+
+        assert_eq!(session.board().key, fresh.key);
+        assert_eq!(*session.board(), fresh);
+
+        // Back where it started, the board is the one it started as, key and all
+        session.toggle((1, 1)).unwrap();
+        session.toggle((3, 2)).unwrap();
+
+        assert_eq!(*session.board(), Board::new("cccc\ncrcc\ncccc").unwrap());
+    }
+}
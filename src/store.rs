@@ -0,0 +1,201 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, Row};
+
+use crate::id::BoardId;
+use crate::{Dir, Position};
+
+/// One solve attempt of one puzzle.
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// What the puzzle is known by in the store: see [`Board::id`](crate::Board::id).
+    pub id: BoardId,
+    pub name: String,
+    pub board: String,
+    pub solved: bool,
+    pub timed_out: bool,
+    pub start: Option<Position>,
+    pub moves: Vec<Dir>,
+    pub states: usize,
+    pub elapsed_ms: f64,
+    pub recorded_at: u64,
+}
+
+impl Record {
+    /// The record in a row of [`COLUMNS`].
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let text = |i| row.get::<_, String>(i);
+        let invalid = |i, e: String| {
+            rusqlite::Error::FromSqlConversionFailure(i, rusqlite::types::Type::Text, e.into())
+        };
+
+        let start = match (row.get::<_, Option<i64>>(5)?, row.get::<_, Option<i64>>(6)?) {
+            (Some(x), Some(y)) => Some((x as usize, y as usize)),
+            _ => None,
+        };
+
+        let moves = text(7)?
+            .chars()
+            .map(|m| m.to_string().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|e| invalid(7, e))?;
+
+        Ok(Self {
+            id: text(0)?.parse().map_err(|e| invalid(0, e))?,
+            name: text(1)?,
+            board: text(2)?,
+            solved: row.get(3)?,
+            timed_out: row.get(4)?,
+            start,
+            moves,
+            states: row.get::<_, i64>(8)? as usize,
+            elapsed_ms: row.get(9)?,
+            recorded_at: row.get::<_, i64>(10)? as u64,
+        })
+    }
+}
+
+/// What [`Record::from_row`] reads, in order.
+const COLUMNS: &str = "board_id, name, board, solved, timed_out, start_x, start_y, moves, \
+                       states, elapsed_ms, recorded_at";
+
+/// The latest record of each board, in the order the boards were first added.
+const LATEST: &str = "FROM records r
+    WHERE r.seq = (SELECT MAX(seq) FROM records WHERE board_id = r.board_id)";
+const FIRST_ADDED: &str = "(SELECT MIN(seq) FROM records WHERE board_id = r.board_id)";
+
+/// Solve records kept in a SQLite file, every attempt at every board.
+pub struct Store {
+    db: Connection,
+}
+
+impl Store {
+    /// Open the store at `path`, creating it if there isn't one.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let db = Connection::open(path)?;
+
+        db.execute_batch(
+            "CREATE TABLE IF NOT EXISTS records (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                board_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                board TEXT NOT NULL,
+                solved INTEGER NOT NULL,
+                timed_out INTEGER NOT NULL,
+                start_x INTEGER,
+                start_y INTEGER,
+                moves TEXT NOT NULL,
+                states INTEGER NOT NULL,
+                elapsed_ms REAL NOT NULL,
+                recorded_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS records_by_board ON records (board_id, seq);",
+        )?;
+
+        Ok(Self { db })
+    }
+
+    pub fn add(&self, record: &Record) -> rusqlite::Result<()> {
+        let moves: String = record.moves.iter().map(|m| m.letter()).collect();
+
+        self.db.execute(
+            &format!(
+                "INSERT INTO records ({COLUMNS}) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"
+            ),
+            params![
+                record.id.to_string(),
+                record.name,
+                record.board,
+                record.solved,
+                record.timed_out,
+                record.start.map(|(x, _)| x as i64),
+                record.start.map(|(_, y)| y as i64),
+                moves,
+                record.states as i64,
+                record.elapsed_ms,
+                record.recorded_at as i64,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// The most recent record for each distinct board by id, in the order the boards were
+    /// first added, whatever file each was added from.
+    pub fn latest(&self) -> rusqlite::Result<Vec<Record>> {
+        self.query(
+            &format!("SELECT {COLUMNS} {LATEST} ORDER BY {FIRST_ADDED}"),
+            [],
+        )
+    }
+
+    /// The boards whose latest attempt didn't solve them, in the order they were first
+    /// added.
+    pub fn unsolved(&self) -> rusqlite::Result<Vec<Record>> {
+        self.query(
+            &format!("SELECT {COLUMNS} {LATEST} AND NOT r.solved ORDER BY {FIRST_ADDED}"),
+            [],
+        )
+    }
+
+    /// The `n` fastest solves among the boards' latest attempts, fastest first.
+    pub fn fastest(&self, n: usize) -> rusqlite::Result<Vec<Record>> {
+        self.query(
+            &format!("SELECT {COLUMNS} {LATEST} AND r.solved ORDER BY r.elapsed_ms LIMIT ?1"),
+            [n as i64],
+        )
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> rusqlite::Result<Vec<Record>> {
+        self.db
+            .prepare(sql)?
+            .query_map(params, Record::from_row)?
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Board;
+
+    fn record(board: &str, solved: bool, elapsed_ms: f64) -> Record {
+        Record {
+            id: Board::new(board).unwrap().id(),
+            name: board.to_string(),
+            board: board.to_string(),
+            solved,
+            timed_out: false,
+            start: solved.then_some((0, 0)),
+            moves: if solved { vec![Dir::Right] } else { Vec::new() },
+            states: 3,
+            elapsed_ms,
+            recorded_at: 0,
+        }
+    }
+
+    #[test]
+    fn queries_see_each_boards_latest_attempt() {
+        let store = Store::open(":memory:").unwrap();
+
+        store.add(&record("c.c", false, 5.0)).unwrap();
+        store.add(&record("cc", true, 2.0)).unwrap();
+        store.add(&record("c.c", true, 1.0)).unwrap();
+        store.add(&record("crc", false, 9.0)).unwrap();
+
+        let names =
+            |records: Vec<Record>| -> Vec<String> { records.into_iter().map(|r| r.name).collect() };
+
+        assert_eq!(names(store.latest().unwrap()), ["c.c", "cc", "crc"]);
+        assert_eq!(names(store.unsolved().unwrap()), ["crc"]);
+        assert_eq!(names(store.fastest(1).unwrap()), ["c.c"]);
+
+        let latest = &store.latest().unwrap()[0];
+
+        assert_eq!(
+            (latest.start, latest.moves.as_slice()),
+            (Some((0, 0)), &[Dir::Right][..])
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+use crate::tiles::TileRegistry;
+use crate::{trail, Board, Position, Solution, Tile};
+
+/// The side of a cell, in SVG user units.
+const CELL: usize = 40;
+
+/// `board` as an SVG image, with the path `solution` takes drawn over it: a line through
+/// every cell the head passes, a ring on the start and each move's number on the cell it
+/// ends in.
+pub fn render(board: &Board, solution: &Solution, tiles: &TileRegistry) -> String {
+    let width = board.tiles.iter().map(Vec::len).max().unwrap_or(0) * CELL;
+    let height = board.tiles.len() * CELL;
+    let center = |(x, y): Position| (x * CELL + CELL / 2, y * CELL + CELL / 2);
+
+    let mut svg = String::new();
+
+    // Writing to a String can't fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="{}">"#,
+        CELL / 3
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="{width}" height="{height}" fill="#f4f1e8"/>"##
+    );
+
+    for (y, row) in board.tiles.iter().enumerate() {
+        for (x, &tile) in row.iter().enumerate() {
+            let (cx, cy) = center((x, y));
+
+            let _ = match tile {
+                Tile::Rock => writeln!(
+                    svg,
+                    r##"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" fill="#777"/>"##,
+                    x * CELL,
+                    y * CELL
+                ),
+                Tile::Cherry => writeln!(
+                    svg,
+                    r##"<circle cx="{cx}" cy="{cy}" r="{}" fill="#d33"/>"##,
+                    CELL / 6
+                ),
+                Tile::SnakeBody | Tile::SnakeHead => writeln!(
+                    svg,
+                    r##"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" fill="#8c8"/>"##,
+                    x * CELL,
+                    y * CELL
+                ),
+                Tile::Empty => Ok(()),
+                Tile::Custom(_) => writeln!(
+                    svg,
+                    r##"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" fill="#bbd"/>"##,
+                    x * CELL,
+                    y * CELL
+                ),
+            };
+        }
+    }
+
+    let points: Vec<String> = trail(board, solution, tiles)
+        .into_iter()
+        .map(|p| {
+            let (x, y) = center(p);
+
+            format!("{x},{y}")
+        })
+        .collect();
+
+    let _ = writeln!(
+        svg,
+        r##"<polyline points="{}" fill="none" stroke="#2a2" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round" opacity="0.8"/>"##,
+        points.join(" "),
+        CELL / 5
+    );
+
+    let (sx, sy) = center(solution.start);
+
+    let _ = writeln!(
+        svg,
+        r##"<circle cx="{sx}" cy="{sy}" r="{}" fill="none" stroke="#161" stroke-width="3"/>"##,
+        CELL / 3
+    );
+
+    // Each number sits on the cell its move ends in
+    let mut state = board.place_snake(solution.start);
+
+    for (i, &dir) in solution.moves.iter().enumerate() {
+        let Some(next) = state.move_snake(dir, tiles) else {
+            break;
+        };
+
+        state = next;
+
+        if let Some(head) = state.get_snake_head() {
+            let (x, y) = center(head);
+
+            let _ = writeln!(
+                svg,
+                r##"<text x="{x}" y="{y}" text-anchor="middle" dominant-baseline="central" fill="#fff" stroke="#161" stroke-width="3" paint-order="stroke">{}</text>"##,
+                i + 1
+            );
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
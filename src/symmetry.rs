@@ -0,0 +1,173 @@
+use std::fmt;
+
+use crate::{Board, Position, Tile};
+
+/// A way of mapping a board onto itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Left-right mirror.
+    MirrorX,
+    /// Top-bottom mirror.
+    MirrorY,
+    /// Mirror in the top-left to bottom-right diagonal. Square boards only.
+    Transpose,
+    /// Mirror in the other diagonal. Square boards only.
+    AntiTranspose,
+    /// Quarter turn clockwise. Square boards only.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Symmetry {
+    pub(crate) const ALL: [Symmetry; 7] = [
+        Symmetry::MirrorX,
+        Symmetry::MirrorY,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+    ];
+
+    /// Where the cell at `(x, y)` ends up on a `width` × `height` board.
+    pub fn apply(self, (x, y): Position, width: usize, height: usize) -> Position {
+        let (fx, fy) = (width - 1 - x, height - 1 - y);
+
+        match self {
+            Symmetry::MirrorX => (fx, y),
+            Symmetry::MirrorY => (x, fy),
+            Symmetry::Transpose => (y, x),
+            Symmetry::AntiTranspose => (fy, fx),
+            Symmetry::Rotate90 => (fy, x),
+            Symmetry::Rotate180 => (fx, fy),
+            Symmetry::Rotate270 => (y, fx),
+        }
+    }
+
+    /// How wide and high a `width` × `height` board is once mapped, which swaps the two
+    /// for the symmetries that only map square boards onto themselves.
+    pub fn image_size(self, width: usize, height: usize) -> (usize, usize) {
+        if self.needs_square() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// The symmetry that undoes this one.
+    pub fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            s => s,
+        }
+    }
+
+    fn needs_square(self) -> bool {
+        matches!(
+            self,
+            Symmetry::Transpose
+                | Symmetry::AntiTranspose
+                | Symmetry::Rotate90
+                | Symmetry::Rotate270
+        )
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Symmetry::MirrorX => "left-right mirror",
+            Symmetry::MirrorY => "top-bottom mirror",
+            Symmetry::Transpose => "diagonal mirror",
+            Symmetry::AntiTranspose => "anti-diagonal mirror",
+            Symmetry::Rotate90 => "quarter turn",
+            Symmetry::Rotate180 => "half turn",
+            Symmetry::Rotate270 => "three-quarter turn",
+        })
+    }
+}
+
+/// Every symmetry that maps the board's layout onto itself. Ragged boards have none.
+pub fn symmetries(board: &Board) -> Vec<Symmetry> {
+    let height = board.tiles.len();
+    let width = board.tiles.first().map_or(0, Vec::len);
+
+    if width == 0 || board.tiles.iter().any(|row| row.len() != width) {
+        return Vec::new();
+    }
+
+    Symmetry::ALL
+        .into_iter()
+        .filter(|s| !s.needs_square() || width == height)
+        .filter(|&s| {
+            (0..height).all(|y| {
+                (0..width).all(|x| {
+                    let (sx, sy) = s.apply((x, y), width, height);
+
+                    board.tiles[y][x] == board.tiles[sy][sx]
+                })
+            })
+        })
+        .collect()
+}
+
+/// The symmetries a search may skip mirrored starts by: those of the layout, unless the
+/// board holds something a mirror doesn't carry over. A custom tile's behaviour is told
+/// where it was entered and may act differently in different places, and a decaying body's
+/// ages aren't part of the layout, so boards with either get none.
+pub fn pruning(board: &Board) -> Vec<Symmetry> {
+    let custom = board
+        .tiles
+        .iter()
+        .flatten()
+        .any(|t| matches!(t, Tile::Custom(_)));
+
+    match custom || !board.ages.is_empty() {
+        true => Vec::new(),
+        false => symmetries(board),
+    }
+}
+
+/// Whether `s` maps `p` onto itself.
+pub fn fixes(s: Symmetry, p: Position, board: &Board) -> bool {
+    let height = board.tiles.len();
+    let width = board.tiles.first().map_or(0, Vec::len);
+
+    s.apply(p, width, height) == p
+}
+
+/// The first of `p`'s images under `symmetries` in reading order, which is the one
+/// [`is_canonical`] keeps, and the symmetry that takes `p` there, if `p` isn't it already.
+pub fn representative(
+    p: Position,
+    symmetries: &[Symmetry],
+    board: &Board,
+) -> (Position, Option<Symmetry>) {
+    let height = board.tiles.len();
+    let width = board.tiles.first().map_or(0, Vec::len);
+    let order = |(x, y): Position| (y, x);
+
+    symmetries
+        .iter()
+        .map(|&s| (s.apply(p, width, height), Some(s)))
+        .fold((p, None), |best, image| {
+            match order(image.0) < order(best.0) {
+                true => image,
+                false => best,
+            }
+        })
+}
+
+/// Whether `p` is the first of its images under `symmetries` in reading order. A search
+/// only needs to try those starts: any other is a mirror image of one already tried.
+pub fn is_canonical(p: Position, symmetries: &[Symmetry], board: &Board) -> bool {
+    let height = board.tiles.len();
+    let width = board.tiles.first().map_or(0, Vec::len);
+    let order = |(x, y): Position| (y, x);
+
+    symmetries
+        .iter()
+        .all(|s| order(s.apply(p, width, height)) >= order(p))
+}
@@ -0,0 +1,184 @@
+use crate::{Board, Dir, Position, Tile};
+
+/// How the snake interacts with a kind of tile. `move_snake` consults this for every cell
+/// it slides into, so new tiles can be added by registering a behaviour rather than by
+/// changing the movement code.
+pub trait TileBehavior: Send + Sync {
+    /// Whether the snake may move onto the tile at all. Tiles that can't be entered stop
+    /// the slide in front of them.
+    fn enterable(&self) -> bool;
+
+    /// Whether the slide ends once the snake has moved onto the tile.
+    fn stops_slide(&self) -> bool {
+        false
+    }
+
+    /// Called after the snake's head has moved onto the tile at `at`.
+    fn on_enter(&self, _board: &mut Board, _at: Position) {}
+}
+
+/// Rules beyond what any one tile does, for variants [`crate::rules::Rules`] can't
+/// describe. A registry carrying them is searched a whole board at a time and without
+/// pruning, since neither assumes less than any hook might do.
+pub trait Hooks: Send + Sync {
+    /// Called once the snake has moved, after its tiles' [`TileBehavior::on_enter`]. A
+    /// move that leaves the snake where it was doesn't count.
+    fn after_move(&self, _board: &mut Board, _dir: Dir) {}
+
+    /// Whether `board` is solved, which the breadth-first search asks of every state. By
+    /// default, once every cherry is eaten.
+    fn solved(&self, board: &Board) -> bool {
+        board.cherry_count() == 0
+    }
+}
+
+struct Blocking;
+
+impl TileBehavior for Blocking {
+    fn enterable(&self) -> bool {
+        false
+    }
+}
+
+struct Edible;
+
+impl TileBehavior for Edible {
+    fn enterable(&self) -> bool {
+        true
+    }
+}
+
+/// A custom tile described entirely by its flags, for tiles declared in data rather than
+/// code.
+pub struct Declared {
+    pub enterable: bool,
+    pub stops_slide: bool,
+}
+
+impl TileBehavior for Declared {
+    fn enterable(&self) -> bool {
+        self.enterable
+    }
+
+    fn stops_slide(&self) -> bool {
+        self.stops_slide
+    }
+}
+
+/// Behaviours for the built-in tiles plus any custom tiles, which are parsed from their
+/// registered glyph into [`Tile::Custom`], and any other glyphs boards may use for them.
+#[derive(Default)]
+pub struct TileRegistry {
+    custom: Vec<(char, Box<dyn TileBehavior>)>,
+    aliases: Vec<(char, Tile)>,
+    hooks: Option<Box<dyn Hooks>>,
+}
+
+impl TileRegistry {
+    /// The header line that gives a board's own glyphs, as [`TileRegistry::parse_aliases`]
+    /// reads them.
+    pub const HEADER: &'static str = "tiles:";
+
+    /// Register a custom tile written as `glyph`, replacing any earlier registration of the
+    /// same glyph. Returns `None` once all 256 custom tile slots are taken.
+    pub fn register(&mut self, glyph: char, behavior: impl TileBehavior + 'static) -> Option<Tile> {
+        if let Some(i) = self.custom.iter().position(|(g, _)| *g == glyph) {
+            self.custom[i].1 = Box::new(behavior);
+
+            return Some(Tile::Custom(i as u8));
+        }
+
+        let id = u8::try_from(self.custom.len()).ok()?;
+
+        self.custom.push((glyph, Box::new(behavior)));
+
+        Some(Tile::Custom(id))
+    }
+
+    /// Read `glyph` as `tile` too, for boards written in another notation, like `#` for
+    /// rocks. Takes precedence over the glyph's own meaning.
+    pub fn alias(&mut self, glyph: char, tile: Tile) {
+        self.aliases.retain(|&(g, _)| g != glyph);
+        self.aliases.push((glyph, tile));
+    }
+
+    /// Aliases written as `G=T` pairs separated by whitespace, like `#=r .=c`, where each `T`
+    /// is a glyph this registry already knows. Errors with the first pair that isn't one.
+    pub fn parse_aliases(&self, pairs: &str) -> Result<Vec<(char, Tile)>, String> {
+        pairs
+            .split_whitespace()
+            .map(|pair| {
+                let mut chars = pair.chars();
+                let alias = match (chars.next(), chars.next(), chars.next(), chars.next()) {
+                    (Some(glyph), Some('='), Some(tile), None) => {
+                        self.tile(tile).map(|tile| (glyph, tile))
+                    }
+                    _ => None,
+                };
+
+                alias.ok_or_else(|| pair.to_string())
+            })
+            .collect()
+    }
+
+    /// Play by `hooks` too, replacing any set before.
+    pub fn set_hooks(&mut self, hooks: impl Hooks + 'static) {
+        self.hooks = Some(Box::new(hooks));
+    }
+
+    pub fn hooks(&self) -> Option<&dyn Hooks> {
+        self.hooks.as_deref()
+    }
+
+    /// Whether `board` is solved: by the [`Hooks`] if there are any, or else once every
+    /// cherry is eaten.
+    pub fn solved(&self, board: &Board) -> bool {
+        match &self.hooks {
+            Some(hooks) => hooks.solved(board),
+            None => board.cherry_count() == 0,
+        }
+    }
+
+    pub fn behavior(&self, tile: Tile) -> &dyn TileBehavior {
+        match tile {
+            Tile::Cherry | Tile::Empty => &Edible,
+            Tile::Rock | Tile::SnakeBody | Tile::SnakeHead => &Blocking,
+            Tile::Custom(id) => self
+                .custom
+                .get(id as usize)
+                .map_or(&Blocking, |(_, b)| b.as_ref()),
+        }
+    }
+
+    /// How `tile` is written: the reverse of [`TileRegistry::tile`]. `None` for a custom
+    /// tile that isn't registered here.
+    pub fn glyph(&self, tile: Tile) -> Option<char> {
+        match tile {
+            Tile::Cherry => Some('c'),
+            Tile::Empty => Some('.'),
+            Tile::Rock => Some('r'),
+            Tile::SnakeBody => Some('s'),
+            Tile::SnakeHead => Some('S'),
+            Tile::Custom(id) => self.custom.get(id as usize).map(|(g, _)| *g),
+        }
+    }
+
+    /// The tile written as `glyph`, if it's an alias or a built-in or registered one.
+    pub fn tile(&self, glyph: char) -> Option<Tile> {
+        if let Some(&(_, tile)) = self.aliases.iter().find(|(g, _)| *g == glyph) {
+            return Some(tile);
+        }
+
+        match self.custom.iter().position(|(g, _)| *g == glyph) {
+            Some(i) => Some(Tile::Custom(i as u8)),
+            None => match glyph {
+                'c' => Some(Tile::Cherry),
+                '.' => Some(Tile::Empty),
+                'r' => Some(Tile::Rock),
+                's' => Some(Tile::SnakeBody),
+                'S' => Some(Tile::SnakeHead),
+                _ => None,
+            },
+        }
+    }
+}
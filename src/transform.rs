@@ -0,0 +1,293 @@
+use crate::symmetry::Symmetry;
+use crate::tiles::TileRegistry;
+use crate::{Board, Dir, Position, Solution, Tile};
+
+/// How [`Board::stitch`] joins rooms side by side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Join {
+    /// Edge to edge.
+    Touching,
+    /// Apart by a wall of rock this thick, with a corridor of cherries through the middle
+    /// of each wall between two rooms.
+    Corridor(usize),
+}
+
+impl Board {
+    fn rectangle(&self) -> Option<(usize, usize)> {
+        let width = self.tiles.first().map_or(0, Vec::len);
+
+        self.tiles
+            .iter()
+            .all(|row| row.len() == width)
+            .then_some((width, self.tiles.len()))
+    }
+
+    /// The board's image under `s`, rules, snake and all. Symmetries that need a square
+    /// board to map it onto itself just swap its width and height. Ragged boards have no
+    /// images.
+    pub fn transformed(&self, s: Symmetry) -> Option<Board> {
+        let (width, height) = self.rectangle()?;
+        let (w, h) = s.image_size(width, height);
+        let mut tiles = vec![vec![Tile::Cherry; w]; h];
+
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, &t) in row.iter().enumerate() {
+                let (ix, iy) = s.apply((x, y), width, height);
+
+                tiles[iy][ix] = t;
+            }
+        }
+
+        let mut board = Board::from_tiles(tiles).with_rules(self.rules);
+
+        board.ages = self
+            .ages
+            .iter()
+            .map(|&(p, n)| (s.apply(p, width, height), n))
+            .collect();
+        board.ages.sort_unstable();
+
+        Some(board)
+    }
+
+    /// The board turned clockwise by `degrees`, which is 90, 180 or 270.
+    pub fn rotate(&self, degrees: u32) -> Option<Board> {
+        match degrees % 360 {
+            0 => Some(self.clone()),
+            90 => self.transformed(Symmetry::Rotate90),
+            180 => self.transformed(Symmetry::Rotate180),
+            270 => self.transformed(Symmetry::Rotate270),
+            _ => None,
+        }
+    }
+
+    /// The board mirrored left to right, or top to bottom when `vertical` is set.
+    pub fn mirror(&self, vertical: bool) -> Option<Board> {
+        self.transformed(match vertical {
+            false => Symmetry::MirrorX,
+            true => Symmetry::MirrorY,
+        })
+    }
+
+    /// The `width` × `height` part of the board from `(x, y)`, if the board covers all of
+    /// it.
+    pub fn crop(&self, (x, y): Position, width: usize, height: usize) -> Option<Board> {
+        let rows = self.tiles.get(y..y.checked_add(height)?)?;
+        let tiles = rows
+            .iter()
+            .map(|row| Some(row.get(x..x.checked_add(width)?)?.to_vec()))
+            .collect::<Option<Vec<_>>>()?;
+
+        if tiles.is_empty() || width == 0 {
+            return None;
+        }
+
+        let mut board = Board::from_tiles(tiles).with_rules(self.rules);
+
+        board.ages = self
+            .ages
+            .iter()
+            .filter(|&&((ax, ay), _)| (x..x + width).contains(&ax) && (y..y + height).contains(&ay))
+            .map(|&((ax, ay), n)| ((ax - x, ay - y), n))
+            .collect();
+
+        Some(board)
+    }
+
+    /// The board with `tile` added around it: `left`, `top`, `right` and `bottom` cells
+    /// deep on each side.
+    pub fn pad(&self, [left, top, right, bottom]: [usize; 4], tile: Tile) -> Board {
+        let width = self.tiles.iter().map(Vec::len).max().unwrap_or(0) + left + right;
+        let side = |n| std::iter::repeat_n(tile, n);
+
+        let tiles = side(top)
+            .map(|_| vec![tile; width])
+            .chain(self.tiles.iter().map(|row| {
+                side(left)
+                    .chain(row.iter().copied())
+                    .chain(side(right))
+                    .collect()
+            }))
+            .chain(side(bottom).map(|_| vec![tile; width]))
+            .collect();
+
+        let mut board = Board::from_tiles(tiles).with_rules(self.rules);
+
+        board.ages = self
+            .ages
+            .iter()
+            .map(|&((x, y), n)| ((x + left, y + top), n))
+            .collect();
+
+        board
+    }
+
+    /// One board made of `rooms`, laid out row by row, each room padded with rock to the
+    /// widest room in its column and the tallest in its row. The rooms must share their
+    /// rules and have no snake on them yet.
+    pub fn stitch(rooms: &[Vec<Board>], join: Join) -> Result<Board, String> {
+        let first = rooms.iter().flatten().next().ok_or("There are no rooms")?;
+        let size = |b: &Board| {
+            (
+                b.tiles.iter().map(Vec::len).max().unwrap_or(0),
+                b.tiles.len(),
+            )
+        };
+
+        for (r, row) in rooms.iter().enumerate() {
+            for (c, room) in row.iter().enumerate() {
+                if room.rules != first.rules {
+                    return Err(format!("Room {c},{r} has different rules from the first"));
+                }
+
+                if room
+                    .tiles
+                    .iter()
+                    .flatten()
+                    .any(|t| matches!(t, Tile::SnakeHead | Tile::SnakeBody))
+                {
+                    return Err(format!("Room {c},{r} already has a snake on it"));
+                }
+            }
+        }
+
+        let columns = rooms.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|c| {
+                rooms
+                    .iter()
+                    .filter_map(|row| row.get(c))
+                    .map(|b| size(b).0)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let heights: Vec<usize> = rooms
+            .iter()
+            .map(|row| row.iter().map(|b| size(b).1).max().unwrap_or(0))
+            .collect();
+
+        let gap = match join {
+            Join::Touching => 0,
+            Join::Corridor(n) => n,
+        };
+        // Where each column and row of rooms starts
+        let offsets = |sizes: &[usize]| -> Vec<usize> {
+            sizes
+                .iter()
+                .scan(0, |at, &n| {
+                    let start = *at;
+
+                    *at += n + gap;
+                    Some(start)
+                })
+                .collect()
+        };
+        let (xs, ys) = (offsets(&widths), offsets(&heights));
+
+        let width = widths.iter().sum::<usize>() + gap * columns.saturating_sub(1);
+        let height = heights.iter().sum::<usize>() + gap * rooms.len().saturating_sub(1);
+        let mut tiles = vec![vec![Tile::Rock; width]; height];
+
+        for (r, row) in rooms.iter().enumerate() {
+            for (c, room) in row.iter().enumerate() {
+                for (y, line) in room.tiles.iter().enumerate() {
+                    tiles[ys[r] + y][xs[c]..xs[c] + line.len()].copy_from_slice(line);
+                }
+            }
+        }
+
+        if gap > 0 {
+            for (r, row) in rooms.iter().enumerate() {
+                for (c, room) in row.iter().enumerate() {
+                    let (w, h) = size(room);
+
+                    // Through the wall on the right, halfway down the shorter room
+                    if let Some(right) = row.get(c + 1) {
+                        let y = ys[r] + h.min(size(right).1) / 2;
+
+                        tiles[y][xs[c] + w..xs[c + 1]].fill(Tile::Cherry);
+                    }
+
+                    // And the one below, halfway across the narrower
+                    if let Some(below) = rooms.get(r + 1).and_then(|row| row.get(c)) {
+                        let x = xs[c] + w.min(size(below).0) / 2;
+
+                        for line in &mut tiles[ys[r] + h..ys[r + 1]] {
+                            line[x] = Tile::Cherry;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Board::from_tiles(tiles).with_rules(first.rules))
+    }
+
+    /// The board as a board file: the rules header, left out for the default rules, then
+    /// the rows, with custom tiles written as their glyphs in `tiles`.
+    pub fn to_text(&self, tiles: &TileRegistry) -> String {
+        let rows = self.tiles.iter().map(|row| {
+            row.iter()
+                .map(|&t| tiles.glyph(t).unwrap_or('?'))
+                .collect::<String>()
+        });
+
+        (self.rules != Default::default())
+            .then(|| self.rules.to_string())
+            .into_iter()
+            .chain(rows)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Dir {
+    /// Where this direction points on a board's image under `s`.
+    pub fn transformed(self, s: Symmetry) -> Dir {
+        let (dx, dy) = self.delta();
+
+        let (dx, dy) = match s {
+            Symmetry::MirrorX => (-dx, dy),
+            Symmetry::MirrorY => (dx, -dy),
+            Symmetry::Transpose => (dy, dx),
+            Symmetry::AntiTranspose => (-dy, -dx),
+            Symmetry::Rotate90 => (-dy, dx),
+            Symmetry::Rotate180 => (-dx, -dy),
+            Symmetry::Rotate270 => (dy, -dx),
+        };
+
+        match (dx, dy) {
+            (0, -1) => Dir::Up,
+            (0, 1) => Dir::Down,
+            (1, 0) => Dir::Right,
+            (-1, 0) => Dir::Left,
+            (-1, -1) => Dir::UpLeft,
+            (1, -1) => Dir::UpRight,
+            (-1, 1) => Dir::DownLeft,
+            _ => Dir::DownRight,
+        }
+    }
+}
+
+impl Solution {
+    /// The same solution on the image under `s` of `board`, the board it solves.
+    pub fn transformed(&self, s: Symmetry, board: &Board) -> Solution {
+        let width = board.tiles.first().map_or(0, Vec::len);
+        let height = board.tiles.len();
+
+        Solution {
+            start: s.apply(self.start, width, height),
+            moves: self.moves.iter().map(|d| d.transformed(s)).collect(),
+        }
+    }
+
+    /// The same solution on a board padded by `left` and `top` cells. Rocks around the
+    /// edge stop the snake as the edge did, unless the board wraps.
+    pub fn shifted(&self, left: usize, top: usize) -> Solution {
+        Solution {
+            start: (self.start.0 + left, self.start.1 + top),
+            moves: self.moves.clone(),
+        }
+    }
+}
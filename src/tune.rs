@@ -0,0 +1,147 @@
+use std::fmt;
+use std::mem::size_of;
+use std::str::FromStr;
+
+use crate::analysis::{self, Metrics};
+use crate::bitboard::{Layout, Packed};
+use crate::hashing::HashKind;
+use crate::order::StartOrder;
+use crate::tiles::TileRegistry;
+use crate::{Board, Step, Tile};
+
+/// How much memory an untuned search from one start may use before it's dropped.
+const MEMORY_BUDGET: usize = 4 << 30;
+
+/// Below this many estimated states a search is over before the start order or the hasher
+/// matters.
+const SMALL: f64 = 1e5;
+
+/// The ways `solve` can search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Breadth-first from each start in turn, the default.
+    #[default]
+    Bfs,
+    /// Best-first from every start at once; see [`crate::astar`].
+    AStar,
+    /// Iterative deepening, which holds little more than the line it follows; see
+    /// [`crate::ida`].
+    Ida,
+    /// Most promising state first, for some solution rather than a shortest; see
+    /// [`crate::greedy`].
+    Greedy,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    /// `bfs`, `astar`, `ida` or `greedy`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bfs" => Ok(Algorithm::Bfs),
+            "astar" => Ok(Algorithm::AStar),
+            "ida" => Ok(Algorithm::Ida),
+            "greedy" => Ok(Algorithm::Greedy),
+            _ => Err(format!(
+                "Unknown algorithm {s:?}, expected bfs, astar, ida or greedy"
+            )),
+        }
+    }
+}
+
+/// The name [`Algorithm::from_str`] reads back.
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Algorithm::Bfs => "bfs",
+            Algorithm::AStar => "astar",
+            Algorithm::Ida => "ida",
+            Algorithm::Greedy => "greedy",
+        })
+    }
+}
+
+/// Search settings picked from a quick look at the board, so that nobody needs to know how
+/// the search works to get a good one. Anything the user sets explicitly wins.
+#[derive(Debug, Clone, Copy)]
+pub struct Tuning {
+    pub order: StartOrder,
+    pub hasher: HashKind,
+    /// A cap on the memory the breadth-first search from each start may hold, as it's
+    /// counted while the search runs, set only when a start looks like it could outgrow
+    /// [`MEMORY_BUDGET`].
+    pub max_memory: Option<usize>,
+    /// How to search the starts the cap drops. The estimate behind the cap is a loose upper
+    /// bound, so rather than trust it to skip the breadth-first search, which is usually
+    /// quickest, only the starts that really outgrow the cap are searched again, with
+    /// iterative deepening, whose memory barely grows.
+    pub fallback: Option<Algorithm>,
+}
+
+pub fn tune(board: &Board, tiles: &TileRegistry) -> Tuning {
+    let metrics = analysis::metrics(board, tiles);
+    let big = start_memory(board, &metrics, tiles) > MEMORY_BUDGET as f64;
+
+    Tuning {
+        order: order(&metrics),
+        hasher: hasher(&metrics),
+        max_memory: big.then_some(MEMORY_BUDGET),
+        fallback: big.then_some(Algorithm::Ida),
+    }
+}
+
+fn order(metrics: &Metrics) -> StartOrder {
+    match metrics {
+        Metrics { state_space, .. } if *state_space < SMALL => StartOrder::RowMajor,
+        // With few rocks, starts near the edge cut the board in two early
+        Metrics { open_ratio, .. } if *open_ratio > 0.85 => StartOrder::CenterOut,
+        _ => StartOrder::MostOpen,
+    }
+}
+
+/// A long search spends much of its time hashing states, which are only a few words, and
+/// SipHash's resistance to collisions made on purpose buys nothing against a board.
+fn hasher(metrics: &Metrics) -> HashKind {
+    match metrics.state_space < SMALL {
+        true => HashKind::Std,
+        false => HashKind::Fx,
+    }
+}
+
+/// Roughly how many bytes a breadth-first search from one start could grow to. Each state
+/// is held about twice over, as a key of the visited map and in the queue, and takes a few
+/// bytes more for how it was reached. Packed states are much smaller than boards.
+fn start_memory(board: &Board, metrics: &Metrics, tiles: &TileRegistry) -> f64 {
+    let rows = board.tiles.len();
+    let cells: usize = board.tiles.iter().map(Vec::len).sum();
+    let state = match Layout::new(board, tiles) {
+        Some(_) => size_of::<Packed>(),
+        None => size_of::<Board>() + rows * size_of::<Vec<Tile>>() + cells * size_of::<Tile>(),
+    };
+    let per_state = 2 * state + size_of::<Step>() + 2 * size_of::<u32>() + size_of::<usize>();
+    // The metrics count the states from every cherry
+    metrics.state_space / metrics.cherries.max(1) as f64 * per_state as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_big_boards_are_capped() {
+        let tiles = TileRegistry::default();
+        let small = Board::new("ccc\ncrc\nccc").unwrap();
+        let big = Board::new(&vec!["c".repeat(16); 16].join("\n")).unwrap();
+
+        let small = tune(&small, &tiles);
+        let big = tune(&big, &tiles);
+
+        assert_eq!((small.max_memory, small.fallback), (None, None));
+        assert_eq!(small.hasher, HashKind::Std);
+        assert_eq!(
+            (big.max_memory, big.fallback),
+            (Some(MEMORY_BUDGET), Some(Algorithm::Ida))
+        );
+        assert_eq!(big.hasher, HashKind::Fx);
+    }
+}
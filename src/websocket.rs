@@ -0,0 +1,366 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use crate::analysis;
+use crate::api::{moves_json, position_json, solve_request, trail_json, SolveRequest};
+use crate::json::Json;
+use crate::metrics::Metrics;
+use crate::server::TIMEOUT;
+use crate::tiles::TileRegistry;
+use crate::{solve_watched, Board, Event, Outcome, ProgressSink};
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The most a client's message may take, however many frames it comes in.
+const MAX_MESSAGE: usize = 1 << 20;
+
+/// Complete the WebSocket handshake for a client that sent `key`, then stream one solve:
+/// the client sends a text message with the same JSON as `POST /solve`, and gets back a
+/// `start` event per starting position, `progress` events while the search runs, and a
+/// final `done` event carrying the usual solve result. Shortest solves instead send a
+/// `solution` event for each shorter solution as soon as it's found.
+pub fn stream_solve(
+    mut stream: TcpStream,
+    key: &str,
+    time_limit: Duration,
+    metrics: &Metrics,
+) -> io::Result<()> {
+    // A client that stops reading mid-solve would otherwise block the search's events
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let accept = base64(&sha1(format!("{}{GUID}", key.trim()).as_bytes()));
+
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+    )?;
+
+    let Some(message) = read_text(&mut stream)? else {
+        return close(&mut stream);
+    };
+
+    let request = match Json::parse(&message) {
+        Ok(json) if json.get("board").is_some() => json,
+        _ => Json::obj([("board", Json::Str(message))]),
+    };
+
+    let request = match solve_request(&request, time_limit) {
+        Ok(request) => request,
+        Err(e) => {
+            send_text(&mut stream, &Json::obj([("error", e.into())]).to_string())?;
+
+            return close(&mut stream);
+        }
+    };
+
+    let SolveRequest {
+        board,
+        tiles,
+        limit,
+        ..
+    } = &request;
+
+    let started = Instant::now();
+    let deadline = Some(started + *limit);
+    let mut events = EventStream {
+        stream: &mut stream,
+        board,
+        tiles,
+        sent: Ok(()),
+    };
+
+    if request.shortest {
+        let mut search = analysis::improving(board, deadline, tiles);
+
+        for solution in search.by_ref() {
+            events.send(Json::obj([
+                ("event", "solution".into()),
+                ("start", position_json(solution.start)),
+                ("moves", moves_json(&solution.moves)),
+                ("trail", trail_json(board, &solution, tiles)),
+            ]));
+        }
+
+        let sent = events.sent;
+
+        return finish(
+            &mut stream,
+            &request,
+            &search.finish(),
+            started,
+            metrics,
+            sent,
+        );
+    }
+
+    let outcome = solve_watched(board, deadline, tiles, &mut events);
+    let sent = events.sent;
+
+    finish(&mut stream, &request, &outcome, started, metrics, sent)
+}
+
+/// Sends search events to the client as they happen. Once a send fails the rest are
+/// dropped, and the error is reported when the search is over.
+struct EventStream<'a> {
+    stream: &'a mut TcpStream,
+    board: &'a Board,
+    tiles: &'a TileRegistry,
+    sent: io::Result<()>,
+}
+
+impl EventStream<'_> {
+    fn send(&mut self, json: Json) {
+        if self.sent.is_ok() {
+            self.sent = send_text(self.stream, &json.to_string());
+        }
+    }
+}
+
+impl ProgressSink for EventStream<'_> {
+    fn report(&mut self, event: Event) {
+        let json = match event {
+            Event::Start(p) => Json::obj([("event", "start".into()), ("start", position_json(p))]),
+            Event::Progress {
+                depth,
+                states,
+                queued,
+                explored,
+                memory,
+                cherries_left,
+                best,
+            } => Json::obj([
+                ("event", "progress".into()),
+                ("depth", depth.into()),
+                ("states", states.into()),
+                ("queued", queued.into()),
+                ("explored", explored.into()),
+                ("memory_bytes", memory.into()),
+                ("cherries_left", cherries_left.into()),
+                ("trail", trail_json(self.board, &best, self.tiles)),
+            ]),
+            Event::Layer {
+                depth,
+                states,
+                elapsed,
+            } => Json::obj([
+                ("event", "layer".into()),
+                ("depth", depth.into()),
+                ("states", states.into()),
+                ("elapsed_ms", (elapsed.as_secs_f64() * 1000.0).into()),
+            ]),
+            Event::Abandoned {
+                start,
+                states,
+                timed_out,
+            } => Json::obj([
+                ("event", "abandoned".into()),
+                ("start", position_json(start)),
+                ("states", states.into()),
+                ("timed_out", timed_out.into()),
+            ]),
+            // Far too many to send, and `done` carries the solution
+            Event::Expanded { .. } | Event::Solved(_) => return,
+        };
+
+        self.send(json);
+    }
+}
+
+/// Send the `done` event and close the connection.
+fn finish(
+    stream: &mut TcpStream,
+    request: &SolveRequest,
+    outcome: &Outcome,
+    started: Instant,
+    metrics: &Metrics,
+    sent: io::Result<()>,
+) -> io::Result<()> {
+    let elapsed = started.elapsed();
+
+    metrics.record(outcome, elapsed);
+    sent?;
+
+    let Json::Obj(mut fields) = request.result(outcome, elapsed) else {
+        unreachable!("Solve results are always objects");
+    };
+
+    fields.insert(0, ("event".to_string(), "done".into()));
+
+    send_text(stream, &Json::Obj(fields).to_string())?;
+    close(stream)
+}
+
+fn send_frame(stream: &mut TcpStream, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+
+    match payload.len() {
+        n if n < 126 => frame.push(n as u8),
+        n if n <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            frame.push(127);
+            frame.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)
+}
+
+fn send_text(stream: &mut TcpStream, text: &str) -> io::Result<()> {
+    send_frame(stream, 0x1, text.as_bytes())
+}
+
+fn close(stream: &mut TcpStream) -> io::Result<()> {
+    send_frame(stream, 0x8, &[])
+}
+
+/// Read frames until a complete text message arrives, of at most [`MAX_MESSAGE`] bytes in
+/// all. Returns `None` if the client closes the connection first.
+fn read_text(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    let mut message = Vec::new();
+
+    loop {
+        let mut header = [0; 2];
+        stream.read_exact(&mut header)?;
+
+        let fin = header[0] & 0x80 != 0;
+        let opcode = header[0] & 0x0f;
+        let masked = header[1] & 0x80 != 0;
+
+        let len = match header[1] & 0x7f {
+            126 => {
+                let mut n = [0; 2];
+                stream.read_exact(&mut n)?;
+                u16::from_be_bytes(n) as usize
+            }
+            127 => {
+                let mut n = [0; 8];
+                stream.read_exact(&mut n)?;
+                u64::from_be_bytes(n) as usize
+            }
+            n => n as usize,
+        };
+
+        if message.len().saturating_add(len) > MAX_MESSAGE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Message too large",
+            ));
+        }
+
+        let mut mask = [0; 4];
+
+        if masked {
+            stream.read_exact(&mut mask)?;
+        }
+
+        let mut payload = vec![0; len];
+        stream.read_exact(&mut payload)?;
+
+        for (i, b) in payload.iter_mut().enumerate() {
+            *b ^= mask[i % 4];
+        }
+
+        match opcode {
+            0x8 => return Ok(None),
+            0x9 => send_frame(stream, 0xa, &payload)?,
+            0x0..=0x2 => message.extend_from_slice(&payload),
+            _ => {}
+        }
+
+        if fin && opcode <= 0x2 {
+            return String::from_utf8(message)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+    }
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 80];
+
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+
+    let mut out = [0; 20];
+
+    for (i, word) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+
+    out
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::new();
+
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
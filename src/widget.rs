@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::{Board, Position, Tile};
+
+/// How a single tile is drawn: the text to print and an optional ANSI SGR colour code.
+#[derive(Debug, Clone)]
+pub struct TileStyle {
+    pub glyph: String,
+    pub color: Option<u8>,
+}
+
+impl TileStyle {
+    pub fn new(glyph: &str, color: Option<u8>) -> Self {
+        Self {
+            glyph: glyph.to_string(),
+            color,
+        }
+    }
+}
+
+/// The look of each kind of tile. Fields are public so callers can start from one of the
+/// built-in themes and override whatever they like.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub rock: TileStyle,
+    pub cherry: TileStyle,
+    pub empty: TileStyle,
+    pub body: TileStyle,
+    pub head: TileStyle,
+    pub custom: TileStyle,
+}
+
+impl Theme {
+    /// The same characters the board is written in.
+    pub fn plain() -> Self {
+        Self {
+            rock: TileStyle::new("r", None),
+            cherry: TileStyle::new("c", None),
+            empty: TileStyle::new(".", None),
+            body: TileStyle::new("s", None),
+            head: TileStyle::new("S", None),
+            custom: TileStyle::new("?", None),
+        }
+    }
+
+    /// The plain characters in ANSI colours: rocks and empty floor grey, cherries red and the
+    /// snake green, its head brighter.
+    pub fn colored() -> Self {
+        Self {
+            rock: TileStyle::new("r", Some(90)),
+            cherry: TileStyle::new("c", Some(31)),
+            empty: TileStyle::new(".", Some(90)),
+            body: TileStyle::new("s", Some(32)),
+            head: TileStyle::new("S", Some(92)),
+            custom: TileStyle::new("?", Some(33)),
+        }
+    }
+
+    pub fn style(&self, tile: Tile) -> &TileStyle {
+        match tile {
+            Tile::Rock => &self.rock,
+            Tile::Cherry => &self.cherry,
+            Tile::Empty => &self.empty,
+            Tile::SnakeBody => &self.body,
+            Tile::SnakeHead => &self.head,
+            Tile::Custom(_) => &self.custom,
+        }
+    }
+}
+
+/// A board view that can be embedded in any terminal output, drawn with a [`Theme`].
+pub struct BoardWidget<'a> {
+    board: &'a Board,
+    theme: &'a Theme,
+}
+
+impl<'a> BoardWidget<'a> {
+    pub fn new(board: &'a Board, theme: &'a Theme) -> Self {
+        Self { board, theme }
+    }
+}
+
+impl fmt::Display for BoardWidget<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.board.tiles.iter().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for &tile in row {
+                match self.theme.style(tile) {
+                    TileStyle {
+                        glyph,
+                        color: Some(c),
+                    } => write!(f, "\x1b[{c}m{glyph}\x1b[0m")?,
+                    TileStyle { glyph, color: None } => write!(f, "{glyph}")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The board with each cell labelled by how many search states had the snake's head there,
+/// shaded from blue (few) to red (the most) when `color` is set.
+pub struct HeatmapWidget<'a> {
+    board: &'a Board,
+    heat: &'a HashMap<Position, usize>,
+    color: bool,
+}
+
+impl<'a> HeatmapWidget<'a> {
+    pub fn new(board: &'a Board, heat: &'a HashMap<Position, usize>, color: bool) -> Self {
+        Self { board, heat, color }
+    }
+}
+
+impl fmt::Display for HeatmapWidget<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const SHADES: [u8; 4] = [44, 46, 43, 41];
+
+        let max = self.heat.values().copied().max().unwrap_or(0).max(1) as f64;
+
+        for (y, row) in self.board.tiles.iter().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for (x, &tile) in row.iter().enumerate() {
+                let count = self.heat.get(&(x, y)).copied().unwrap_or(0);
+
+                let label = match (tile, count) {
+                    (Tile::Rock, _) => "####".to_string(),
+                    (_, 0) => "   .".to_string(),
+                    (_, n) if n < 10_000 => format!("{n:>4}"),
+                    (_, n) if n < 1_000_000 => format!("{:>3}k", n / 1000),
+                    (_, n) => format!("{:>3}M", (n / 1_000_000).min(999)),
+                };
+
+                if self.color && count > 0 {
+                    // Log scale, so that a few hot cells don't wash out everything else
+                    let level = (count as f64).ln_1p() / max.ln_1p();
+                    let shade = SHADES[((level * SHADES.len() as f64) as usize).min(3)];
+
+                    write!(f, "\x1b[{shade};30m{label}\x1b[0m")?;
+                } else {
+                    write!(f, "{label}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The board with the cells two solutions cover marked by which of them covers each: `=`
+/// for both, `a` or `b` for one, and `*` where they part. Rocks and untouched cells are
+/// drawn as in the board file.
+pub struct DiffWidget<'a> {
+    board: &'a Board,
+    a: HashSet<Position>,
+    b: HashSet<Position>,
+    fork: Option<Position>,
+}
+
+impl<'a> DiffWidget<'a> {
+    pub fn new(board: &'a Board, a: &[Position], b: &[Position], fork: Option<Position>) -> Self {
+        Self {
+            board,
+            a: a.iter().copied().collect(),
+            b: b.iter().copied().collect(),
+            fork,
+        }
+    }
+}
+
+impl fmt::Display for DiffWidget<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let theme = Theme::plain();
+
+        for (y, row) in self.board.tiles.iter().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for (x, &tile) in row.iter().enumerate() {
+                let p = (x, y);
+
+                let glyph = match (self.a.contains(&p), self.b.contains(&p)) {
+                    _ if self.fork == Some(p) => "*",
+                    (true, true) => "=",
+                    (true, false) => "a",
+                    (false, true) => "b",
+                    (false, false) => &theme.style(tile).glyph,
+                };
+
+                write!(f, "{glyph}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
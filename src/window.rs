@@ -0,0 +1,238 @@
+use macroquad::miniquad::conf::Conf;
+use macroquad::prelude::*;
+
+use crate::tiles::TileRegistry;
+use crate::{Board, Position, Solution, Tile};
+
+/// The side of a cell, in pixels.
+const CELL: f32 = 64.0;
+/// The strip under the board that says which move is playing.
+const CAPTION: f32 = 40.0;
+
+/// The board partway through a solution: the snake's trail so far, head last, and what
+/// to say about it.
+struct Frame {
+    board: Board,
+    trail: Vec<Position>,
+    caption: String,
+}
+
+/// Open a window that plays `solution` on `board` a cell at a time, taking `delay` seconds
+/// over each. Space plays it again from the start and Escape closes the window, which
+/// this waits for.
+pub fn play(board: &Board, solution: &Solution, tiles: &TileRegistry, delay: f64) {
+    let frames = frames(board, solution, tiles);
+    let glyphs: Vec<(Tile, char)> = frames
+        .iter()
+        .flat_map(|f| f.board.tiles.iter().flatten())
+        .filter(|tile| matches!(tile, Tile::Custom(_)))
+        .filter_map(|&tile| Some((tile, tiles.glyph(tile)?)))
+        .collect();
+    let width = board.tiles.iter().map(Vec::len).max().unwrap_or(0) as f32 * CELL;
+    let height = board.tiles.len() as f32 * CELL;
+
+    let conf = Conf {
+        window_title: "Snake".to_string(),
+        window_width: width.max(320.0) as i32,
+        window_height: (height + CAPTION) as i32,
+        ..Default::default()
+    };
+
+    macroquad::Window::from_config(conf, async move {
+        let mut started = get_time();
+
+        loop {
+            if is_key_pressed(KeyCode::Escape) {
+                break;
+            }
+
+            if is_key_pressed(KeyCode::Space) {
+                started = get_time();
+            }
+
+            let shown = ((get_time() - started) / delay) as usize;
+            let frame = &frames[shown.min(frames.len() - 1)];
+
+            clear_background(Color::from_hex(0x203020));
+            draw_rectangle(0.0, 0.0, width, height, Color::from_hex(0x6a8f4e));
+            draw(frame, &glyphs);
+            draw_text(
+                &frame.caption,
+                12.0,
+                height + 27.0,
+                24.0,
+                Color::from_hex(0xeeeeee),
+            );
+
+            next_frame().await;
+        }
+    });
+}
+
+/// One frame for the start and each cell the head enters after it. A slide shows the
+/// board it started from until it's over, with only the cells entered so far eaten, so a
+/// tile that changes the board does so as the slide ends.
+fn frames(board: &Board, solution: &Solution, tiles: &TileRegistry) -> Vec<Frame> {
+    let mut board = board.place_snake(solution.start);
+    let mut trail = vec![solution.start];
+    let (x, y) = solution.start;
+    let mut frames = vec![Frame {
+        board: board.clone(),
+        trail: trail.clone(),
+        caption: format!("Start at {x}, {y}"),
+    }];
+
+    for (i, &dir) in solution.moves.iter().enumerate() {
+        let mut cells = Vec::new();
+        let Some(next) = board.clone().slide(dir, tiles, Some(&mut cells)) else {
+            break;
+        };
+        let caption = format!("Move {} of {}: {dir:?}", i + 1, solution.moves.len());
+        let mut sliding = board;
+
+        for (j, &cell) in cells.iter().enumerate() {
+            trail.push(cell);
+
+            if j + 1 < cells.len() {
+                sliding.set(cell, Tile::SnakeBody);
+                frames.push(Frame {
+                    board: sliding.clone(),
+                    trail: trail.clone(),
+                    caption: caption.clone(),
+                });
+            }
+        }
+
+        frames.push(Frame {
+            board: next.clone(),
+            trail: trail.clone(),
+            caption,
+        });
+        board = next;
+    }
+
+    if let Some(last) = frames.last_mut() {
+        if last.board.cherry_count() == 0 {
+            last.caption = "Yum! All cherries eaten.".to_string();
+        }
+    }
+
+    frames
+}
+
+fn draw(frame: &Frame, glyphs: &[(Tile, char)]) {
+    for (y, row) in frame.board.tiles.iter().enumerate() {
+        for (x, &tile) in row.iter().enumerate() {
+            let (px, py) = (x as f32 * CELL, y as f32 * CELL);
+
+            match tile {
+                Tile::Rock => rock(px, py),
+                Tile::Cherry => cherry(px, py),
+                Tile::Empty | Tile::SnakeBody | Tile::SnakeHead => {}
+                Tile::Custom(_) => {
+                    let glyph = glyphs.iter().find(|&&(t, _)| t == tile).map(|&(_, g)| g);
+
+                    draw_rectangle(px + 6.0, py + 6.0, 52.0, 52.0, Color::from_hex(0x4d6b3a));
+                    draw_text(
+                        glyph.unwrap_or('?').to_string(),
+                        px + 22.0,
+                        py + 42.0,
+                        36.0,
+                        Color::from_hex(0xeeeeee),
+                    );
+                }
+            }
+        }
+    }
+
+    snake(&frame.board, &frame.trail);
+}
+
+fn rock(x: f32, y: f32) {
+    draw_ellipse(
+        x + 32.0,
+        y + 36.0,
+        26.0,
+        20.0,
+        0.0,
+        Color::from_hex(0x777777),
+    );
+    draw_ellipse(
+        x + 26.0,
+        y + 30.0,
+        10.0,
+        6.0,
+        0.0,
+        Color::from_hex(0x999999),
+    );
+}
+
+fn cherry(x: f32, y: f32) {
+    let stalk = Color::from_hex(0x33bb22);
+
+    draw_line(x + 24.0, y + 38.0, x + 38.0, y + 12.0, 3.0, stalk);
+    draw_line(x + 42.0, y + 40.0, x + 38.0, y + 12.0, 3.0, stalk);
+    draw_circle(x + 24.0, y + 42.0, 10.0, Color::from_hex(0xdd2222));
+    draw_circle(x + 42.0, y + 44.0, 10.0, Color::from_hex(0xdd2222));
+}
+
+/// The snake along `trail`, leaving out cells that are no longer its body, like those
+/// decay has freed, and joining only cells next to each other, not those it wrapped
+/// between.
+fn snake(board: &Board, trail: &[Position]) {
+    let body = Color::from_hex(0x22aa66);
+    let centre = |(x, y): Position| (x as f32 * CELL + 32.0, y as f32 * CELL + 32.0);
+    let on_board =
+        |&(x, y): &Position| matches!(board.tiles[y][x], Tile::SnakeBody | Tile::SnakeHead);
+
+    for pair in trail.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+
+        if on_board(&a) && on_board(&b) && a.0.abs_diff(b.0) <= 1 && a.1.abs_diff(b.1) <= 1 {
+            let ((ax, ay), (bx, by)) = (centre(a), centre(b));
+
+            draw_line(ax, ay, bx, by, 36.0, body);
+        }
+    }
+
+    for &cell in trail.iter().filter(|c| on_board(c)) {
+        let (x, y) = centre(cell);
+
+        draw_circle(x, y, 18.0, body);
+    }
+
+    let Some(&head) = trail.last() else {
+        return;
+    };
+    let (x, y) = centre(head);
+
+    draw_circle(x, y, 24.0, Color::from_hex(0x11dd44));
+
+    for dx in [-9.0, 9.0] {
+        draw_circle(x + dx, y - 6.0, 6.0, WHITE);
+        draw_circle(x + dx, y - 6.0, 3.0, BLACK);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dir;
+
+    #[test]
+    fn slides_play_a_cell_at_a_time() {
+        let tiles = TileRegistry::default();
+        let board = Board::new("c.c\nc..").unwrap();
+        let solution = Solution {
+            start: (0, 1),
+            moves: vec![Dir::Up, Dir::Right],
+        };
+
+        let frames = frames(&board, &solution, &tiles);
+        let trails: Vec<_> = frames.iter().map(|f| f.trail.len()).collect();
+
+        assert_eq!(trails, [1, 2, 3, 4]);
+        assert_eq!(frames[2].board.tiles[0][2], Tile::Cherry);
+        assert_eq!(frames[3].caption, "Yum! All cherries eaten.");
+    }
+}
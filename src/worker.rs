@@ -0,0 +1,204 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::api;
+use crate::json::Json;
+
+/// A job queue shared through a directory, e.g. on a network drive. Boards waiting to be
+/// solved live in `pending/`. A worker claims one by renaming it into `claimed/`, which
+/// only one worker can win, and writes the result to `done/<job>.json`. A claim left behind
+/// by a worker that died goes back to `pending/` when the next worker starts.
+pub struct Queue {
+    root: PathBuf,
+}
+
+impl Queue {
+    pub fn open(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+
+        for dir in ["pending", "claimed", "done"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+
+        Ok(Self { root })
+    }
+
+    /// Claim the next pending job, returning its name and claimed path.
+    fn claim(&self, worker: &str) -> io::Result<Option<(String, PathBuf)>> {
+        let mut pending: Vec<PathBuf> = fs::read_dir(self.root.join("pending"))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.is_file())
+            .collect();
+
+        pending.sort();
+
+        for path in pending {
+            let Some(name) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(str::to_string)
+            else {
+                continue;
+            };
+
+            let claimed = self.root.join("claimed").join(format!("{name}.{worker}"));
+
+            match fs::rename(&path, &claimed) {
+                Ok(()) => {
+                    // When it was claimed, which is how a stale claim is told from one still
+                    // being worked on
+                    fs::File::options()
+                        .write(true)
+                        .open(&claimed)?
+                        .set_modified(SystemTime::now())?;
+
+                    return Ok(Some((name, claimed)));
+                }
+                // Another worker got there first
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Put claims nobody has touched for `stale` back in `pending/`, returning how many.
+    fn recover(&self, stale: Duration) -> io::Result<usize> {
+        let mut recovered = 0;
+
+        for entry in fs::read_dir(self.root.join("claimed"))? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|n| n.to_str());
+            // Claims are named `<job>.<worker>`, and worker names have no dots
+            let Some((job, _)) = name.and_then(|n| n.rsplit_once('.')) else {
+                continue;
+            };
+            let age = fs::metadata(&path)?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+
+            if age > stale {
+                fs::rename(&path, self.root.join("pending").join(job))?;
+                recovered += 1;
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    fn finish(&self, name: &str, claimed: &Path, result: &Json) -> io::Result<()> {
+        let done = self.root.join("done").join(format!("{name}.json"));
+        let partial = done.with_extension("json.tmp");
+
+        fs::write(&partial, format!("{result}\n"))?;
+        fs::rename(&partial, &done)?;
+        fs::remove_file(claimed)
+    }
+}
+
+/// Solve jobs from `queue` until it is empty, or forever when `watch` is set, polling for
+/// new jobs every `poll`. A job that can't be read or solved gets an error result, and one
+/// whose result can't be written stays claimed, so neither stops the worker.
+pub fn run(queue: &Queue, time_limit: Duration, watch: bool, poll: Duration) -> io::Result<()> {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "worker".to_string());
+    let worker = format!("{}-{}", host.replace('.', "_"), process::id());
+
+    // No job takes much longer than the time limit, so a claim that has sat for a good
+    // while past it belongs to a worker that has gone
+    let recovered = queue.recover(time_limit * 2 + Duration::from_secs(60))?;
+
+    if recovered > 0 {
+        eprintln!("{worker}: put {recovered} stale claims back in pending/");
+    }
+
+    loop {
+        let Some((name, claimed)) = queue.claim(&worker)? else {
+            if watch {
+                thread::sleep(poll);
+                continue;
+            }
+
+            return Ok(());
+        };
+
+        eprintln!("{worker}: solving {name}");
+
+        let solved = fs::read_to_string(&claimed)
+            .map_err(|e| format!("Couldn't read the job: {e}"))
+            .and_then(|board| api::solve(&Json::obj([("board", board.into())]), time_limit));
+
+        let result = match solved {
+            Ok(Json::Obj(mut fields)) => {
+                fields.insert(0, ("job".to_string(), name.as_str().into()));
+                fields.insert(1, ("worker".to_string(), worker.as_str().into()));
+
+                Json::Obj(fields)
+            }
+            Ok(other) => other,
+            Err(e) => Json::obj([("job", name.as_str().into()), ("error", e.into())]),
+        };
+
+        if let Err(e) = queue.finish(&name, &claimed, &result) {
+            eprintln!("{worker}: couldn't write the result of {name}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(test: &str) -> Queue {
+        let root = std::env::temp_dir().join(format!("snake-{test}-{}", process::id()));
+
+        let _ = fs::remove_dir_all(&root);
+        Queue::open(root).unwrap()
+    }
+
+    fn done(queue: &Queue, job: &str) -> String {
+        fs::read_to_string(queue.root.join("done").join(format!("{job}.json"))).unwrap()
+    }
+
+    #[test]
+    fn a_bad_job_gets_an_error_and_the_rest_still_run() {
+        let queue = queue("bad-job");
+
+        fs::write(queue.root.join("pending/a"), [0xff, 0xfe]).unwrap();
+        fs::write(queue.root.join("pending/b"), "c.c\nc..").unwrap();
+
+        run(&queue, Duration::from_secs(5), false, Duration::ZERO).unwrap();
+
+        assert!(done(&queue, "a").contains("\"error\""));
+        assert!(done(&queue, "b").contains("\"solution\""));
+        assert_eq!(fs::read_dir(queue.root.join("claimed")).unwrap().count(), 0);
+
+        fs::remove_dir_all(&queue.root).unwrap();
+    }
+
+    #[test]
+    fn stale_claims_go_back_to_pending() {
+        let queue = queue("stale");
+        let claimed = queue.root.join("claimed/a.gone-1");
+
+        fs::write(&claimed, "c.c\nc..").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&claimed)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(600))
+            .unwrap();
+        fs::write(queue.root.join("claimed/b.busy-2"), "cc").unwrap();
+
+        assert_eq!(queue.recover(Duration::from_secs(60)).unwrap(), 1);
+        assert!(queue.root.join("pending/a").exists());
+        assert!(queue.root.join("claimed/b.busy-2").exists());
+
+        fs::remove_dir_all(&queue.root).unwrap();
+    }
+}
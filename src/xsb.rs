@@ -0,0 +1,128 @@
+use crate::{Board, Position, Tile};
+
+const LEVEL_CHARS: &str = "#@+$*.-_ ";
+
+fn is_level_line(line: &str) -> bool {
+    !line.trim().is_empty() && line.chars().all(|c| LEVEL_CHARS.contains(c))
+}
+
+/// Read the first level of an XSB file. Walls, boxes and the space outside the outer wall
+/// become rocks; every other cell becomes a cherry. The all-rock border left by the outer
+/// wall is trimmed away, and the player position (if any) is returned as a suggested start.
+pub fn import(text: &str) -> Result<(Board, Option<Position>), String> {
+    let lines: Vec<&str> = text
+        .lines()
+        .skip_while(|l| !is_level_line(l))
+        .take_while(|l| is_level_line(l))
+        .collect();
+
+    if lines.is_empty() {
+        return Err("No XSB level found".to_string());
+    }
+
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let height = lines.len();
+
+    let mut solid = vec![vec![false; width]; height];
+    let mut player = None;
+
+    for (y, line) in lines.iter().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '#' | '$' | '*' => solid[y][x] = true,
+                '@' | '+' => player = Some((x, y)),
+                _ => {}
+            }
+        }
+    }
+
+    // Anything reachable from the edge without crossing a wall is outside the level
+    let mut outside = vec![vec![false; width]; height];
+    let mut stack: Vec<Position> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| x == 0 || y == 0 || x + 1 == width || y + 1 == height)
+        .collect();
+
+    while let Some((x, y)) = stack.pop() {
+        if solid[y][x] || outside[y][x] || Some((x, y)) == player {
+            continue;
+        }
+
+        outside[y][x] = true;
+
+        if x > 0 {
+            stack.push((x - 1, y));
+        }
+        if y > 0 {
+            stack.push((x, y - 1));
+        }
+        if x + 1 < width {
+            stack.push((x + 1, y));
+        }
+        if y + 1 < height {
+            stack.push((x, y + 1));
+        }
+    }
+
+    let tiles: Vec<Vec<Tile>> = (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    if solid[y][x] || outside[y][x] {
+                        Tile::Rock
+                    } else {
+                        Tile::Cherry
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let open_row = |y: usize| tiles[y].iter().any(|&t| t != Tile::Rock);
+    let open_col = |x: usize| tiles.iter().any(|row| row[x] != Tile::Rock);
+
+    let Some(top) = (0..height).find(|&y| open_row(y)) else {
+        return Err("Level has no open cells".to_string());
+    };
+    let bottom = (0..height).rfind(|&y| open_row(y)).unwrap_or(top);
+    let left = (0..width).find(|&x| open_col(x)).unwrap_or(0);
+    let right = (0..width).rfind(|&x| open_col(x)).unwrap_or(left);
+
+    let tiles = tiles[top..=bottom]
+        .iter()
+        .map(|row| row[left..=right].to_vec())
+        .collect();
+
+    let player = player
+        .filter(|&(x, y)| (left..=right).contains(&x) && (top..=bottom).contains(&y))
+        .map(|(x, y)| (x - left, y - top));
+
+    Ok((Board::from_tiles(tiles), player))
+}
+
+/// Write the board as an XSB level wrapped in an outer wall, with the player at `start`.
+/// Cherries and empty floor are both written as `-` floor, so that editors don't strip them
+/// as trailing space.
+pub fn export(board: &Board, start: Option<Position>) -> String {
+    let width = board.tiles.iter().map(Vec::len).max().unwrap_or(0);
+    let wall = "#".repeat(width + 2);
+
+    let rows = board.tiles.iter().enumerate().map(|(y, row)| {
+        let cells: String = (0..width)
+            .map(|x| match row.get(x) {
+                _ if start == Some((x, y)) => '@',
+                Some(Tile::Cherry | Tile::Empty) => '-',
+                Some(Tile::SnakeHead) => '@',
+                Some(Tile::Rock | Tile::SnakeBody | Tile::Custom(_)) | None => '#',
+            })
+            .collect();
+
+        format!("#{cells}#")
+    });
+
+    std::iter::once(wall.clone())
+        .chain(rows)
+        .chain(std::iter::once(wall))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,33 @@
+use crate::{Position, Tile};
+
+/// The key of `tile` at `(x, y)`. A Zobrist table would hold a random number for each cell
+/// and tile; mixing the cell and tile instead gives numbers just as random without a table
+/// to size for each board.
+pub(crate) fn key((x, y): Position, tile: Tile) -> u64 {
+    let tile = match tile {
+        Tile::Rock => 0,
+        Tile::Cherry => 1,
+        Tile::SnakeBody => 2,
+        Tile::SnakeHead => 3,
+        Tile::Custom(n) => 4 + u64::from(n),
+        Tile::Empty => 4 + 256,
+    };
+
+    // SplitMix64's finaliser
+    let mut z = ((y as u64) << 40 | (x as u64) << 16 | tile).wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The key of a whole grid: every cell's keys combined. Changing one cell's tile changes
+/// the key by the keys of the old and new tiles alone, so boards keep theirs up to date
+/// as the snake moves instead of rehashing every cell.
+pub(crate) fn grid_key(tiles: &[Vec<Tile>]) -> u64 {
+    tiles
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, &t)| key((x, y), t)))
+        .fold(0, |k, cell| k ^ cell)
+}
@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use snake::order::StartOrder;
+use snake::puzzles::PUZZLES;
+use snake::tiles::TileRegistry;
+use snake::{solve_traced, Board, Constraints, Outcome, StateDump};
+
+/// A breadth-first search of `board` with the default settings, and its trace.
+fn search(board: &Board, tiles: &TileRegistry) -> (Outcome, String) {
+    let mut trace = Vec::new();
+    let outcome = solve_traced(
+        board,
+        Constraints::default(),
+        StartOrder::default(),
+        None,
+        tiles,
+        &mut (),
+        Some(&mut trace),
+    );
+
+    (outcome, String::from_utf8(trace).unwrap())
+}
+
+/// What the search found, then each state along the solution as a [`StateDump`].
+fn report(board: &Board, outcome: &Outcome, tiles: &TileRegistry) -> String {
+    let solution = outcome.solution.as_ref().expect("the puzzles are solvable");
+    let mut out = String::new();
+
+    let _ = writeln!(out, "start {},{}", solution.start.0, solution.start.1);
+    let _ = writeln!(
+        out,
+        "moves {}",
+        solution
+            .moves
+            .iter()
+            .map(|d| d.letter())
+            .collect::<String>()
+    );
+    let _ = writeln!(out, "states {}", outcome.states);
+
+    let mut state = board.place_snake(solution.start);
+    let _ = writeln!(
+        out,
+        "{}",
+        StateDump {
+            board: &state,
+            depth: 0
+        }
+    );
+
+    for (i, &dir) in solution.moves.iter().enumerate() {
+        state = state.move_snake(dir, tiles).unwrap();
+
+        let _ = writeln!(
+            out,
+            "{}",
+            StateDump {
+                board: &state,
+                depth: i + 1
+            }
+        );
+    }
+
+    out
+}
+
+#[test]
+fn puzzle_solutions() {
+    let tiles = TileRegistry::default();
+
+    for puzzle in PUZZLES {
+        let board = puzzle.board(&tiles).unwrap();
+        let (outcome, _) = search(&board, &tiles);
+
+        insta::assert_snapshot!(puzzle.name, report(&board, &outcome, &tiles));
+    }
+}
+
+#[test]
+fn first_bite_trace() {
+    let tiles = TileRegistry::default();
+    let board = PUZZLES[0].board(&tiles).unwrap();
+    let (_, trace) = search(&board, &tiles);
+
+    insta::assert_snapshot!(trace);
+}